@@ -0,0 +1,473 @@
+//! The JSON API, for scripts and other programs rather than browsers.
+//!
+//! Everything lives under `/api/v1`, and routes there keep their shape for as long as v1 is
+//! served: new fields may appear, but nothing is renamed or removed. A breaking change gets a new
+//! prefix, and the routes it replaces keep working with a `Deprecation` header and a `Link` to
+//! their successor. Failures are reported as `{ "error": ..., "version": "v1" }` with a matching
+//! status code.
+
+use {
+    super::{
+        anonymized_export,
+        cache::Reports,
+        calendar_month,
+        colors::ColorFilter,
+        db::{Archived, Connection, Cursor, ListFilter},
+        graphql, history, openapi, search, utils, CalendarOpts, Item, ItemPatch, ReportOpts,
+        SortItems, View,
+    },
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::fmt,
+    warp::{http::StatusCode, path, Filter, Rejection, Reply},
+};
+
+pub(crate) const VERSION: &str = "v1";
+
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl warp::reject::Reject for ApiError {}
+
+impl From<ApiError> for Rejection {
+    fn from(e: ApiError) -> Self {
+        warp::reject::custom(e)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl fmt::Display) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn bad_request(message: impl fmt::Display) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    /// Log the real cause, and tell the client only that something went wrong.
+    fn internal(context: &str, e: impl fmt::Display) -> Self {
+        eprintln!("{}: {}", context, e);
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")
+    }
+
+    fn item_lookup(id: usize, e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => {
+                Self::new(StatusCode::NOT_FOUND, format!("No item with ID {}", id))
+            }
+            e => Self::internal(&format!("request for item {}", id), e),
+        }
+    }
+}
+
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct ErrorBody<'a> {
+    error: &'a str,
+    version: &'static str,
+}
+
+/// Mark a reply from an unversioned route as replaced by `successor`.
+pub(crate) fn deprecated(reply: impl Reply, successor: String) -> impl Reply {
+    warp::reply::with_header(
+        warp::reply::with_header(reply, "Deprecation", "true"),
+        "Link",
+        format!("<{}>; rel=\"successor-version\"", successor),
+    )
+}
+
+/// Apply a partial update to an item, returning the item as it now stands.
+pub(crate) async fn patch(
+    id: usize,
+    mut patch: ItemPatch,
+    conn: &Connection,
+) -> Result<Item, ApiError> {
+    if let Some(tags) = &mut patch.tags {
+        *tags = tags
+            .iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+
+    let item = conn
+        .get_item(id)
+        .await
+        .map_err(|e| ApiError::item_lookup(id, e))?;
+    let patched = patch.apply(item);
+    if patched.name.trim().is_empty() {
+        return Err(ApiError::bad_request("Name cannot be empty"));
+    }
+    patched.validate().map_err(ApiError::bad_request)?;
+
+    let context = format!("patch for item {}", id);
+    conn.patch_item(id, &patch)
+        .await
+        .map_err(|e| ApiError::internal(&context, e))?;
+    conn.get_item(id)
+        .await
+        .map_err(|e| ApiError::internal(&context, e))
+}
+
+/// Items returned by one request for the list, unless asked for otherwise.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct ListOpts {
+    view: Option<View>,
+    q: Option<String>,
+    tag: Option<String>,
+    color: Option<String>,
+    unworn_days: Option<u32>,
+    filter: Option<String>,
+    archived: Option<bool>,
+    sort: Option<SortItems>,
+    descending: Option<bool>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+}
+
+impl ListOpts {
+    /// Link to the same list, at `cursor`.
+    fn link(&self, cursor: Option<&str>) -> String {
+        let mut params = Vec::new();
+        if let Some(view) = self.view {
+            params.push(format!("view={}", json!(view).as_str().unwrap_or_default()));
+        }
+        if let Some(q) = &self.q {
+            params.push(format!("q={}", utils::encode_query(q)));
+        }
+        if let Some(tag) = &self.tag {
+            params.push(format!("tag={}", utils::encode_query(tag)));
+        }
+        if let Some(color) = &self.color {
+            params.push(format!("color={}", utils::encode_query(color)));
+        }
+        if let Some(days) = self.unworn_days {
+            params.push(format!("unworn_days={}", days));
+        }
+        if let Some(filter) = &self.filter {
+            params.push(format!("filter={}", utils::encode_query(filter)));
+        }
+        if self.archived == Some(true) {
+            params.push("archived=true".into());
+        }
+        if let Some(sort) = &self.sort {
+            params.push(format!("sort={}", json!(sort).as_str().unwrap_or_default()));
+        }
+        if self.descending == Some(true) {
+            params.push("descending=true".into());
+        }
+        if let Some(limit) = self.limit {
+            params.push(format!("limit={}", limit));
+        }
+        if let Some(cursor) = cursor {
+            params.push(format!("cursor={}", cursor));
+        }
+
+        match params.is_empty() {
+            true => format!("/api/{}/items", VERSION),
+            false => format!("/api/{}/items?{}", VERSION, params.join("&")),
+        }
+    }
+}
+
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct ItemPage {
+    items: Vec<Item>,
+    /// Pass as `cursor` to get the items before these; absent on the first page
+    prev_cursor: Option<String>,
+    /// Pass as `cursor` to get the items after these; absent on the last page
+    next_cursor: Option<String>,
+}
+
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct Status {
+    /// When an item was last edited, worn or washed; absent if nothing has happened yet
+    updated_at: Option<DateTime<Utc>>,
+}
+
+async fn status(conn: Connection) -> Result<impl Reply, Rejection> {
+    let updated_at = conn
+        .last_change()
+        .await
+        .map_err(|e| ApiError::internal("request for status", e))?;
+
+    Ok(warp::reply::json(&Status { updated_at }))
+}
+
+async fn list_items(params: ListOpts, conn: Connection) -> Result<impl Reply, Rejection> {
+    let at = match params.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(c) => Some(Cursor::decode(c).ok_or_else(|| ApiError::bad_request("Invalid cursor"))?),
+        None => None,
+    };
+
+    let filter = ListFilter {
+        view: params.view,
+        search: params.q.clone(),
+        tag: params.tag.clone(),
+        color: params.color.as_deref().and_then(ColorFilter::new),
+        unworn_days: params.unworn_days,
+        query: params
+            .filter
+            .as_deref()
+            .unwrap_or_default()
+            .parse()
+            .map_err(ApiError::bad_request)?,
+        archived: match params.archived {
+            Some(true) => Archived::Only,
+            _ => Archived::Hidden,
+        },
+        deleted: false,
+        wishlist: false,
+    };
+    let (items, prev, next) = conn
+        .get_page(
+            &filter,
+            &params.sort,
+            params.descending != Some(true),
+            at.as_ref(),
+            params.limit.unwrap_or(PAGE_SIZE).clamp(1, 500),
+        )
+        .await
+        .map_err(|e| ApiError::internal("request for item list", e))?;
+    let total = conn
+        .count_items(&filter)
+        .await
+        .map_err(|e| ApiError::internal("request for item list", e))?;
+
+    let prev_cursor = prev.map(|c| c.encode());
+    let next_cursor = next.map(|c| c.encode());
+    let mut links = vec![format!("<{}>; rel=\"first\"", params.link(None))];
+    if let Some(cursor) = &prev_cursor {
+        links.push(format!("<{}>; rel=\"prev\"", params.link(Some(cursor))));
+    }
+    if let Some(cursor) = &next_cursor {
+        links.push(format!("<{}>; rel=\"next\"", params.link(Some(cursor))));
+    }
+    links.push(format!(
+        "<{}>; rel=\"last\"",
+        params.link(Some(&Cursor::last().encode()))
+    ));
+
+    let reply = warp::reply::json(&ItemPage {
+        items,
+        prev_cursor,
+        next_cursor,
+    });
+    Ok(warp::reply::with_header(
+        warp::reply::with_header(reply, "X-Total-Count", total.to_string()),
+        "Link",
+        links.join(", "),
+    ))
+}
+
+async fn get_item(id: usize, conn: Connection) -> Result<impl Reply, Rejection> {
+    let item = conn
+        .get_item(id)
+        .await
+        .map_err(|e| ApiError::item_lookup(id, e))?;
+
+    Ok(warp::reply::json(&item))
+}
+
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct History {
+    /// Wears and washes still on record, oldest first
+    events: Vec<history::Event>,
+    /// Absent until the item has been washed at least twice
+    typical: Option<history::Typical>,
+}
+
+async fn get_history(id: usize, conn: Connection) -> Result<impl Reply, Rejection> {
+    // look the item up first so a missing one is a 404 rather than an empty history
+    conn.get_item(id)
+        .await
+        .map_err(|e| ApiError::item_lookup(id, e))?;
+    let events = conn
+        .get_item_history(id)
+        .await
+        .map_err(|e| ApiError::internal(&format!("request for history of item {}", id), e))?;
+
+    Ok(warp::reply::json(&History {
+        typical: history::typical(&events),
+        events,
+    }))
+}
+
+async fn get_calendar(opts: CalendarOpts, conn: Connection) -> Result<impl Reply, Rejection> {
+    let month = calendar_month(&conn, &opts)
+        .await
+        .map_err(|e| ApiError::internal("request for calendar", e))?;
+
+    Ok(warp::reply::json(&month))
+}
+
+async fn patch_item(id: usize, body: ItemPatch, conn: Connection) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&patch(id, body, &conn).await?))
+}
+
+async fn export(
+    opts: ReportOpts,
+    conn: Connection,
+    reports: Reports,
+) -> Result<impl Reply, Rejection> {
+    let export = anonymized_export(&conn, &reports, opts)
+        .await
+        .map_err(|e| ApiError::internal("request for anonymized export", e))?;
+
+    Ok(warp::reply::json(&export))
+}
+
+/// Turn any rejection from inside the API into a JSON error body.
+async fn recover(err: Rejection) -> Result<impl Reply, Rejection> {
+    let (status, message) = if let Some(e) = err.find::<ApiError>() {
+        (e.status, e.message.clone())
+    } else if let Some(e) = err.find::<warp::body::BodyDeserializeError>() {
+        (StatusCode::BAD_REQUEST, e.to_string())
+    } else if let Some(e) = err.find::<warp::reject::InvalidQuery>() {
+        (StatusCode::BAD_REQUEST, e.to_string())
+    } else if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Request body is too large".into(),
+        )
+    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed".into())
+    } else {
+        (StatusCode::NOT_FOUND, "No such route".into())
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&ErrorBody {
+            error: &message,
+            version: VERSION,
+        }),
+        status,
+    ))
+}
+
+/// Every route under `/api/v1`.
+pub(crate) fn routes(
+    db: Connection,
+    schema: graphql::WardrobeSchema,
+    reports: Reports,
+) -> warp::filters::BoxedFilter<(impl Reply,)> {
+    let with_state = warp::any().map(move || db.clone());
+    let with_schema = warp::any().map(move || schema.clone());
+    let with_reports = warp::any().map(move || reports.clone());
+
+    let list = warp::get()
+        .and(path("items"))
+        .and(path::end())
+        .and(warp::query::query())
+        .and(with_state.clone())
+        .and_then(list_items);
+
+    // the same listing, but insisting on something to search for
+    let search = warp::get()
+        .and(path("items"))
+        .and(path("search"))
+        .and(path::end())
+        .and(warp::query::query())
+        .and_then(|params: ListOpts| async move {
+            match params.q.as_deref().map(search::patterns) {
+                Some(p) if !p.is_empty() => Ok(params),
+                _ => Err(warp::reject::custom(ApiError::bad_request(
+                    "Nothing to search for; pass some words as `q`",
+                ))),
+            }
+        })
+        .and(with_state.clone())
+        .and_then(list_items);
+
+    let item = warp::get()
+        .and(path("items"))
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(get_item);
+
+    let history = warp::get()
+        .and(path("items"))
+        .and(path::param())
+        .and(path("history"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(get_history);
+
+    let patch = warp::patch()
+        .and(path("items"))
+        .and(path::param())
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::json())
+        .and(with_state.clone())
+        .and_then(patch_item);
+
+    let status = warp::get()
+        .and(path("status"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(status);
+
+    let calendar = warp::get()
+        .and(path("calendar"))
+        .and(path::end())
+        .and(warp::query::<CalendarOpts>())
+        .and(with_state.clone())
+        .and_then(get_calendar);
+
+    let graphql = warp::post()
+        .and(path("graphql"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::json())
+        .and(with_schema)
+        .and_then(graphql::execute);
+
+    let export = warp::get()
+        .and(path("export"))
+        .and(path("anonymized.json"))
+        .and(path::end())
+        .and(warp::query::<ReportOpts>())
+        .and(with_state)
+        .and(with_reports)
+        .and_then(export);
+
+    let spec = openapi::document().to_string();
+    let openapi = warp::get()
+        .and(path("openapi.json"))
+        .and(path::end())
+        .map(move || warp::reply::with_header(spec.clone(), "Content-Type", "application/json"));
+
+    path("api")
+        .and(path(VERSION))
+        .and(
+            list.or(search)
+                .or(item)
+                .or(history)
+                .or(patch)
+                .or(status)
+                .or(calendar)
+                .or(graphql)
+                .or(export)
+                .or(openapi)
+                .recover(recover),
+        )
+        .map(|reply| warp::reply::with_header(reply, "API-Version", VERSION))
+        .boxed()
+}
@@ -0,0 +1,262 @@
+use {
+    super::{
+        db::{Attachment, Connection},
+        media::Media,
+        thumbnails,
+    },
+    serde::Deserialize,
+    tokio::stream::StreamExt,
+    warp::{
+        http::{Response, StatusCode},
+        multipart::FormData,
+        Buf,
+    },
+};
+
+fn fail(item_id: usize, e: impl std::fmt::Display) -> warp::Rejection {
+    eprintln!("attachments for item {}: {}", item_id, e);
+    warp::reject::not_found()
+}
+
+/// A file from an upload form.
+pub(crate) struct Upload {
+    pub(crate) field: String,
+    pub(crate) file_name: String,
+    pub(crate) content_type: String,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Read a multipart form into its text fields and its files. Browsers send an empty part when no
+/// file was picked, so those are left out.
+pub(crate) async fn read_form(
+    mut form: FormData,
+) -> Result<(Vec<(String, String)>, Vec<Upload>), warp::Error> {
+    let (mut fields, mut uploads) = (Vec::new(), Vec::new());
+
+    while let Some(part) = form.next().await {
+        let mut part = part?;
+        let field = part.name().to_string();
+        let file_name = part.filename().map(ToString::to_string);
+        let content_type = part
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        let mut data = Vec::new();
+        while let Some(chunk) = part.data().await {
+            data.extend_from_slice(chunk?.bytes());
+        }
+
+        match file_name {
+            Some(_) if data.is_empty() => (),
+            Some(file_name) => uploads.push(Upload {
+                field,
+                file_name,
+                content_type,
+                data,
+            }),
+            None => fields.push((field, String::from_utf8_lossy(&data).into_owned())),
+        }
+    }
+
+    Ok((fields, uploads))
+}
+
+/// Store one uploaded file against the given item.
+pub(crate) async fn store(
+    item_id: usize,
+    upload: &Upload,
+    conn: &Connection,
+    media: &Media,
+) -> anyhow::Result<()> {
+    let extension = Media::extension_for(&upload.content_type).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} has unsupported type {}",
+            upload.file_name,
+            upload.content_type
+        )
+    })?;
+
+    let stored_as = media.save(item_id, extension, &upload.data).await?;
+    if upload.content_type.starts_with("image/") {
+        // the original is still worth keeping if it can't be scaled
+        if let Err(e) =
+            thumbnails::create(media, &stored_as, &upload.content_type, &upload.data).await
+        {
+            eprintln!("Could not make thumbnails of {}: {}", upload.file_name, e);
+        }
+    }
+    conn.add_attachment(
+        item_id,
+        &upload.file_name,
+        &upload.content_type,
+        upload.data.len(),
+        &stored_as,
+        &Media::digest(&upload.data),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Store every file in an upload form against the given item. Returns the item's ID.
+pub(crate) async fn upload(
+    item_id: usize,
+    form: FormData,
+    conn: Connection,
+    media: Media,
+) -> Result<usize, warp::Rejection> {
+    // make sure the item exists before writing anything to disk
+    conn.get_item(item_id).await.map_err(|e| fail(item_id, e))?;
+
+    let (_, uploads) = read_form(form).await.map_err(|e| fail(item_id, e))?;
+    for upload in uploads.iter().filter(|u| u.field == "file") {
+        store(item_id, upload, &conn, &media)
+            .await
+            .map_err(|e| fail(item_id, e))?;
+    }
+
+    Ok(item_id)
+}
+
+/// Send a stored file back as it was uploaded.
+fn respond(
+    item_id: usize,
+    attachment: Attachment,
+    data: Vec<u8>,
+) -> Result<Response<Vec<u8>>, warp::Rejection> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", attachment.content_type)
+        .header("X-Content-Type-Options", "nosniff")
+        .header(
+            "Content-Disposition",
+            format!(
+                "inline; filename=\"{}\"",
+                attachment.file_name.replace('"', "")
+            ),
+        )
+        .body(data)
+        .map_err(|e| fail(item_id, e))
+}
+
+pub(crate) async fn download(
+    item_id: usize,
+    attachment_id: usize,
+    conn: Connection,
+    media: Media,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let attachment = conn
+        .get_attachment(item_id, attachment_id)
+        .await
+        .map_err(|e| fail(item_id, e))?;
+    let data = media
+        .read(&attachment.stored_as)
+        .await
+        .map_err(|e| fail(item_id, e))?;
+
+    respond(item_id, attachment, data)
+}
+
+/// Which copy of a photo to send.
+#[derive(Deserialize)]
+pub(crate) struct PhotoOpts {
+    size: Option<thumbnails::Size>,
+}
+
+/// The item's photo, which is its most recently uploaded image, at full size or as a thumbnail.
+pub(crate) async fn photo(
+    item_id: usize,
+    opts: PhotoOpts,
+    conn: Connection,
+    media: Media,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let attachment = conn
+        .get_photo(item_id)
+        .await
+        .map_err(|e| fail(item_id, e))?;
+
+    match opts.size {
+        Some(size) => {
+            let data = thumbnails::fetch(&media, &attachment, size)
+                .await
+                .map_err(|e| fail(item_id, e))?;
+            let content_type = thumbnails::content_type(&attachment.content_type).to_string();
+            respond(
+                item_id,
+                Attachment {
+                    content_type,
+                    ..attachment
+                },
+                data,
+            )
+        }
+        None => {
+            let data = media
+                .read(&attachment.stored_as)
+                .await
+                .map_err(|e| fail(item_id, e))?;
+            respond(item_id, attachment, data)
+        }
+    }
+}
+
+/// Delete an attachment and its file. Returns the item's ID.
+pub(crate) async fn remove(
+    item_id: usize,
+    attachment_id: usize,
+    conn: Connection,
+    media: Media,
+) -> Result<usize, warp::Rejection> {
+    let attachment = conn
+        .get_attachment(item_id, attachment_id)
+        .await
+        .map_err(|e| fail(item_id, e))?;
+
+    conn.delete_attachment(attachment.id)
+        .await
+        .map_err(|e| fail(item_id, e))?;
+    media.remove(&attachment.stored_as).await;
+    thumbnails::remove(&media, &attachment).await;
+
+    Ok(item_id)
+}
+
+/// Delete the files for every attachment on an item that's about to be removed.
+pub(crate) async fn remove_all(item_id: usize, conn: &Connection, media: &Media) {
+    match conn.get_attachments(item_id).await {
+        Ok(attachments) => remove_files(&attachments, media).await,
+        Err(e) => eprintln!("attachments for item {}: {}", item_id, e),
+    }
+}
+
+/// Delete the files of attachments whose records are already gone, or about to be.
+pub(crate) async fn remove_files(attachments: &[Attachment], media: &Media) {
+    for attachment in attachments {
+        media.remove(&attachment.stored_as).await;
+        thumbnails::remove(media, attachment).await;
+    }
+}
+
+/// Hash the files of any attachments uploaded before digests were recorded, so older photos are
+/// included in duplicate detection.
+pub(crate) async fn backfill_digests(conn: Connection, media: Media) {
+    let attachments = match conn.get_undigested_attachments().await {
+        Ok(a) => a,
+        Err(e) => return eprintln!("Could not look up attachments to hash: {}", e),
+    };
+
+    for attachment in attachments {
+        let digest = match media.read(&attachment.stored_as).await {
+            Ok(data) => Media::digest(&data),
+            Err(e) => {
+                eprintln!("Could not hash {}: {}", attachment.stored_as, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = conn.set_attachment_digest(attachment.id, &digest).await {
+            eprintln!("Could not store digest for {}: {}", attachment.stored_as, e);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! The weekly wear-balance report: how often each category was worn against how often it's meant
+//! to be, so the neglected corners of the wardrobe get noticed. An item's category is its first
+//! tag.
+
+/// One category's wears over the week, next to its goal.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Line {
+    pub(crate) category: String,
+    pub(crate) wears: u32,
+    pub(crate) goal: u32,
+}
+
+impl Line {
+    fn shortfall(&self) -> u32 {
+        self.goal.saturating_sub(self.wears)
+    }
+}
+
+/// Match wears per category up with the goals, furthest behind first. Categories without a goal
+/// are left out.
+pub(crate) fn compare(goals: &[(String, u32)], wears: &[(String, u32)]) -> Vec<Line> {
+    let mut lines = goals
+        .iter()
+        .map(|(category, goal)| Line {
+            category: category.clone(),
+            wears: wears
+                .iter()
+                .find(|(c, _)| c == category)
+                .map_or(0, |(_, n)| *n),
+            goal: *goal,
+        })
+        .collect::<Vec<_>>();
+
+    lines.sort_by(|a, b| {
+        b.shortfall()
+            .cmp(&a.shortfall())
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    lines
+}
+
+/// Subject and body of the notification, if there are any goals to report on.
+pub(crate) fn message(lines: &[Line]) -> Option<(String, String)> {
+    if lines.is_empty() {
+        return None;
+    }
+
+    let behind = lines.iter().filter(|l| l.shortfall() > 0).count();
+    let subject = match behind {
+        0 => "Wear balance: every category on track".to_string(),
+        n => format!(
+            "Wear balance: {} categor{} neglected",
+            n,
+            if n == 1 { "y" } else { "ies" }
+        ),
+    };
+    let body = lines
+        .iter()
+        .map(|l| {
+            format!(
+                "{}: {} of {} wear(s){}",
+                l.category,
+                l.wears,
+                l.goal,
+                if l.shortfall() > 0 {
+                    " - neglected"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some((subject, body))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn neglected_first() {
+        let goals = [
+            ("jeans".to_string(), 2),
+            ("shirts".to_string(), 5),
+            ("shoes".to_string(), 3),
+        ];
+        let wears = [
+            ("jeans".to_string(), 4),
+            ("shirts".to_string(), 1),
+            ("hats".to_string(), 7),
+        ];
+
+        let lines = compare(&goals, &wears);
+        assert_eq!(
+            lines
+                .iter()
+                .map(|l| (l.category.as_str(), l.wears))
+                .collect::<Vec<_>>(),
+            vec![("shirts", 1), ("shoes", 0), ("jeans", 4)]
+        );
+
+        let (subject, body) = message(&lines).unwrap();
+        assert_eq!(subject, "Wear balance: 2 categories neglected");
+        assert!(body.starts_with("shirts: 1 of 5 wear(s) - neglected\n"));
+        assert!(message(&[]).is_none());
+    }
+}
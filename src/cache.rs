@@ -0,0 +1,47 @@
+//! Results of the heavier reports, kept for a few minutes. Only one request computes a given
+//! report at a time; anyone else asking for it meanwhile waits and then gets the same result.
+
+use {
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        future::Future,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// How long a computed report is served before it is run again.
+const MAX_AGE: Duration = Duration::from_secs(3 * 60);
+
+type Slot = Arc<tokio::sync::Mutex<Option<(Instant, Value)>>>;
+
+#[derive(Clone, Default)]
+pub(crate) struct Reports(Arc<Mutex<HashMap<&'static str, Slot>>>);
+
+impl Reports {
+    /// Return the cached `name` report if it is fresh enough, otherwise run it and keep the result.
+    pub(crate) async fn get<E>(
+        &self,
+        name: &'static str,
+        run: impl Future<Output = Result<Value, E>>,
+    ) -> Result<Value, E> {
+        let slot = self.0.lock().unwrap().entry(name).or_default().clone();
+        let mut cached = slot.lock().await;
+
+        if let Some((at, value)) = &*cached {
+            if at.elapsed() < MAX_AGE {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = run.await?;
+        *cached = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+
+    /// Forget every cached report, e.g. because the settings they were formatted with changed.
+    pub(crate) fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
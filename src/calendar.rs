@@ -0,0 +1,224 @@
+//! A month of wear history laid out as a calendar, weeks starting on Monday, with what was worn
+//! on each day and what's planned for it. Wears already rolled up into monthly totals have no day
+//! any more, so they don't show up here.
+
+use {
+    chrono::{Datelike, Duration, NaiveDate},
+    schemars::JsonSchema,
+    serde::{Deserialize, Deserializer, Serialize},
+};
+
+#[derive(Debug, JsonSchema, PartialEq, Serialize)]
+pub(crate) struct Worn {
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    pub(crate) color: String,
+    /// How many times it was worn that day
+    pub(crate) wears: u32,
+}
+
+/// An item or outfit to be worn on a day. Only one of the ids is set.
+#[derive(Debug, JsonSchema, PartialEq, Serialize)]
+pub(crate) struct Planned {
+    pub(crate) id: usize,
+    pub(crate) item_id: Option<usize>,
+    pub(crate) outfit_id: Option<usize>,
+    pub(crate) name: String,
+    pub(crate) note: String,
+}
+
+#[derive(Debug, JsonSchema, Serialize)]
+pub(crate) struct Day {
+    pub(crate) date: NaiveDate,
+    /// Day of the month
+    pub(crate) day: u32,
+    pub(crate) today: bool,
+    /// After today, so nothing planned can be worn yet
+    pub(crate) upcoming: bool,
+    pub(crate) items: Vec<Worn>,
+    pub(crate) planned: Vec<Planned>,
+}
+
+#[derive(Debug, JsonSchema, Serialize)]
+pub(crate) struct Month {
+    /// The month shown, as `YYYY-MM`
+    pub(crate) month: String,
+    pub(crate) label: String,
+    pub(crate) previous: String,
+    pub(crate) next: String,
+    /// Monday to Sunday; days from the months either side are left empty
+    pub(crate) weeks: Vec<Vec<Option<Day>>>,
+}
+
+/// The first of the month given as `YYYY-MM`. Months at the very ends of the dates that can be
+/// represented are refused, since the calendar links to the months either side.
+pub(crate) fn parse_month(month: &str) -> anyhow::Result<NaiveDate> {
+    let first = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Not a month (expected YYYY-MM): {}", month))?;
+    if next_month(first).is_none() || previous_month(first).is_none() {
+        anyhow::bail!("Month out of range: {}", month);
+    }
+    Ok(first)
+}
+
+/// Read an optional month in a query string, as `parse_month` does; empty means none.
+pub(crate) fn deserialize_month<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<NaiveDate>, D::Error> {
+    match Option::<String>::deserialize(d)? {
+        Some(month) if !month.trim().is_empty() => parse_month(&month)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+/// The first and last day of the month that `first` starts.
+pub(crate) fn bounds(first: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    Some((first, next_month(first)? - Duration::days(1)))
+}
+
+fn next_month(first: NaiveDate) -> Option<NaiveDate> {
+    match first.month() {
+        12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m + 1, 1),
+    }
+}
+
+fn previous_month(first: NaiveDate) -> Option<NaiveDate> {
+    match first.month() {
+        1 => NaiveDate::from_ymd_opt(first.year() - 1, 12, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m - 1, 1),
+    }
+}
+
+/// Everything in `entries` up to and including `date`, which are taken off the front.
+fn take_until<T>(
+    entries: &mut std::iter::Peekable<impl Iterator<Item = (NaiveDate, T)>>,
+    date: NaiveDate,
+) -> Vec<T> {
+    let mut taken = Vec::new();
+    while let Some((_, entry)) = entries.next_if(|(d, _)| *d <= date) {
+        taken.push(entry);
+    }
+    taken
+}
+
+/// Lay out the month starting on `first`, given what was worn and what's planned on which day,
+/// each in the order they should be listed. Nothing for months `parse_month` would refuse.
+pub(crate) fn month(
+    first: NaiveDate,
+    today: NaiveDate,
+    worn: Vec<(NaiveDate, Worn)>,
+    planned: Vec<(NaiveDate, Planned)>,
+) -> Option<Month> {
+    let (_, last) = bounds(first)?;
+    let mut worn = worn.into_iter().peekable();
+    let mut planned = planned.into_iter().peekable();
+
+    let mut weeks = vec![Vec::new()];
+    for _ in 0..first.weekday().num_days_from_monday() {
+        weeks[0].push(None);
+    }
+
+    let mut date = first;
+    while date <= last {
+        if weeks.last().is_some_and(|w| w.len() == 7) {
+            weeks.push(Vec::new());
+        }
+        if let Some(week) = weeks.last_mut() {
+            week.push(Some(Day {
+                date,
+                day: date.day(),
+                today: date == today,
+                upcoming: date > today,
+                items: take_until(&mut worn, date),
+                planned: take_until(&mut planned, date),
+            }));
+        }
+        date += Duration::days(1);
+    }
+
+    if let Some(week) = weeks.last_mut() {
+        week.resize_with(7, || None);
+    }
+
+    Some(Month {
+        month: first.format("%Y-%m").to_string(),
+        label: first.format("%B %Y").to_string(),
+        previous: previous_month(first)?.format("%Y-%m").to_string(),
+        next: next_month(first)?.format("%Y-%m").to_string(),
+        weeks,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn worn(id: usize) -> Worn {
+        Worn {
+            id,
+            name: format!("item {}", id),
+            color: "#000000".into(),
+            wears: 1,
+        }
+    }
+
+    #[test]
+    fn month_grid() {
+        let first = parse_month("2026-10").unwrap();
+        let day = |d| NaiveDate::from_ymd(2026, 10, d);
+        let plan = Planned {
+            id: 1,
+            item_id: None,
+            outfit_id: Some(2),
+            name: "Office".into(),
+            note: String::new(),
+        };
+        let month = month(
+            first,
+            day(17),
+            vec![(day(1), worn(1)), (day(1), worn(2)), (day(17), worn(1))],
+            vec![(day(20), plan)],
+        )
+        .unwrap();
+
+        assert_eq!(month.label, "October 2026");
+        assert_eq!(
+            (month.previous.as_str(), month.next.as_str()),
+            ("2026-09", "2026-11")
+        );
+        assert_eq!(month.weeks.len(), 5);
+        assert!(month.weeks.iter().all(|w| w.len() == 7));
+
+        // the 1st is a Thursday
+        let thursday = month.weeks[0][3].as_ref().unwrap();
+        assert!(month.weeks[0][2].is_none());
+        assert_eq!(thursday.date, day(1));
+        assert_eq!(thursday.items, vec![worn(1), worn(2)]);
+
+        let saturday = month.weeks[2][5].as_ref().unwrap();
+        assert!(saturday.today && !saturday.upcoming);
+        assert_eq!(saturday.items, vec![worn(1)]);
+        assert!(saturday.planned.is_empty());
+
+        let tuesday = month.weeks[3][1].as_ref().unwrap();
+        assert!(tuesday.upcoming);
+        assert_eq!(tuesday.planned[0].outfit_id, Some(2));
+        assert!(month.weeks[4][6].is_none());
+
+        assert_eq!(
+            bounds(parse_month("2026-12").unwrap()),
+            Some((
+                NaiveDate::from_ymd(2026, 12, 1),
+                NaiveDate::from_ymd(2026, 12, 31)
+            ))
+        );
+        assert!(parse_month("October").is_err());
+        // chrono's first and last representable months
+        assert!(parse_month("-262144-01").is_err());
+        assert!(parse_month("+262143-12").is_err());
+        assert!(parse_month("-262144-02").is_ok());
+    }
+}
@@ -0,0 +1,229 @@
+//! Laundry care instructions, stored per item as one short code for each of the five standard
+//! categories (washing, bleaching, drying, ironing and professional cleaning), and rendered as the
+//! familiar care label symbols.
+
+use {super::Item, serde::Serialize, serde_json::json};
+
+/// The codes allowed in each category, with a human-readable description.
+const WASH: &[(&str, &str)] = &[
+    ("30", "Machine wash, 30°C"),
+    ("40", "Machine wash, 40°C"),
+    ("60", "Machine wash, 60°C"),
+    ("95", "Machine wash, 95°C"),
+    ("hand", "Hand wash"),
+    ("no", "Do not wash"),
+];
+
+const BLEACH: &[(&str, &str)] = &[
+    ("any", "Any bleach"),
+    ("non-chlorine", "Non-chlorine bleach only"),
+    ("no", "Do not bleach"),
+];
+
+const DRY: &[(&str, &str)] = &[
+    ("tumble", "Tumble dry, normal heat"),
+    ("tumble-low", "Tumble dry, low heat"),
+    ("no-tumble", "Do not tumble dry"),
+    ("line", "Line dry"),
+    ("flat", "Dry flat"),
+];
+
+const IRON: &[(&str, &str)] = &[
+    ("low", "Iron, low heat"),
+    ("medium", "Iron, medium heat"),
+    ("high", "Iron, high heat"),
+    ("no", "Do not iron"),
+];
+
+const DRY_CLEAN: &[(&str, &str)] = &[
+    ("any", "Dry clean, any solvent"),
+    ("p", "Dry clean, PCE only"),
+    ("f", "Dry clean, hydrocarbon only"),
+    ("no", "Do not dry clean"),
+];
+
+type Codes = &'static [(&'static str, &'static str)];
+
+struct Category<'a> {
+    kind: &'static str,
+    title: &'static str,
+    codes: Codes,
+    value: &'a str,
+}
+
+fn categories(item: &Item) -> [Category<'_>; 5] {
+    let category = |kind, title, codes, value| Category {
+        kind,
+        title,
+        codes,
+        value,
+    };
+
+    [
+        category("wash", "Washing", WASH, &item.care_wash),
+        category("bleach", "Bleaching", BLEACH, &item.care_bleach),
+        category("dry", "Drying", DRY, &item.care_dry),
+        category("iron", "Ironing", IRON, &item.care_iron),
+        category("dry-clean", "Dry cleaning", DRY_CLEAN, &item.care_dry_clean),
+    ]
+}
+
+/// Make sure every care code on an item is one we know how to draw.
+pub(crate) fn validate(item: &Item) -> anyhow::Result<()> {
+    for cat in categories(item).iter() {
+        if !cat.value.is_empty() && !cat.codes.iter().any(|(c, _)| *c == cat.value) {
+            anyhow::bail!(
+                "Unknown {} instruction: {}",
+                cat.title.to_lowercase(),
+                cat.value
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything the `care` partial needs to draw one symbol.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct Symbol {
+    kind: &'static str,
+    label: &'static str,
+    text: Option<&'static str>,
+    dots: Vec<(u8, u8)>,
+    crossed: bool,
+    circle: bool,
+    lines: bool,
+    variant: &'static str,
+}
+
+fn symbol(kind: &'static str, code: &'static str, label: &'static str) -> Symbol {
+    let base = Symbol {
+        kind,
+        label,
+        crossed: code == "no" || code == "no-tumble",
+        ..Symbol::default()
+    };
+
+    match (kind, code) {
+        ("wash", "hand") => Symbol {
+            text: Some("✋"),
+            ..base
+        },
+        ("wash", "no") => base,
+        ("wash", temperature) => Symbol {
+            text: Some(temperature),
+            ..base
+        },
+        ("bleach", "non-chlorine") => Symbol {
+            lines: true,
+            ..base
+        },
+        ("dry", "tumble") => Symbol {
+            circle: true,
+            dots: vec![(16, 20), (24, 20)],
+            ..base
+        },
+        ("dry", "tumble-low") => Symbol {
+            circle: true,
+            dots: vec![(20, 20)],
+            ..base
+        },
+        ("dry", "no-tumble") => Symbol {
+            circle: true,
+            ..base
+        },
+        ("dry", variant) => Symbol { variant, ..base },
+        ("iron", "low") => Symbol {
+            dots: vec![(20, 24)],
+            ..base
+        },
+        ("iron", "medium") => Symbol {
+            dots: vec![(16, 24), (24, 24)],
+            ..base
+        },
+        ("iron", "high") => Symbol {
+            dots: vec![(13, 24), (20, 24), (27, 24)],
+            ..base
+        },
+        ("dry-clean", "any") => Symbol {
+            text: Some("A"),
+            ..base
+        },
+        ("dry-clean", "p") => Symbol {
+            text: Some("P"),
+            ..base
+        },
+        ("dry-clean", "f") => Symbol {
+            text: Some("F"),
+            ..base
+        },
+        _ => base,
+    }
+}
+
+/// The symbols for every care instruction set on an item, in label order.
+pub(crate) fn symbols(item: &Item) -> Vec<Symbol> {
+    categories(item)
+        .iter()
+        .filter_map(|cat| {
+            cat.codes
+                .iter()
+                .find(|(c, _)| *c == cat.value)
+                .map(|(code, label)| symbol(cat.kind, code, label))
+        })
+        .collect()
+}
+
+/// The choices for each care category, for building the item form.
+pub(crate) fn form_options(item: &Item) -> serde_json::Value {
+    categories(item)
+        .iter()
+        .map(|cat| {
+            json!({
+                "field": format!("care_{}", cat.kind.replace('-', "_")),
+                "title": cat.title,
+                "options": cat.codes
+                    .iter()
+                    .map(|(code, label)| json!({
+                        "code": code,
+                        "label": label,
+                        "selected": *code == cat.value,
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_set_categories_are_drawn() {
+        let item = Item {
+            care_wash: "40".into(),
+            care_iron: "no".into(),
+            ..Item::default()
+        };
+
+        let drawn = symbols(&item);
+
+        assert_eq!(drawn.len(), 2);
+        assert_eq!(drawn[0].text, Some("40"));
+        assert!(!drawn[0].crossed);
+        assert_eq!(drawn[1].kind, "iron");
+        assert!(drawn[1].crossed);
+    }
+
+    #[test]
+    fn unknown_codes_rejected() {
+        let item = Item {
+            care_dry: "in the oven".into(),
+            ..Item::default()
+        };
+
+        assert!(validate(&item).is_err());
+        assert!(validate(&Item::default()).is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+//! How the wardrobe changed between two days: what was added and retired, how many items there
+//! were at each end, and what got worn in between. Built from when items were created and
+//! archived and from the wear history, so it covers any period since those were recorded.
+
+use {
+    chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc},
+    serde::Serialize,
+};
+
+/// The days a comparison runs from and to, both included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Period {
+    pub(crate) from: NaiveDate,
+    pub(crate) to: NaiveDate,
+}
+
+impl Period {
+    /// The given days, in whichever order, defaulting to the start of the year and today.
+    pub(crate) fn new(from: Option<NaiveDate>, to: Option<NaiveDate>, today: NaiveDate) -> Self {
+        let from = from.unwrap_or_else(|| NaiveDate::from_ymd(today.year(), 1, 1));
+        let to = to.unwrap_or(today);
+
+        if from <= to {
+            Self { from, to }
+        } else {
+            Self { from: to, to: from }
+        }
+    }
+
+    /// The start of the first day and the end of the last, by the local clock.
+    pub(crate) fn bounds(self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = |day: NaiveDate| {
+            Local
+                .from_local_datetime(&day.and_hms(0, 0, 0))
+                .earliest()
+                .map_or_else(
+                    || Utc.from_utc_datetime(&day.and_hms(0, 0, 0)),
+                    |t| t.with_timezone(&Utc),
+                )
+        };
+        (start(self.from), start(self.to + Duration::days(1)))
+    }
+}
+
+/// An item mentioned in a comparison.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Entry {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    /// Wears within the period, for items that were worn
+    pub(crate) wears: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Changes {
+    pub(crate) items_before: u32,
+    pub(crate) items_after: u32,
+    pub(crate) added: Vec<Entry>,
+    pub(crate) retired: Vec<Entry>,
+    pub(crate) worn: Vec<Entry>,
+    pub(crate) total_wears: u32,
+    /// Items there at the end of the period that weren't worn during it
+    pub(crate) unworn: u32,
+}
+
+/// Put together a comparison from rows as the database returns them: `(id, name)` for added and
+/// retired items, and `(id, name, wears)` for worn ones.
+pub(crate) fn compare(
+    (items_before, items_after): (u32, u32),
+    added: Vec<(i32, String)>,
+    retired: Vec<(i32, String)>,
+    worn: Vec<(i32, String, i32)>,
+) -> Changes {
+    let entry = |(id, name): (i32, String)| Entry {
+        key: id as usize,
+        name,
+        wears: None,
+    };
+
+    let mut worn = worn
+        .into_iter()
+        .filter(|(_, _, wears)| *wears > 0)
+        .map(|(id, name, wears)| Entry {
+            key: id as usize,
+            name,
+            wears: Some(wears as u32),
+        })
+        .collect::<Vec<_>>();
+    worn.sort_by(|a, b| b.wears.cmp(&a.wears).then_with(|| a.name.cmp(&b.name)));
+
+    let total_wears = worn.iter().filter_map(|e| e.wears).sum();
+    // worn items may since have been retired, so they don't all count against the end
+    let retired_ids = retired
+        .iter()
+        .map(|(id, _)| *id as usize)
+        .collect::<Vec<_>>();
+    let still_worn = worn
+        .iter()
+        .filter(|e| !retired_ids.contains(&e.key))
+        .count() as u32;
+
+    Changes {
+        items_before,
+        items_after,
+        added: added.into_iter().map(entry).collect(),
+        retired: retired.into_iter().map(entry).collect(),
+        total_wears,
+        unworn: items_after.saturating_sub(still_worn),
+        worn,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn period() {
+        let today = NaiveDate::from_ymd(2021, 6, 15);
+        let jan = NaiveDate::from_ymd(2021, 1, 1);
+
+        assert_eq!(
+            Period::new(None, None, today),
+            Period {
+                from: jan,
+                to: today
+            }
+        );
+        assert_eq!(
+            Period::new(Some(today), Some(jan), today),
+            Period {
+                from: jan,
+                to: today
+            }
+        );
+
+        let (start, end) = Period::new(Some(today), Some(today), today).bounds();
+        assert_eq!(end - start, Duration::days(1));
+    }
+
+    #[test]
+    fn compared() {
+        let changes = compare(
+            (3, 4),
+            vec![(4, "Boots".into()), (5, "Scarf".into())],
+            vec![(2, "Old coat".into())],
+            vec![
+                (1, "Jeans".into(), 3),
+                (2, "Old coat".into(), 5),
+                (4, "Boots".into(), 3),
+            ],
+        );
+
+        assert_eq!(changes.total_wears, 11);
+        assert_eq!(
+            changes
+                .worn
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Old coat", "Boots", "Jeans"]
+        );
+        assert_eq!(changes.unworn, 2);
+    }
+}
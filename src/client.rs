@@ -0,0 +1,137 @@
+//! Working out who a request came from. Behind a reverse proxy every connection comes from the
+//! proxy itself, so for proxies we were told to trust, the client is read from the
+//! `X-Forwarded-For` header they add instead.
+
+use {
+    super::timing::{self, Timings},
+    std::{
+        net::{IpAddr, SocketAddr},
+        sync::Arc,
+        time::Instant,
+    },
+    warp::{http::Method, path::FullPath, Filter, Rejection, Reply},
+};
+
+/// The address a request really came from, given the socket it arrived on and any
+/// `X-Forwarded-For` header.
+///
+/// Addresses are taken from the right of the header for as long as they belong to trusted
+/// proxies, since anything further left could have been made up by the client.
+fn resolve(remote: IpAddr, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&remote) {
+        return remote;
+    }
+
+    let mut client = remote;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse() {
+            Ok(ip) => {
+                client = ip;
+                if !trusted.contains(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+/// Extract the client's address, or `None` if the connection has no peer address.
+pub(crate) fn ip(
+    trusted: Arc<Vec<IpAddr>>,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(
+            move |remote: Option<SocketAddr>, forwarded: Option<String>| {
+                remote.map(|r| resolve(r.ip(), forwarded.as_deref(), &trusted))
+            },
+        )
+}
+
+/// Log every request along with the client's real address, like `warp::log` does for the
+/// address of the connection, and time the ones that were answered.
+pub(crate) fn log<F, R>(
+    name: &'static str,
+    trusted: Arc<Vec<IpAddr>>,
+    timings: Timings,
+    routes: F,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let outcome = routes
+        .map(|reply: R| Ok(reply.into_response()))
+        .or_else(|rejection| async { Ok::<_, Rejection>((Err(rejection),)) });
+
+    ip(trusted)
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(warp::any().map(Instant::now))
+        .and(outcome)
+        .and_then(
+            move |client: Option<IpAddr>,
+                  method: Method,
+                  path: FullPath,
+                  started: Instant,
+                  outcome: Result<warp::reply::Response, Rejection>| {
+                let timings = timings.clone();
+                async move {
+                    let elapsed = started.elapsed();
+                    if outcome.is_ok() {
+                        let route = timing::route(&method, path.as_str());
+                        if timings.record(route.clone(), elapsed) {
+                            eprintln!(
+                                "Slow request: {} took {:?}, over the {:?} budget",
+                                route,
+                                elapsed,
+                                timings.budget()
+                            );
+                        }
+                    }
+
+                    let status = match &outcome {
+                        Ok(response) => response.status().as_u16().to_string(),
+                        Err(rejection) if rejection.is_not_found() => "404".into(),
+                        Err(_) => "rejected".into(),
+                    };
+                    log::info!(
+                        target: name,
+                        "{} \"{} {}\" {} {:?}",
+                        client.map_or_else(|| "-".into(), |ip| ip.to_string()),
+                        method,
+                        path.as_str(),
+                        status,
+                        elapsed,
+                    );
+                    outcome
+                }
+            },
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn forwarded_only_through_trusted_proxies() {
+        let proxy: IpAddr = "127.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.9".parse().unwrap();
+        let trusted = [proxy];
+
+        // direct connections, and untrusted ones, can't claim to be someone else
+        assert_eq!(resolve(client, Some("10.0.0.1"), &trusted), client);
+        assert_eq!(resolve(proxy, Some("203.0.113.9"), &[]), proxy);
+
+        assert_eq!(resolve(proxy, Some("203.0.113.9"), &trusted), client);
+        assert_eq!(
+            resolve(proxy, Some("10.0.0.1, 203.0.113.9, 127.0.0.1"), &trusted),
+            client
+        );
+        assert_eq!(resolve(proxy, None, &trusted), proxy);
+        assert_eq!(resolve(proxy, Some("garbage"), &trusted), proxy);
+    }
+}
@@ -0,0 +1,156 @@
+//! Sorting item colors into a handful of families, so "all my black garments" also finds the
+//! ones picked as #1b1b1f.
+
+/// Every family a color can belong to, roughly in rainbow order.
+pub(crate) const FAMILIES: &[&str] = &[
+    "black", "gray", "white", "brown", "red", "orange", "yellow", "green", "blue", "purple", "pink",
+];
+
+/// Read a `#rrggbb` color into its channels.
+fn parse(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Whether a color is written as `#rrggbb`.
+pub(crate) fn is_valid(hex: &str) -> bool {
+    parse(hex).is_some()
+}
+
+/// Hue in degrees, saturation and lightness, the latter two from 0 to 1.
+fn hsl((r, g, b): (u8, u8, u8)) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255., g as f64 / 255., b as f64 / 255.);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.;
+    let delta = max - min;
+
+    if delta == 0. {
+        return (0., 0., lightness);
+    }
+
+    let saturation = delta / (1. - (2. * lightness - 1.).abs());
+    let hue = if max == r {
+        60. * ((g - b) / delta).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / delta + 2.)
+    } else {
+        60. * ((r - g) / delta + 4.)
+    };
+
+    (hue, saturation, lightness)
+}
+
+/// How light a `#rrggbb` color is, from 0 to 1, if it can be read.
+pub(crate) fn lightness(hex: &str) -> Option<f64> {
+    parse(hex).map(|rgb| hsl(rgb).2)
+}
+
+/// The family a `#rrggbb` color belongs to, if it can be read.
+pub(crate) fn family(hex: &str) -> Option<&'static str> {
+    let (hue, saturation, lightness) = hsl(parse(hex)?);
+
+    Some(match hue {
+        _ if lightness < 0.12 => "black",
+        _ if lightness > 0.93 => "white",
+        _ if saturation < 0.15 => match lightness {
+            l if l < 0.2 => "black",
+            l if l > 0.85 => "white",
+            _ => "gray",
+        },
+        h if !(15. ..345.).contains(&h) => {
+            if lightness > 0.75 {
+                "pink"
+            } else {
+                "red"
+            }
+        }
+        h if h < 45. => {
+            if lightness < 0.45 || saturation < 0.4 {
+                "brown"
+            } else {
+                "orange"
+            }
+        }
+        h if h < 70. => {
+            if lightness < 0.3 {
+                "brown"
+            } else {
+                "yellow"
+            }
+        }
+        h if h < 165. => "green",
+        h if h < 255. => "blue",
+        h if h < 320. => "purple",
+        _ => "pink",
+    })
+}
+
+/// What a `color` filter asks for: one exact color, or a whole family.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ColorFilter {
+    Exact(String),
+    Family(String),
+}
+
+impl ColorFilter {
+    pub(crate) fn new(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        match input.as_str() {
+            "" => None,
+            hex if parse(hex).is_some() => Some(Self::Exact(input)),
+            // let people leave out the hash, which would need escaping in a URL
+            hex if parse(&format!("#{}", hex)).is_some() => Some(Self::Exact(format!("#{}", hex))),
+            _ => Some(Self::Family(input)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn families() {
+        let cases = [
+            ("#000000", "black"),
+            ("#1b1b1f", "black"),
+            ("#ffffff", "white"),
+            ("#808080", "gray"),
+            ("#1f2a44", "blue"),
+            ("#ff0000", "red"),
+            ("#ffc0cb", "pink"),
+            ("#8b4513", "brown"),
+            ("#ffa500", "orange"),
+            ("#ffff00", "yellow"),
+            ("#556b2f", "green"),
+            ("#800080", "purple"),
+        ];
+        for (hex, expected) in cases.iter() {
+            assert_eq!(family(hex), Some(*expected), "{}", hex);
+            assert!(FAMILIES.contains(expected));
+        }
+
+        assert_eq!(family("navy"), None);
+        assert_eq!(family("#12345"), None);
+    }
+
+    #[test]
+    fn filters() {
+        assert!(
+            matches!(ColorFilter::new("#1F2A44"), Some(ColorFilter::Exact(c)) if c == "#1f2a44")
+        );
+        assert!(
+            matches!(ColorFilter::new("1f2a44"), Some(ColorFilter::Exact(c)) if c == "#1f2a44")
+        );
+        assert!(
+            matches!(ColorFilter::new(" Black "), Some(ColorFilter::Family(f)) if f == "black")
+        );
+        assert!(ColorFilter::new("").is_none());
+    }
+}
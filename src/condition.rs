@@ -0,0 +1,135 @@
+//! How worn out an item is, as rated by hand, and a guess at the rating from how much of its
+//! expected life its wears have used up. The guess is only shown next to the rating; it never
+//! replaces one.
+
+use {
+    super::{lifespan, Item},
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::{fmt, str::FromStr},
+};
+
+/// Wears each piece is expected to last, for guessing at items without a lifespan of their own.
+const TYPICAL_LIFESPAN_WEARS: u32 = 100;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Condition {
+    New,
+    Good,
+    Worn,
+    RetireSoon,
+}
+
+impl Condition {
+    pub(crate) const ALL: [Self; 4] = [Self::New, Self::Good, Self::Worn, Self::RetireSoon];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Good => "good",
+            Self::Worn => "worn",
+            Self::RetireSoon => "retire-soon",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::New => "New",
+            Self::Good => "Good",
+            Self::Worn => "Worn",
+            Self::RetireSoon => "Retire soon",
+        }
+    }
+
+    /// The rating that fits a share of its expected life used up, in percent.
+    fn for_life_used(percent: u32) -> Self {
+        match percent {
+            p if p < 10 => Self::New,
+            p if p < 50 => Self::Good,
+            p if p < lifespan::NEARING_END => Self::Worn,
+            _ => Self::RetireSoon,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown condition: {}", s))
+    }
+}
+
+/// A guess at an item's condition from its wears, against its expected lifespan in wears or a
+/// typical one.
+pub(crate) fn suggest(item: &Item) -> Condition {
+    let wears = item
+        .lifespan_wears
+        .filter(|w| *w > 0)
+        .unwrap_or(TYPICAL_LIFESPAN_WEARS) as usize
+        * item.quantity.max(1) as usize;
+
+    Condition::for_life_used((item.total_count * 100 / wears).min(100) as u32)
+}
+
+/// Choices for the item form, with the item's own rating selected.
+pub(crate) fn form_options(item: &Item) -> serde_json::Value {
+    Condition::ALL
+        .iter()
+        .map(|c| {
+            json!({
+                "value": c.as_str(),
+                "label": c.label(),
+                "selected": item.condition == Some(*c),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn suggested_from_wears() {
+        let worn = |total_count, lifespan_wears| {
+            suggest(&Item {
+                total_count,
+                lifespan_wears,
+                quantity: 1,
+                ..Item::default()
+            })
+        };
+
+        assert_eq!(worn(0, None), Condition::New);
+        assert_eq!(worn(30, None), Condition::Good);
+        assert_eq!(worn(60, None), Condition::Worn);
+        assert_eq!(worn(500, None), Condition::RetireSoon);
+        assert_eq!(worn(30, Some(40)), Condition::Worn);
+        assert_eq!(worn(30, Some(0)), Condition::Good);
+
+        let pair = Item {
+            total_count: 60,
+            quantity: 2,
+            ..Item::default()
+        };
+        assert_eq!(suggest(&pair), Condition::Good);
+
+        assert_eq!(
+            "retire-soon".parse::<Condition>().ok(),
+            Some(Condition::RetireSoon)
+        );
+        assert!("mint".parse::<Condition>().is_err());
+    }
+}
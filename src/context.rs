@@ -0,0 +1,141 @@
+//! What the item list and the item form are rendered with. They take more than any other page, so
+//! they get structs instead of `json!`: a field can't go missing from one render path without the
+//! compiler noticing, and the golden tests in `template` show what each one does to the page.
+
+use {
+    super::{condition::Condition, Nulls, SortItems, View},
+    chrono::{DateTime, NaiveDate, Utc},
+    serde::Serialize,
+};
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Index {
+    pub(crate) items: Vec<IndexItem>,
+    pub(crate) num_items: usize,
+    pub(crate) view: Option<View>,
+    pub(crate) q: Option<String>,
+    pub(crate) q_param: Option<String>,
+    pub(crate) tag: Option<String>,
+    pub(crate) tag_param: Option<String>,
+    pub(crate) color: Option<String>,
+    pub(crate) color_param: Option<String>,
+    pub(crate) color_families: &'static [&'static str],
+    pub(crate) unworn_days: Option<u32>,
+    /// Whether only archived items are listed
+    pub(crate) archived: bool,
+    pub(crate) filter: Option<String>,
+    pub(crate) filter_param: Option<String>,
+    pub(crate) filter_error: Option<String>,
+    pub(crate) filtered: bool,
+    pub(crate) exact_color: Option<String>,
+    pub(crate) sort: Option<SortItems>,
+    /// The rest of the sort keys, kept when sorting by another column
+    pub(crate) then_by: Option<String>,
+    pub(crate) descending: Option<bool>,
+    pub(crate) nulls: Option<Nulls>,
+    pub(crate) nulls_first: bool,
+    /// Links to this listing with never worn or washed items first, then last
+    pub(crate) nulls_links: Vec<String>,
+    pub(crate) page: usize,
+    pub(crate) pages: usize,
+    pub(crate) prev: Option<String>,
+    pub(crate) next: Option<String>,
+    pub(crate) form_key: String,
+    pub(crate) saved_views: Vec<ViewLink>,
+    pub(crate) current_view: Option<CurrentView>,
+    pub(crate) current_query: String,
+    pub(crate) updated_at: Option<DateTime<Utc>>,
+    pub(crate) updated_fmt: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexItem {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) count: usize,
+    pub(crate) total_count: usize,
+    pub(crate) has_wear: bool,
+    pub(crate) wear: Option<DateTime<Utc>>,
+    pub(crate) wear_fmt: Option<String>,
+    pub(crate) has_wash: bool,
+    pub(crate) wash: Option<DateTime<Utc>>,
+    pub(crate) wash_fmt: Option<String>,
+    pub(crate) color: String,
+    pub(crate) color_param: String,
+    /// Separated by commas, for the quick edit form
+    pub(crate) tags: String,
+    pub(crate) tag_links: Vec<TagLink>,
+    pub(crate) weekly_wears: Option<Vec<u32>>,
+    /// Only once it's been worn more than once today
+    pub(crate) worn_today: Option<u32>,
+    pub(crate) has_photo: bool,
+    pub(crate) cost_per_wear: Option<String>,
+    /// Only for items tracked by the hour
+    pub(crate) hours: Option<String>,
+    pub(crate) cost_per_hour: Option<String>,
+    /// Only for items with more than one piece
+    pub(crate) quantity: Option<u32>,
+    pub(crate) wears_each: usize,
+    /// Worn as many times as it should go between washes
+    pub(crate) needs_wash: bool,
+    pub(crate) condition: Option<Condition>,
+    pub(crate) condition_label: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TagLink {
+    pub(crate) name: String,
+    pub(crate) param: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ViewLink {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) link: String,
+    pub(crate) current: bool,
+}
+
+/// The saved view matching the listing being shown.
+#[derive(Serialize)]
+pub(crate) struct CurrentView {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) alert: bool,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Edit {
+    pub(crate) edit: bool,
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) color: String,
+    pub(crate) tags: String,
+    pub(crate) retailer: String,
+    pub(crate) product_url: String,
+    pub(crate) return_by: Option<NaiveDate>,
+    pub(crate) warranty_until: Option<NaiveDate>,
+    pub(crate) care_options: serde_json::Value,
+    pub(crate) units: &'static str,
+    pub(crate) measurement_fields: Vec<serde_json::Value>,
+    pub(crate) wash_after: Option<u32>,
+    pub(crate) quantity: u32,
+    pub(crate) condition_options: serde_json::Value,
+    pub(crate) tracking_options: serde_json::Value,
+    pub(crate) warmth_options: serde_json::Value,
+    pub(crate) season_options: serde_json::Value,
+    /// What its wears so far suggest, when that isn't how it's rated
+    pub(crate) suggested_condition: Option<&'static str>,
+    pub(crate) purchased_on: Option<NaiveDate>,
+    pub(crate) lifespan_months: Option<u32>,
+    pub(crate) lifespan_wears: Option<u32>,
+    pub(crate) price: Option<f64>,
+    pub(crate) currency: String,
+    pub(crate) default_currency: String,
+    pub(crate) custom_fields: Vec<serde_json::Value>,
+}
@@ -0,0 +1,159 @@
+//! Fields added to items from the settings page, for anything the built-in ones don't cover
+//! ("thrifted?", "warmth rating"). Values are stored as text; a field's kind only decides how it
+//! is entered and shown.
+
+use {
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+};
+
+/// Form inputs for custom fields are named with this and the field's id.
+const PREFIX: &str = "custom_";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Kind {
+    Text,
+    Number,
+    YesNo,
+}
+
+impl Kind {
+    pub(crate) fn parse(kind: &str) -> Self {
+        match kind {
+            "number" => Self::Number,
+            "yesno" => Self::YesNo,
+            _ => Self::Text,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Number => "number",
+            Self::YesNo => "yesno",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Field {
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    pub(crate) kind: Kind,
+}
+
+/// Values of custom fields submitted with the item form, by field id. Yes/no fields are sent
+/// twice when checked (a hidden "no", then the checkbox), so later values win.
+pub(crate) fn from_form(fields: &[(String, String)]) -> Vec<(usize, String)> {
+    let mut values = Vec::<(usize, String)>::new();
+
+    for (key, value) in fields {
+        if let Some(id) = key.strip_prefix(PREFIX).and_then(|id| id.parse().ok()) {
+            values.retain(|(i, _)| *i != id);
+            values.push((id, value.trim().to_string()));
+        }
+    }
+
+    values
+}
+
+/// Check that values fit their fields' kinds. Values for fields that no longer exist are
+/// dropped.
+pub(crate) fn validate(
+    fields: &[Field],
+    values: Vec<(usize, String)>,
+) -> anyhow::Result<Vec<(usize, String)>> {
+    let mut checked = Vec::new();
+
+    for (id, value) in values {
+        let field = match fields.iter().find(|f| f.id == id) {
+            Some(f) => f,
+            None => continue,
+        };
+
+        let ok = value.is_empty()
+            || match field.kind {
+                Kind::Text => true,
+                Kind::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+                Kind::YesNo => value == "yes" || value == "no",
+            };
+        if !ok {
+            anyhow::bail!("{} is not a valid value for {}", value, field.name);
+        }
+
+        checked.push((id, value));
+    }
+
+    Ok(checked)
+}
+
+/// Inputs for every custom field, filled in with an item's values.
+pub(crate) fn form_fields(fields: &[Field], values: &[(usize, String)]) -> Vec<serde_json::Value> {
+    fields
+        .iter()
+        .map(|f| {
+            let value = values.iter().find(|(id, _)| *id == f.id).map(|(_, v)| v);
+            json!({
+                "input": format!("{}{}", PREFIX, f.id),
+                "name": f.name,
+                "kind": f.kind,
+                "value": value,
+                "checked": value.is_some_and(|v| v == "yes"),
+            })
+        })
+        .collect()
+}
+
+/// An item's custom fields that have a value, ready to show.
+pub(crate) fn shown(fields: &[Field], values: &[(usize, String)]) -> Vec<serde_json::Value> {
+    fields
+        .iter()
+        .filter_map(|f| {
+            let value = values.iter().find(|(id, _)| *id == f.id)?.1.as_str();
+            let value = match (f.kind, value) {
+                (_, "") => return None,
+                (Kind::YesNo, "yes") => "Yes",
+                (Kind::YesNo, _) => "No",
+                (_, value) => value,
+            };
+            Some(json!({ "name": f.name, "value": value }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked_boxes_win() {
+        let form = vec![
+            ("name".to_string(), "Coat".to_string()),
+            ("custom_1".to_string(), "no".to_string()),
+            ("custom_1".to_string(), "yes".to_string()),
+            ("custom_2".to_string(), " 4 ".to_string()),
+            ("custom_x".to_string(), "?".to_string()),
+        ];
+        let values = from_form(&form);
+        assert_eq!(values, vec![(1, "yes".into()), (2, "4".into())]);
+
+        let fields = vec![
+            Field {
+                id: 1,
+                name: "Thrifted".into(),
+                kind: Kind::YesNo,
+            },
+            Field {
+                id: 2,
+                name: "Warmth".into(),
+                kind: Kind::Number,
+            },
+        ];
+        assert_eq!(validate(&fields, values).unwrap().len(), 2);
+        assert!(validate(&fields, vec![(2, "warm".into())]).is_err());
+        assert!(validate(&fields, vec![(3, "gone".into())])
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+ALTER TABLE garments ADD COLUMN token TEXT;
+
+UPDATE garments SET token = lower(hex(randomblob(6))) WHERE token IS NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS garments_token ON garments (token);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS wear_events (
+  id      INTEGER PRIMARY KEY NOT NULL,
+  item_id INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  worn_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS wear_events_item ON wear_events (item_id, worn_at);
+
+CREATE TABLE IF NOT EXISTS wear_summaries (
+  item_id INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  month   TEXT NOT NULL,
+  wears   INTEGER NOT NULL DEFAULT 0,
+  PRIMARY KEY (item_id, month)
+);
@@ -0,0 +1,3 @@
+ALTER TABLE garments ADD COLUMN retailer TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE garments ADD COLUMN product_url TEXT NOT NULL DEFAULT '';
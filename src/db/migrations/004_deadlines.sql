@@ -0,0 +1,3 @@
+ALTER TABLE garments ADD COLUMN return_by TEXT;
+
+ALTER TABLE garments ADD COLUMN warranty_until TEXT;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS attachments (
+  id           INTEGER PRIMARY KEY NOT NULL,
+  item_id      INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  file_name    TEXT NOT NULL,
+  content_type TEXT NOT NULL,
+  size         INTEGER NOT NULL,
+  stored_as    TEXT NOT NULL,
+  uploaded_at  TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attachments_item ON attachments (item_id);
@@ -0,0 +1,9 @@
+ALTER TABLE garments ADD COLUMN care_wash TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE garments ADD COLUMN care_bleach TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE garments ADD COLUMN care_dry TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE garments ADD COLUMN care_iron TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE garments ADD COLUMN care_dry_clean TEXT NOT NULL DEFAULT '';
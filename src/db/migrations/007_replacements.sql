@@ -0,0 +1 @@
+ALTER TABLE garments ADD COLUMN replaced_by INTEGER REFERENCES garments (id) ON DELETE SET NULL;
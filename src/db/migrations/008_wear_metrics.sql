@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS wear_metrics (
+  event_id INTEGER NOT NULL REFERENCES wear_events (id) ON DELETE CASCADE,
+  kind     TEXT NOT NULL,
+  value    REAL NOT NULL,
+  PRIMARY KEY (event_id, kind)
+);
+
+ALTER TABLE garments ADD COLUMN resoled_at TEXT;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS wash_events (
+  id          INTEGER PRIMARY KEY NOT NULL,
+  item_id     INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  washed_at   TEXT NOT NULL,
+  method      TEXT NOT NULL DEFAULT '',
+  temperature INTEGER,
+  detergent   TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS wash_events_item ON wash_events (item_id, washed_at);
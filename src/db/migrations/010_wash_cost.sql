@@ -0,0 +1 @@
+ALTER TABLE wash_events ADD COLUMN cost REAL;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS item_tasks (
+  item_id    INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  task       TEXT NOT NULL,
+  note       TEXT NOT NULL DEFAULT '',
+  flagged_at TEXT NOT NULL,
+  PRIMARY KEY (item_id, task)
+);
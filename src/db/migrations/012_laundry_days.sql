@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS laundry_schedule (
+  weekday INTEGER PRIMARY KEY NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS laundry_days (
+  day          TEXT PRIMARY KEY NOT NULL,
+  completed_at TEXT NOT NULL,
+  washed       INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS retired_tokens (
+  token      TEXT PRIMARY KEY NOT NULL,
+  item_id    INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  retired_at TEXT NOT NULL,
+  revoked    INTEGER NOT NULL DEFAULT 0
+);
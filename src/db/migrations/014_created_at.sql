@@ -0,0 +1 @@
+ALTER TABLE garments ADD COLUMN created_at TEXT;
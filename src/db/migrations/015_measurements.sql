@@ -0,0 +1,9 @@
+ALTER TABLE garments ADD COLUMN chest REAL;
+ALTER TABLE garments ADD COLUMN waist REAL;
+ALTER TABLE garments ADD COLUMN inseam REAL;
+ALTER TABLE garments ADD COLUMN sleeve REAL;
+
+CREATE TABLE IF NOT EXISTS settings (
+  key   TEXT PRIMARY KEY NOT NULL,
+  value TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+ALTER TABLE attachments ADD COLUMN digest TEXT;
+
+CREATE INDEX IF NOT EXISTS attachments_digest ON attachments (digest);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  key TEXT NOT NULL,
+  action TEXT NOT NULL,
+  claimed_at TEXT NOT NULL,
+  PRIMARY KEY (key, action)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS search_terms (
+  term TEXT NOT NULL,
+  item_id INTEGER NOT NULL,
+  PRIMARY KEY (term, item_id)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS search_terms_item ON search_terms (item_id);
+
+CREATE TRIGGER IF NOT EXISTS search_terms_delete AFTER DELETE ON garments BEGIN
+  DELETE FROM search_terms WHERE item_id = old.id;
+END;
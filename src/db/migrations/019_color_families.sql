@@ -0,0 +1,3 @@
+ALTER TABLE garments ADD COLUMN color_family TEXT;
+
+CREATE INDEX IF NOT EXISTS garments_color_family ON garments (color_family);
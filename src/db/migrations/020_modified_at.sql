@@ -0,0 +1,3 @@
+ALTER TABLE garments ADD COLUMN modified_at TEXT;
+
+UPDATE garments SET modified_at = created_at;
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS views (
+  id    INTEGER PRIMARY KEY NOT NULL,
+  name  TEXT NOT NULL,
+  query TEXT NOT NULL
+);
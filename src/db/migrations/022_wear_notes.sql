@@ -0,0 +1,8 @@
+ALTER TABLE wear_events ADD COLUMN note TEXT NOT NULL DEFAULT '';
+
+-- items last worn before wears were recorded individually still have that one wear to show
+INSERT INTO wear_events ( item_id, worn_at )
+SELECT id, wear FROM garments g
+WHERE wear IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM wear_events e WHERE e.item_id = g.id)
+  AND NOT EXISTS (SELECT 1 FROM wear_summaries s WHERE s.item_id = g.id);
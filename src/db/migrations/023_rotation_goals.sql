@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS rotation_goals (
+  category     TEXT PRIMARY KEY NOT NULL,
+  weekly_wears INTEGER NOT NULL
+);
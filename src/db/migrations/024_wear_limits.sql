@@ -0,0 +1,6 @@
+ALTER TABLE garments ADD COLUMN wash_after INTEGER;
+
+CREATE TABLE IF NOT EXISTS wear_limits (
+  category TEXT PRIMARY KEY NOT NULL,
+  wears    INTEGER NOT NULL
+);
@@ -0,0 +1,5 @@
+ALTER TABLE garments ADD COLUMN purchased_on TEXT;
+
+ALTER TABLE garments ADD COLUMN lifespan_months INTEGER;
+
+ALTER TABLE garments ADD COLUMN lifespan_wears INTEGER;
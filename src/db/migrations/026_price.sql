@@ -0,0 +1,4 @@
+ALTER TABLE garments ADD COLUMN price REAL;
+
+-- empty means the currency in the settings
+ALTER TABLE garments ADD COLUMN currency TEXT NOT NULL DEFAULT '';
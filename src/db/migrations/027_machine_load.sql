@@ -0,0 +1,6 @@
+-- what's in the washing machine right now; finished_at is set when the plug reports the cycle done
+CREATE TABLE machine_load (
+  item_id     INTEGER PRIMARY KEY REFERENCES garments (id) ON DELETE CASCADE,
+  started_at  TEXT NOT NULL,
+  finished_at TEXT
+);
@@ -0,0 +1,12 @@
+CREATE TABLE custom_fields (
+  id   INTEGER PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  kind TEXT NOT NULL DEFAULT 'text'
+);
+
+CREATE TABLE custom_values (
+  item_id  INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  field_id INTEGER NOT NULL REFERENCES custom_fields (id) ON DELETE CASCADE,
+  value    TEXT NOT NULL,
+  PRIMARY KEY (item_id, field_id)
+);
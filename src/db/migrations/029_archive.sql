@@ -0,0 +1 @@
+ALTER TABLE garments ADD COLUMN archived_at TEXT;
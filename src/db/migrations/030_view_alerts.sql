@@ -0,0 +1,8 @@
+ALTER TABLE views ADD COLUMN alert INTEGER NOT NULL DEFAULT 0;
+
+-- what each alerting view matched when it was last checked, so only new matches are reported
+CREATE TABLE view_alert_matches (
+  view_id INTEGER NOT NULL REFERENCES views (id) ON DELETE CASCADE,
+  item_id INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  PRIMARY KEY (view_id, item_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE garments ADD COLUMN deleted_at TEXT;
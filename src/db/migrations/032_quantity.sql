@@ -0,0 +1,2 @@
+-- identical pieces tracked as one item, like a pack of socks
+ALTER TABLE garments ADD COLUMN quantity INTEGER NOT NULL DEFAULT 1;
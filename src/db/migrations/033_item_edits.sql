@@ -0,0 +1,11 @@
+-- what each edit of an item changed, one row per field
+CREATE TABLE item_edits (
+  id        INTEGER PRIMARY KEY,
+  item_id   INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  edited_at TEXT NOT NULL,
+  field     TEXT NOT NULL,
+  old_value TEXT NOT NULL,
+  new_value TEXT NOT NULL
+);
+
+CREATE INDEX item_edits_item ON item_edits (item_id);
@@ -0,0 +1,2 @@
+-- when an item was put on the wishlist; empty once it's been bought, or if it always was owned
+ALTER TABLE garments ADD COLUMN wishlisted_at TEXT;
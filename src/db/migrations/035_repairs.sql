@@ -0,0 +1,10 @@
+-- repairs and alterations, with what each cost
+CREATE TABLE repairs (
+  id          INTEGER PRIMARY KEY,
+  item_id     INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  repaired_on TEXT NOT NULL,
+  description TEXT NOT NULL,
+  cost        REAL
+);
+
+CREATE INDEX repairs_item ON repairs (item_id);
@@ -0,0 +1,2 @@
+-- how worn out each item is, as last rated by hand
+ALTER TABLE garments ADD COLUMN condition TEXT;
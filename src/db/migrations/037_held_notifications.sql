@@ -0,0 +1,7 @@
+-- notifications waiting for quiet hours to end
+CREATE TABLE held_notifications (
+  id      INTEGER PRIMARY KEY,
+  subject TEXT NOT NULL,
+  body    TEXT NOT NULL,
+  held_at TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE garments ADD COLUMN tracking TEXT NOT NULL DEFAULT 'wears';
+ALTER TABLE garments ADD COLUMN total_hours REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,11 @@
+-- items usually worn together, logged with one button
+CREATE TABLE outfits (
+  id   INTEGER PRIMARY KEY,
+  name TEXT NOT NULL
+);
+
+CREATE TABLE outfit_items (
+  outfit_id INTEGER NOT NULL REFERENCES outfits (id) ON DELETE CASCADE,
+  item_id   INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  PRIMARY KEY (outfit_id, item_id)
+);
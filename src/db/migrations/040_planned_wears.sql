@@ -0,0 +1,11 @@
+-- an item or outfit to wear on a day to come
+CREATE TABLE planned_wears (
+  id         INTEGER PRIMARY KEY,
+  planned_on TEXT NOT NULL,
+  item_id    INTEGER REFERENCES garments (id) ON DELETE CASCADE,
+  outfit_id  INTEGER REFERENCES outfits (id) ON DELETE CASCADE,
+  note       TEXT NOT NULL DEFAULT '',
+  CHECK ((item_id IS NULL) != (outfit_id IS NULL))
+);
+
+CREATE INDEX planned_wears_day ON planned_wears (planned_on);
@@ -0,0 +1,18 @@
+-- trips to pack for, and what's going on each
+CREATE TABLE trips (
+  id             INTEGER PRIMARY KEY,
+  name           TEXT NOT NULL,
+  starts_on      TEXT NOT NULL,
+  ends_on        TEXT NOT NULL,
+  -- whether everything packed is logged as worn on each day of the trip, and up to which day it
+  -- has been
+  log_wears      INTEGER NOT NULL DEFAULT 0,
+  logged_through TEXT
+);
+
+CREATE TABLE trip_items (
+  trip_id INTEGER NOT NULL REFERENCES trips (id) ON DELETE CASCADE,
+  item_id INTEGER NOT NULL REFERENCES garments (id) ON DELETE CASCADE,
+  packed  INTEGER NOT NULL DEFAULT 0,
+  PRIMARY KEY (trip_id, item_id)
+);
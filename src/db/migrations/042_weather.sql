@@ -0,0 +1,4 @@
+-- how warm each item is and the seasons it's worn in, for suggesting what to wear; items
+-- without them suit any weather
+ALTER TABLE garments ADD COLUMN warmth TEXT;
+ALTER TABLE garments ADD COLUMN seasons TEXT NOT NULL DEFAULT '';
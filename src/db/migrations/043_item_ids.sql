@@ -0,0 +1,76 @@
+-- rebuild the items table so a deleted item's id is never handed out again, which would give the
+-- next new item whatever history was left under it
+CREATE TEMP TABLE garments_old AS SELECT * FROM garments;
+
+DROP TABLE garments;
+
+CREATE TABLE garments (
+  id              INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  name            TEXT NOT NULL,
+  description     TEXT NOT NULL DEFAULT '',
+  color           TEXT NOT NULL,
+  tags            TEXT NOT NULL DEFAULT '',
+  count           INTEGER NOT NULL DEFAULT 0,
+  total           INTEGER NOT NULL DEFAULT 0,
+  wash            TEXT,
+  wear            TEXT,
+  token           TEXT,
+  retailer        TEXT NOT NULL DEFAULT '',
+  product_url     TEXT NOT NULL DEFAULT '',
+  return_by       TEXT,
+  warranty_until  TEXT,
+  care_wash       TEXT NOT NULL DEFAULT '',
+  care_bleach     TEXT NOT NULL DEFAULT '',
+  care_dry        TEXT NOT NULL DEFAULT '',
+  care_iron       TEXT NOT NULL DEFAULT '',
+  care_dry_clean  TEXT NOT NULL DEFAULT '',
+  replaced_by     INTEGER REFERENCES garments (id) ON DELETE SET NULL,
+  resoled_at      TEXT,
+  created_at      TEXT,
+  chest           REAL,
+  waist           REAL,
+  inseam          REAL,
+  sleeve          REAL,
+  color_family    TEXT,
+  modified_at     TEXT,
+  wash_after      INTEGER,
+  purchased_on    TEXT,
+  lifespan_months INTEGER,
+  lifespan_wears  INTEGER,
+  price           REAL,
+  currency        TEXT NOT NULL DEFAULT '',
+  archived_at     TEXT,
+  deleted_at      TEXT,
+  quantity        INTEGER NOT NULL DEFAULT 1,
+  wishlisted_at   TEXT,
+  condition       TEXT,
+  tracking        TEXT NOT NULL DEFAULT 'wears',
+  total_hours     REAL NOT NULL DEFAULT 0,
+  warmth          TEXT,
+  seasons         TEXT NOT NULL DEFAULT ''
+);
+
+INSERT INTO garments (
+  id, name, description, color, tags, count, total, wash, wear, token, retailer, product_url,
+  return_by, warranty_until, care_wash, care_bleach, care_dry, care_iron, care_dry_clean,
+  replaced_by, resoled_at, created_at, chest, waist, inseam, sleeve, color_family, modified_at,
+  wash_after, purchased_on, lifespan_months, lifespan_wears, price, currency, archived_at,
+  deleted_at, quantity, wishlisted_at, condition, tracking, total_hours, warmth, seasons
+)
+SELECT
+  id, name, description, color, tags, count, total, wash, wear, token, retailer, product_url,
+  return_by, warranty_until, care_wash, care_bleach, care_dry, care_iron, care_dry_clean,
+  replaced_by, resoled_at, created_at, chest, waist, inseam, sleeve, color_family, modified_at,
+  wash_after, purchased_on, lifespan_months, lifespan_wears, price, currency, archived_at,
+  deleted_at, quantity, wishlisted_at, condition, tracking, total_hours, warmth, seasons
+FROM garments_old;
+
+DROP TABLE garments_old;
+
+-- dropping the table took these with it
+CREATE UNIQUE INDEX IF NOT EXISTS garments_token ON garments (token);
+CREATE INDEX IF NOT EXISTS garments_color_family ON garments (color_family);
+
+CREATE TRIGGER IF NOT EXISTS search_terms_delete AFTER DELETE ON garments BEGIN
+  DELETE FROM search_terms WHERE item_id = old.id;
+END;
@@ -1,22 +1,450 @@
 use {
-    super::{Item, SortItems},
+    super::{
+        colors::{self, ColorFilter},
+        condition::Condition,
+        custom::{self, Field},
+        edits::{self, Edit},
+        history::{Event, Kind},
+        laundry,
+        measurements::Measurement,
+        search,
+        settings::{Collation, Settings},
+        trips::{self, Trip, TripItem},
+        utils,
+        weather::{Season, Warmth},
+        Item, ItemPatch, Metric, Nulls, SortItems, Task, View, WashDetails, WashMethod,
+        WearDetails,
+    },
     anyhow::Context,
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, Local, NaiveDate, Utc, Weekday},
+    serde::{Deserialize, Serialize},
     sqlx::{
         prelude::*,
         sqlite::{SqlitePool, SqliteRow},
     },
     std::{
-        ffi::OsString,
+        ffi::{OsStr, OsString},
         fmt::{self, Display},
-        path::PathBuf,
+        path::Path,
+        sync::{Arc, RwLock},
         time::Instant,
     },
     tokio::fs,
 };
 
+pub(crate) mod query;
+
+use query::{Query, Term};
+
 type ExecResult = sqlx::Result<u64>;
 
+type Tx = sqlx::Transaction<sqlx::pool::PoolConnection<sqlx::SqliteConnection>>;
+
+/// An outfit and one of its items, if it has any: `(id, name, item id, item name, item color)`.
+type OutfitRow = (i32, String, Option<i32>, Option<String>, Option<String>);
+
+/// A planned wear: `(id, day, item id, outfit id, name of the item or outfit, note)`.
+type PlannedRow = (i32, String, Option<i32>, Option<i32>, String, String);
+
+/// What's needed to log a trip's wears: `(name, start, end, logged through, packed item id)`,
+/// once for each item packed.
+type TripWearRow = (String, String, String, Option<String>, Option<i32>);
+
+/// A day as stored, as a `sqlx` error if it can't be read.
+fn parse_day(day: &str) -> sqlx::Result<NaiveDate> {
+    day.parse()
+        .map_err(|e: chrono::ParseError| sqlx::Error::Decode(e.into()))
+}
+
+/// Changes to apply on top of `schema.sql`, in order, with the names they're listed under. The
+/// number of these that a database has already seen is stored in its `user_version` pragma.
+const MIGRATIONS: &[(&str, &str)] = &[
+    ("001_tokens", include_str!("./migrations/001_tokens.sql")),
+    (
+        "002_wear_events",
+        include_str!("./migrations/002_wear_events.sql"),
+    ),
+    (
+        "003_purchase_source",
+        include_str!("./migrations/003_purchase_source.sql"),
+    ),
+    (
+        "004_deadlines",
+        include_str!("./migrations/004_deadlines.sql"),
+    ),
+    (
+        "005_attachments",
+        include_str!("./migrations/005_attachments.sql"),
+    ),
+    ("006_care", include_str!("./migrations/006_care.sql")),
+    (
+        "007_replacements",
+        include_str!("./migrations/007_replacements.sql"),
+    ),
+    (
+        "008_wear_metrics",
+        include_str!("./migrations/008_wear_metrics.sql"),
+    ),
+    (
+        "009_wash_events",
+        include_str!("./migrations/009_wash_events.sql"),
+    ),
+    (
+        "010_wash_cost",
+        include_str!("./migrations/010_wash_cost.sql"),
+    ),
+    ("011_tasks", include_str!("./migrations/011_tasks.sql")),
+    (
+        "012_laundry_days",
+        include_str!("./migrations/012_laundry_days.sql"),
+    ),
+    (
+        "013_retired_tokens",
+        include_str!("./migrations/013_retired_tokens.sql"),
+    ),
+    (
+        "014_created_at",
+        include_str!("./migrations/014_created_at.sql"),
+    ),
+    (
+        "015_measurements",
+        include_str!("./migrations/015_measurements.sql"),
+    ),
+    (
+        "016_attachment_digests",
+        include_str!("./migrations/016_attachment_digests.sql"),
+    ),
+    (
+        "017_idempotency_keys",
+        include_str!("./migrations/017_idempotency_keys.sql"),
+    ),
+    ("018_search", include_str!("./migrations/018_search.sql")),
+    (
+        "019_color_families",
+        include_str!("./migrations/019_color_families.sql"),
+    ),
+    (
+        "020_modified_at",
+        include_str!("./migrations/020_modified_at.sql"),
+    ),
+    (
+        "021_saved_views",
+        include_str!("./migrations/021_saved_views.sql"),
+    ),
+    (
+        "022_wear_notes",
+        include_str!("./migrations/022_wear_notes.sql"),
+    ),
+    (
+        "023_rotation_goals",
+        include_str!("./migrations/023_rotation_goals.sql"),
+    ),
+    (
+        "024_wear_limits",
+        include_str!("./migrations/024_wear_limits.sql"),
+    ),
+    (
+        "025_lifespan",
+        include_str!("./migrations/025_lifespan.sql"),
+    ),
+    ("026_price", include_str!("./migrations/026_price.sql")),
+    (
+        "027_machine_load",
+        include_str!("./migrations/027_machine_load.sql"),
+    ),
+    (
+        "028_custom_fields",
+        include_str!("./migrations/028_custom_fields.sql"),
+    ),
+    ("029_archive", include_str!("./migrations/029_archive.sql")),
+    (
+        "030_view_alerts",
+        include_str!("./migrations/030_view_alerts.sql"),
+    ),
+    ("031_trash", include_str!("./migrations/031_trash.sql")),
+    (
+        "032_quantity",
+        include_str!("./migrations/032_quantity.sql"),
+    ),
+    (
+        "033_item_edits",
+        include_str!("./migrations/033_item_edits.sql"),
+    ),
+    (
+        "034_wishlist",
+        include_str!("./migrations/034_wishlist.sql"),
+    ),
+    ("035_repairs", include_str!("./migrations/035_repairs.sql")),
+    (
+        "036_condition",
+        include_str!("./migrations/036_condition.sql"),
+    ),
+    (
+        "037_held_notifications",
+        include_str!("./migrations/037_held_notifications.sql"),
+    ),
+    (
+        "038_duration",
+        include_str!("./migrations/038_duration.sql"),
+    ),
+    ("039_outfits", include_str!("./migrations/039_outfits.sql")),
+    (
+        "040_planned_wears",
+        include_str!("./migrations/040_planned_wears.sql"),
+    ),
+    ("041_trips", include_str!("./migrations/041_trips.sql")),
+    ("042_weather", include_str!("./migrations/042_weather.sql")),
+    (
+        "043_item_ids",
+        include_str!("./migrations/043_item_ids.sql"),
+    ),
+];
+
+/// An item's category, which is its first tag.
+const CATEGORY: &str = "trim(lower(CASE WHEN instr(tags, ',') > 0 THEN substr(tags, 1, instr(tags, ',') - 1) ELSE tags END))";
+
+/// What's been spent on cleaning an item, for queries that load items to work out what each wear
+/// costs.
+const CLEANING_COST: &str =
+    "( SELECT TOTAL(cost) FROM wash_events w WHERE w.item_id = garments.id ) AS cleaning_cost";
+
+/// An item's condition as text that sorts from new to worn out, or NULL if it hasn't been rated.
+const CONDITION: &str = "CASE condition WHEN 'new' THEN '1' WHEN 'good' THEN '2' WHEN 'worn' THEN '3' WHEN 'retire-soon' THEN '4' END";
+
+/// Tables with a row or more per item, keyed by `item_id`, which go when the item does.
+const ITEM_TABLES: &[&str] = &[
+    "wear_events",
+    "wear_summaries",
+    "wash_events",
+    "attachments",
+    "item_tasks",
+    "retired_tokens",
+    "machine_load",
+    "custom_values",
+    "view_alert_matches",
+    "item_edits",
+    "repairs",
+    "outfit_items",
+    "planned_wears",
+    "trip_items",
+];
+
+/// Checks for records that disagree with each other, as a description of the problem, the table
+/// it's in, a condition on the rows that have it, and the change that fixes them (or nothing, to
+/// delete them). They run in order, so unreadable times are dealt with before anything compares
+/// times. SQLite's `julianday` is NULL for a time it can't read.
+const REPAIRS: &[(&str, &str, &str, Option<&str>)] = &[
+    (
+        "wear records for items that no longer exist",
+        "wear_events",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "monthly wear summaries for items that no longer exist",
+        "wear_summaries",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "wash records for items that no longer exist",
+        "wash_events",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "tasks for items that no longer exist",
+        "item_tasks",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "old tag tokens for items that no longer exist",
+        "retired_tokens",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "custom field values for items or fields that no longer exist",
+        "custom_values",
+        "item_id NOT IN ( SELECT id FROM garments ) \
+         OR field_id NOT IN ( SELECT id FROM custom_fields )",
+        None,
+    ),
+    (
+        "items in the washing machine that no longer exist",
+        "machine_load",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "alert matches for views or items that no longer exist",
+        "view_alert_matches",
+        "view_id NOT IN ( SELECT id FROM views ) OR item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "edit records for items that no longer exist",
+        "item_edits",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "repairs of items that no longer exist",
+        "repairs",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "outfit members for outfits or items that no longer exist",
+        "outfit_items",
+        "outfit_id NOT IN ( SELECT id FROM outfits ) OR item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "planned wears of items or outfits that no longer exist",
+        "planned_wears",
+        "( item_id IS NOT NULL AND item_id NOT IN ( SELECT id FROM garments ) ) \
+         OR ( outfit_id IS NOT NULL AND outfit_id NOT IN ( SELECT id FROM outfits ) )",
+        None,
+    ),
+    (
+        "trip packing list entries for trips or items that no longer exist",
+        "trip_items",
+        "trip_id NOT IN ( SELECT id FROM trips ) OR item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "search terms for items that no longer exist",
+        "search_terms",
+        "item_id NOT IN ( SELECT id FROM garments )",
+        None,
+    ),
+    (
+        "items replaced by items that no longer exist",
+        "garments",
+        "replaced_by NOT IN ( SELECT id FROM garments )",
+        Some("replaced_by = NULL"),
+    ),
+    (
+        "wear records with unreadable times",
+        "wear_events",
+        "julianday(worn_at) IS NULL",
+        None,
+    ),
+    (
+        "wear measurements for wear records that no longer exist",
+        "wear_metrics",
+        "event_id NOT IN ( SELECT id FROM wear_events )",
+        None,
+    ),
+    (
+        "wash records with unreadable times",
+        "wash_events",
+        "julianday(washed_at) IS NULL",
+        None,
+    ),
+    (
+        "items with an unreadable last wash time",
+        "garments",
+        "wash IS NOT NULL AND julianday(wash) IS NULL",
+        Some(
+            "wash = ( SELECT washed_at FROM wash_events e WHERE e.item_id = garments.id \
+             ORDER BY julianday(washed_at) DESC LIMIT 1 )",
+        ),
+    ),
+    (
+        "items with an unreadable resole time",
+        "garments",
+        "resoled_at IS NOT NULL AND julianday(resoled_at) IS NULL",
+        Some("resoled_at = NULL"),
+    ),
+    (
+        "items with an unreadable time they were moved to the trash",
+        "garments",
+        "deleted_at IS NOT NULL AND julianday(deleted_at) IS NULL",
+        Some("deleted_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')"),
+    ),
+    (
+        "items with an unreadable time they were put on the wishlist",
+        "garments",
+        "wishlisted_at IS NOT NULL AND julianday(wishlisted_at) IS NULL",
+        Some("wishlisted_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')"),
+    ),
+    (
+        "items with a condition that isn't one of the ratings",
+        "garments",
+        "condition NOT IN ( 'new', 'good', 'worn', 'retire-soon' )",
+        Some("condition = NULL"),
+    ),
+    (
+        "items with a warmth that isn't one of the ratings",
+        "garments",
+        "warmth NOT IN ( 'light', 'medium', 'warm' )",
+        Some("warmth = NULL"),
+    ),
+    (
+        "items tracked in a way that isn't wears or hours",
+        "garments",
+        "tracking NOT IN ( 'wears', 'hours' )",
+        Some("tracking = 'wears'"),
+    ),
+    (
+        "items with an unreadable last wear time",
+        "garments",
+        "wear IS NOT NULL AND julianday(wear) IS NULL",
+        Some("wear = NULL"),
+    ),
+    (
+        "items last worn before their latest recorded wear",
+        "garments",
+        "EXISTS ( SELECT 1 FROM wear_events e WHERE e.item_id = garments.id \
+         AND ( garments.wear IS NULL OR julianday(e.worn_at) > julianday(garments.wear) ) )",
+        Some(
+            "wear = ( SELECT worn_at FROM wear_events e WHERE e.item_id = garments.id \
+             ORDER BY julianday(worn_at) DESC LIMIT 1 )",
+        ),
+    ),
+    // totals can be higher than what's recorded, since wears from before they were recorded
+    // individually only count towards the total, but never lower
+    (
+        "items with fewer total wears than are recorded",
+        "garments",
+        "total < ( SELECT COUNT(*) FROM wear_events e WHERE e.item_id = garments.id ) \
+         + ( SELECT TOTAL(wears) FROM wear_summaries s WHERE s.item_id = garments.id )",
+        Some(
+            "total = ( SELECT COUNT(*) FROM wear_events e WHERE e.item_id = garments.id ) \
+             + ( SELECT TOTAL(wears) FROM wear_summaries s WHERE s.item_id = garments.id )",
+        ),
+    ),
+    (
+        "items with fewer hours of use than are recorded",
+        "garments",
+        "total_hours < ( SELECT TOTAL(m.value) FROM wear_metrics m \
+         JOIN wear_events e ON e.id = m.event_id WHERE e.item_id = garments.id AND m.kind = 'hours' )",
+        Some(
+            "total_hours = ( SELECT TOTAL(m.value) FROM wear_metrics m \
+             JOIN wear_events e ON e.id = m.event_id WHERE e.item_id = garments.id AND m.kind = 'hours' )",
+        ),
+    ),
+    (
+        "items with fewer wears since their last wash than are recorded",
+        "garments",
+        "count < ( SELECT COUNT(*) FROM wear_events e WHERE e.item_id = garments.id \
+         AND ( garments.wash IS NULL OR julianday(e.worn_at) > julianday(garments.wash) ) )",
+        Some(
+            "count = ( SELECT COUNT(*) FROM wear_events e WHERE e.item_id = garments.id \
+             AND ( garments.wash IS NULL OR julianday(e.worn_at) > julianday(garments.wash) ) )",
+        ),
+    ),
+    (
+        "items with more wears since their last wash than in total",
+        "garments",
+        "count > total",
+        Some("count = total"),
+    ),
+];
+
 impl<'c> FromRow<'c, SqliteRow<'c>> for Item {
     fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
         Ok(Item {
@@ -25,17 +453,16 @@ impl<'c> FromRow<'c, SqliteRow<'c>> for Item {
             description: row.try_get::<String, _>("description")?,
             count: row.try_get::<i32, _>("count")? as usize,
             total_count: row.try_get::<i32, _>("total")? as usize,
+            total_hours: row.try_get::<f64, _>("total_hours")?.max(0.),
             last_wash: row
                 .try_get::<Option<&str>, _>("wash")?
                 .map(DateTime::parse_from_rfc3339)
-                .map(Result::ok)
-                .flatten()
+                .and_then(Result::ok)
                 .map(|d| d.with_timezone(&Utc)),
             last_wear: row
                 .try_get::<Option<&str>, _>("wear")?
                 .map(DateTime::parse_from_rfc3339)
-                .map(Result::ok)
-                .flatten()
+                .and_then(Result::ok)
                 .map(|d| d.with_timezone(&Utc)),
             color: row.try_get::<String, _>("color")?,
             tags: row
@@ -43,6 +470,152 @@ impl<'c> FromRow<'c, SqliteRow<'c>> for Item {
                 .split(',')
                 .map(ToOwned::to_owned)
                 .collect(),
+            token: row
+                .try_get::<Option<String>, _>("token")?
+                .unwrap_or_default(),
+            retailer: row.try_get::<String, _>("retailer")?,
+            product_url: row.try_get::<String, _>("product_url")?,
+            return_by: row
+                .try_get::<Option<&str>, _>("return_by")?
+                .and_then(|d| d.parse().ok()),
+            warranty_until: row
+                .try_get::<Option<&str>, _>("warranty_until")?
+                .and_then(|d| d.parse().ok()),
+            care_wash: row.try_get::<String, _>("care_wash")?,
+            care_bleach: row.try_get::<String, _>("care_bleach")?,
+            care_dry: row.try_get::<String, _>("care_dry")?,
+            care_iron: row.try_get::<String, _>("care_iron")?,
+            care_dry_clean: row.try_get::<String, _>("care_dry_clean")?,
+            chest: row.try_get::<Option<f64>, _>("chest")?,
+            waist: row.try_get::<Option<f64>, _>("waist")?,
+            inseam: row.try_get::<Option<f64>, _>("inseam")?,
+            sleeve: row.try_get::<Option<f64>, _>("sleeve")?,
+            wash_after: row
+                .try_get::<Option<i32>, _>("wash_after")?
+                .map(|n| n.max(0) as u32),
+            quantity: row.try_get::<i32, _>("quantity")?.max(1) as u32,
+            purchased_on: row
+                .try_get::<Option<&str>, _>("purchased_on")?
+                .and_then(|d| d.parse().ok()),
+            lifespan_months: row
+                .try_get::<Option<i32>, _>("lifespan_months")?
+                .map(|n| n.max(0) as u32),
+            lifespan_wears: row
+                .try_get::<Option<i32>, _>("lifespan_wears")?
+                .map(|n| n.max(0) as u32),
+            price: row.try_get::<Option<f64>, _>("price")?,
+            currency: row.try_get::<String, _>("currency")?,
+            condition: row
+                .try_get::<Option<&str>, _>("condition")?
+                .and_then(|c| c.parse::<Condition>().ok()),
+            tracking: row
+                .try_get::<&str, _>("tracking")?
+                .parse()
+                .unwrap_or_default(),
+            warmth: row
+                .try_get::<Option<&str>, _>("warmth")?
+                .and_then(|w| w.parse::<Warmth>().ok()),
+            seasons: row
+                .try_get::<&str, _>("seasons")?
+                .split(',')
+                .filter_map(|s| s.trim().parse::<Season>().ok())
+                .collect(),
+            replaced_by: row
+                .try_get::<Option<i32>, _>("replaced_by")?
+                .map(|id| id as usize),
+            resoled_at: row
+                .try_get::<Option<&str>, _>("resoled_at")?
+                .map(DateTime::parse_from_rfc3339)
+                .and_then(Result::ok)
+                .map(|d| d.with_timezone(&Utc)),
+            archived_at: row
+                .try_get::<Option<&str>, _>("archived_at")?
+                .map(DateTime::parse_from_rfc3339)
+                .and_then(Result::ok)
+                .map(|d| d.with_timezone(&Utc)),
+            wishlisted_at: row
+                .try_get::<Option<&str>, _>("wishlisted_at")?
+                .map(DateTime::parse_from_rfc3339)
+                .and_then(Result::ok)
+                .map(|d| d.with_timezone(&Utc)),
+            deleted_at: row
+                .try_get::<Option<&str>, _>("deleted_at")?
+                .map(DateTime::parse_from_rfc3339)
+                .and_then(Result::ok)
+                .map(|d| d.with_timezone(&Utc)),
+            // only some queries add it up
+            cleaning_cost: match row.try_get::<f64, _>("cleaning_cost") {
+                Ok(cost) => cost,
+                Err(sqlx::Error::ColumnNotFound(_)) => 0.,
+                Err(e) => return Err(e),
+            },
+        })
+    }
+}
+
+/// One item in a chain of replacements, numbered relative to the item the chain was built from
+/// (older items have negative generations).
+pub(crate) struct Generation {
+    pub(crate) item: Item,
+    pub(crate) generation: i32,
+    pub(crate) first_wear: Option<DateTime<Utc>>,
+}
+
+impl<'c> FromRow<'c, SqliteRow<'c>> for Generation {
+    fn from_row(row: &SqliteRow<'c>) -> Result<Self, sqlx::Error> {
+        Ok(Generation {
+            item: Item::from_row(row)?,
+            generation: row.try_get::<i32, _>("generation")?,
+            first_wear: row
+                .try_get::<Option<&str>, _>("first_wear")?
+                .map(DateTime::parse_from_rfc3339)
+                .and_then(Result::ok)
+                .map(|d| d.with_timezone(&Utc)),
+        })
+    }
+}
+
+/// Items saved together so they can be logged as one. Items in the trash are left out.
+#[derive(Debug, Serialize)]
+pub(crate) struct Outfit {
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    pub(crate) items: Vec<OutfitItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct OutfitItem {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) color: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Attachment {
+    pub(crate) id: usize,
+    pub(crate) item_id: usize,
+    pub(crate) file_name: String,
+    pub(crate) content_type: String,
+    pub(crate) size: usize,
+    #[serde(skip)]
+    pub(crate) stored_as: String,
+    pub(crate) uploaded_at: String,
+    #[serde(skip)]
+    pub(crate) digest: Option<String>,
+}
+
+impl<'c> FromRow<'c, SqliteRow<'c>> for Attachment {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Attachment {
+            id: row.try_get::<i32, _>("id")? as usize,
+            item_id: row.try_get::<i32, _>("item_id")? as usize,
+            file_name: row.try_get::<String, _>("file_name")?,
+            content_type: row.try_get::<String, _>("content_type")?,
+            size: row.try_get::<i64, _>("size")? as usize,
+            stored_as: row.try_get::<String, _>("stored_as")?,
+            uploaded_at: row.try_get::<String, _>("uploaded_at")?,
+            digest: row.try_get::<Option<String>, _>("digest")?,
         })
     }
 }
@@ -66,25 +639,166 @@ impl Display for ConnectionError {
     }
 }
 
-#[derive(Clone)]
-pub(crate) struct Connection(SqlitePool);
+async fn migrate(pool: &SqlitePool, backup: Option<&Path>) -> anyhow::Result<()> {
+    // read to the end, so the statement isn't left in progress to block the backup
+    let applied = sqlx::query_as::<_, (i32,)>("PRAGMA user_version")
+        .fetch_all(pool)
+        .await?
+        .pop()
+        .map_or(0, |(version,)| version);
 
-impl Connection {
-    pub(crate) async fn new(data_path: Option<PathBuf>) -> anyhow::Result<Self> {
-        const PROTOCOL: &str = "sqlite://";
+    if applied as usize > MIGRATIONS.len() {
+        anyhow::bail!(
+            "Database is at schema version {}, but this version of wear only knows up to {}. Upgrade wear before using it with this database.",
+            applied,
+            MIGRATIONS.len()
+        );
+    }
 
-        let (directory, file_name) = super::location::database_file(data_path).await?;
-        fs::create_dir_all(&directory).await?;
+    if let Some(backup) = backup.filter(|_| (applied as usize) < MIGRATIONS.len()) {
+        back_up(pool, backup)
+            .await
+            .context("Failed to back up database before migrating it")?;
+        eprintln!("Backed up database to {}", backup.display());
+    }
+
+    // SQLite is built to enforce foreign keys, and a migration that rebuilds a table would take
+    // everything referring to it along when it drops the old one. This can't be changed inside a
+    // transaction, so it's done for the one connection the migrations use.
+    let mut conn = pool.acquire().await?;
+    conn.execute("PRAGMA foreign_keys = OFF").await?;
+
+    for (idx, (_, migration)) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        eprintln!("Applying migration {} of {}", idx + 1, MIGRATIONS.len());
+
+        let mut tx = sqlx::Connection::begin(conn).await?;
+        tx.execute(*migration).await?;
+        tx.execute(format!("PRAGMA user_version = {}", idx + 1).as_str())
+            .await?;
+        conn = tx.commit().await?;
+    }
+
+    conn.execute("PRAGMA foreign_keys = ON").await?;
+    Ok(())
+}
+
+/// Copy a database into a new file, consistently even if it's in use. Like the backup API, but
+/// available through sqlx.
+async fn back_up(pool: &SqlitePool, to: &Path) -> anyhow::Result<()> {
+    if let Some(directory) = to.parent() {
+        fs::create_dir_all(directory).await?;
+    }
+
+    let to = to
+        .to_str()
+        .ok_or_else(|| ConnectionError::Utf8(to.into()))?;
+    sqlx::query("VACUUM INTO ?").bind(to).execute(pool).await?;
+    Ok(())
+}
+
+fn url(file: &Path) -> Result<String, ConnectionError> {
+    const PROTOCOL: &str = "sqlite://";
+
+    let mut db_path = OsString::from(PROTOCOL);
+    db_path.push(file);
 
-        let mut file = directory;
-        file.push(file_name);
+    db_path.into_string().map_err(ConnectionError::Utf8)
+}
+
+/// A schema change that a database hasn't had yet.
+pub(crate) struct Migration {
+    /// Its position in the list, which is the schema version it brings a database up to
+    pub(crate) version: usize,
+    pub(crate) name: &'static str,
+    pub(crate) sql: &'static str,
+}
+
+/// Everything that would be done to a database on the next start, without doing any of it.
+/// Returns nothing if it's up to date, and starts with the base schema if it doesn't exist yet.
+pub(crate) async fn pending_migrations(file: &Path) -> anyhow::Result<Vec<Migration>> {
+    let mut pending = Vec::new();
+
+    let applied = if fs::metadata(file).await.is_ok() {
+        let health = health(file).await?;
+        if health.schema_version > health.latest_version {
+            anyhow::bail!(
+                "Database is at schema version {}, but this version of wear only knows up to {}",
+                health.schema_version,
+                health.latest_version
+            );
+        }
+        health.schema_version
+    } else {
+        pending.push(Migration {
+            version: 0,
+            name: "schema",
+            sql: include_str!("./schema.sql"),
+        });
+        0
+    };
+
+    for (idx, (name, sql)) in MIGRATIONS.iter().enumerate().skip(applied) {
+        pending.push(Migration {
+            version: idx + 1,
+            name,
+            sql,
+        });
+    }
+
+    Ok(pending)
+}
+
+/// What `wear doctor` finds out about an existing database.
+pub(crate) struct Health {
+    pub(crate) schema_version: usize,
+    pub(crate) latest_version: usize,
+    /// What SQLite's quick integrity check has to say, which is "ok" if all is well
+    pub(crate) integrity: String,
+}
+
+/// Look at an existing database without changing anything in it.
+pub(crate) async fn health(file: &Path) -> anyhow::Result<Health> {
+    let pool = SqlitePool::new(&url(file)?).await?;
+
+    let (schema_version,): (i32,) = sqlx::query_as("PRAGMA user_version")
+        .fetch_one(&pool)
+        .await?;
+    let integrity = sqlx::query_as::<_, (String,)>("PRAGMA quick_check")
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|(line,)| line)
+        .collect::<Vec<_>>()
+        .join("; ");
+    pool.close().await;
+
+    Ok(Health {
+        schema_version: schema_version as usize,
+        latest_version: MIGRATIONS.len(),
+        integrity,
+    })
+}
+
+/// The database, and the collation chosen in the settings, which every sorted listing needs.
+#[derive(Clone)]
+pub(crate) struct Connection(SqlitePool, Arc<RwLock<Collation>>);
+
+impl Connection {
+    pub(crate) async fn new(directory: &Path, file_name: &OsStr) -> anyhow::Result<Self> {
+        fs::create_dir_all(directory).await?;
 
-        let mut db_path = OsString::from(PROTOCOL);
-        db_path.push(file);
+        let file = directory.join(file_name);
+        let string_path = url(&file)?;
 
-        let string_path = db_path
-            .into_string()
-            .map_err(ConnectionError::Utf8)?;
+        // a new database has nothing in it worth saving
+        let backup = match fs::metadata(&file).await {
+            Ok(_) => {
+                let mut name = file.file_stem().unwrap_or_default().to_os_string();
+                name.push(Utc::now().format("-%Y%m%dT%H%M%SZ.db").to_string());
+                Some(directory.join("backups").join(name))
+            }
+            Err(_) => None,
+        };
 
         eprintln!("Connecting to database at {}", string_path);
         let before = Instant::now();
@@ -107,9 +821,25 @@ impl Connection {
             .await
             .context("Failed to apply schema to database")?;
 
+        migrate(&pool, backup.as_deref())
+            .await
+            .context("Failed to migrate database")?;
+
         eprintln!("Done after {}ms", before.elapsed().as_millis());
 
-        Ok(Self(pool))
+        let conn = Self(pool, Arc::default());
+        conn.set_collation(conn.get_settings().await?.collation);
+        Ok(conn)
+    }
+
+    fn collation(&self) -> Collation {
+        self.1.read().map(|c| *c).unwrap_or_default()
+    }
+
+    fn set_collation(&self, collation: Collation) {
+        if let Ok(mut c) = self.1.write() {
+            *c = collation;
+        }
     }
 
     pub(crate) async fn close(&self) {
@@ -128,61 +858,351 @@ impl Connection {
         );
     }
 
+    /// Every item, sorted by each key in turn. `ascending` applies to the first one; ties are
+    /// always broken in the natural order.
     pub(crate) async fn get_all(
         &self,
-        order: &Option<SortItems>,
-        mut ascending: bool,
+        order: &[SortItems],
+        ascending: bool,
     ) -> sqlx::Result<Vec<Item>> {
-        let mut cmd = "SELECT * FROM garments".to_string();
-
-        if let Some(column) = order {
-            cmd += " ORDER BY ";
-            cmd += match column {
-                SortItems::Name => "name",
-                SortItems::Count => "count",
-
-                // values stored as datetimes are (to the user) in reverse sort order
-                SortItems::Wear => {
-                    ascending ^= true;
-                    "datetime(wear)"
-                }
-                SortItems::Wash => {
-                    ascending ^= true;
-                    "datetime(wash)"
-                }
+        self.get_view(&ListFilter::default(), order, ascending, Nulls::Last)
+            .await
+    }
+
+    /// Like `get_all`, but narrowed down by a filter.
+    pub(crate) async fn get_view(
+        &self,
+        filter: &ListFilter,
+        order: &[SortItems],
+        ascending: bool,
+        nulls: Nulls,
+    ) -> sqlx::Result<Vec<Item>> {
+        let (conditions, values) = filter.conditions();
+        let cmd = list_query(&conditions, order, ascending, nulls, self.collation());
+        let mut query = sqlx::query_as(&cmd);
+        for value in values {
+            query = query.bind(value);
+        }
+        query.fetch_all(&self.0).await
+    }
+
+    /// Up to `limit` items matching a filter, on one side of `at` (or from the start, without
+    /// one), and cursors for the pages before and after them, if there are any.
+    pub(crate) async fn get_page(
+        &self,
+        filter: &ListFilter,
+        order: &Option<SortItems>,
+        ascending: bool,
+        at: Option<&Cursor>,
+        limit: usize,
+    ) -> sqlx::Result<(Vec<Item>, Option<Cursor>, Option<Cursor>)> {
+        // NULL already sorts first, so the same order can be kept while giving every row a key
+        // that compares as a row value
+        let (key, ascending) = match order {
+            Some(order) => {
+                let (expression, ascending) = sort_expression(order, ascending);
+                (
+                    format!(
+                        "IFNULL({}, ''){}",
+                        expression,
+                        collate(order, self.collation())
+                    ),
+                    ascending,
+                )
+            }
+            None => ("id".to_string(), true),
+        };
+        // a page before a cursor is read backwards from it, and turned around afterwards
+        let before = at.is_some_and(|c| c.before);
+        let forwards = ascending != before;
+        let direction = if forwards { "ASC" } else { "DESC" };
+
+        let (mut conditions, values) = filter.conditions();
+        let position = at.filter(|c| !c.key.is_null());
+        if position.is_some() {
+            conditions.push(format!(
+                "({}, id) {} (?, ?)",
+                key,
+                if forwards { ">" } else { "<" }
+            ));
+        }
+
+        let mut cmd = list_query(&conditions, &[], true, Nulls::Last, Collation::Binary);
+        cmd += &format!(
+            " ORDER BY {key} {dir}, id {dir} LIMIT ?",
+            key = key,
+            dir = direction
+        );
+
+        let mut query = sqlx::query_as(&cmd);
+        for value in values {
+            query = query.bind(value);
+        }
+        if let Some(cursor) = position {
+            query = match &cursor.key {
+                serde_json::Value::Number(n) => query.bind(n.as_i64().unwrap_or_default()),
+                serde_json::Value::String(s) => query.bind(s.clone()),
+                _ => query.bind(""),
             };
-            cmd += if ascending { " ASC" } else { " DESC" };
+            query = query.bind(cursor.id as i32);
+        }
+        // one more than asked for, to know whether there's another page past these
+        let mut items: Vec<Item> = query.bind(limit as i64 + 1).fetch_all(&self.0).await?;
+        let more = items.len() > limit;
+        items.truncate(limit);
+        if before {
+            items.reverse();
         }
 
-        sqlx::query_as(&cmd).fetch_all(&self.0).await
+        let (first, last) = match (items.first(), items.last()) {
+            (Some(first), Some(last)) => (first.id, last.id),
+            _ => return Ok((items, None, None)),
+        };
+        // going backwards, there's more before; otherwise there's more after, and whatever came
+        // before the cursor
+        let (earlier, later) = match before {
+            true => (more, position.is_some()),
+            false => (at.is_some(), more),
+        };
+        let prev = match earlier {
+            true => Some(self.cursor(order, &key, first, true).await?),
+            false => None,
+        };
+        let next = match later {
+            true => Some(self.cursor(order, &key, last, false).await?),
+            false => None,
+        };
+
+        Ok((items, prev, next))
     }
 
-    pub(crate) async fn new_item(
+    /// A cursor for the items on one side of an item in a listing sorted by `key`.
+    async fn cursor(
         &self,
-        Item {
-            name,
-            description,
-            color,
-            tags,
-            ..
-        }: Item,
-    ) -> ExecResult {
-        sqlx::query("INSERT INTO garments ( name, description, color, tags ) VALUES ( ?, ?, ?, ? )")
-            .bind(name)
-            .bind(description)
-            .bind(color)
-            .bind(tags.join(","))
-            .execute(&self.0)
-            .await
+        order: &Option<SortItems>,
+        key: &str,
+        id: usize,
+        before: bool,
+    ) -> sqlx::Result<Cursor> {
+        let cmd = format!("SELECT {} FROM garments WHERE id = ?", key);
+        let key = match order {
+            Some(SortItems::Count) | Some(SortItems::Total) | None => {
+                let (count,) = sqlx::query_as::<_, (i64,)>(&cmd)
+                    .bind(id as i32)
+                    .fetch_one(&self.0)
+                    .await?;
+                serde_json::Value::from(count)
+            }
+            Some(_) => {
+                let (text,) = sqlx::query_as::<_, (String,)>(&cmd)
+                    .bind(id as i32)
+                    .fetch_one(&self.0)
+                    .await?;
+                serde_json::Value::from(text)
+            }
+        };
+
+        Ok(Cursor { key, id, before })
+    }
+
+    /// One page of a filtered listing, along with how many items match the filter in all.
+    pub(crate) async fn get_view_page(
+        &self,
+        filter: &ListFilter,
+        order: &[SortItems],
+        ascending: bool,
+        nulls: Nulls,
+        limit: usize,
+        offset: usize,
+    ) -> sqlx::Result<(Vec<Item>, usize)> {
+        let (conditions, values) = filter.conditions();
+        let mut cmd = list_query(&conditions, order, ascending, nulls, self.collation());
+        // break ties so that pages don't overlap
+        cmd += if !order.is_empty() {
+            ", id"
+        } else {
+            " ORDER BY id"
+        };
+        cmd += " LIMIT ? OFFSET ?";
+
+        let mut query = sqlx::query_as(&cmd);
+        for value in values {
+            query = query.bind(value);
+        }
+        let items = query
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.0)
+            .await?;
+
+        Ok((items, self.count_items(filter).await?))
+    }
+
+    /// How many items match a filter, or in total.
+    pub(crate) async fn count_items(&self, filter: &ListFilter) -> sqlx::Result<usize> {
+        let (conditions, values) = filter.conditions();
+        let mut cmd = "SELECT COUNT(*) FROM garments".to_string();
+        if !conditions.is_empty() {
+            cmd += " WHERE ";
+            cmd += &conditions.join(" AND ");
+        }
+
+        let mut query = sqlx::query_as::<_, (i64,)>(&cmd);
+        for value in values {
+            query = query.bind(value);
+        }
+        let (total,) = query.fetch_one(&self.0).await?;
+
+        Ok(total as usize)
+    }
+
+    pub(crate) async fn new_item(&self, item: Item) -> sqlx::Result<usize> {
+        let mut tx = self.0.begin().await?;
+        let id = insert_item(&mut tx, item).await?;
+        tx.commit().await?;
+
+        self.index_item(id).await?;
+        Ok(id)
+    }
+
+    /// Add an item with the same details as an existing one, but none of its history, and
+    /// return its id. The copy's name says it's a copy.
+    pub(crate) async fn clone_item(&self, item_id: usize) -> sqlx::Result<usize> {
+        let mut item = self.get_item(item_id).await?;
+        item.name = format!("{} (copy)", item.name);
+
+        let values = self.get_custom_values(item_id).await?;
+
+        let mut tx = self.0.begin().await?;
+        let id = insert_item(&mut tx, item).await?;
+        write_custom_values(&mut tx, id, &values).await?;
+        tx.commit().await?;
+
+        self.index_item(id).await?;
+        Ok(id)
     }
 
     pub(crate) async fn get_item(&self, item_id: usize) -> sqlx::Result<Item> {
-        sqlx::query_as("SELECT * FROM garments WHERE id = ?")
-            .bind(item_id as i32)
+        sqlx::query_as(&format!(
+            "SELECT *, {} FROM garments WHERE id = ?",
+            CLEANING_COST
+        ))
+        .bind(item_id as i32)
+        .fetch_one(&self.0)
+        .await
+    }
+
+    pub(crate) async fn get_item_by_token(&self, token: &str) -> sqlx::Result<Item> {
+        sqlx::query_as("SELECT * FROM garments WHERE token = ? AND deleted_at IS NULL AND wishlisted_at IS NULL")
+            .bind(token)
             .fetch_one(&self.0)
             .await
     }
 
+    /// Look up a token that used to belong to an item, as `(item id, item name, revoked)`.
+    pub(crate) async fn get_retired_token(
+        &self,
+        token: &str,
+    ) -> sqlx::Result<Option<(i32, String, bool)>> {
+        sqlx::query_as(
+            r#"
+            SELECT g.id, g.name, r.revoked
+            FROM retired_tokens r JOIN garments g ON g.id = r.item_id
+            WHERE r.token = ? AND g.deleted_at IS NULL
+        "#,
+        )
+        .bind(token)
+        .fetch_optional(&self.0)
+        .await
+    }
+
+    /// Record that a request with this key is doing `action`, returning false if one already
+    /// did. Keys are forgotten after a day.
+    pub(crate) async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        action: &str,
+    ) -> sqlx::Result<bool> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query(
+            "DELETE FROM idempotency_keys WHERE datetime(claimed_at) < datetime('now', '-1 day')",
+        )
+        .execute(&mut tx)
+        .await?;
+        let claimed = sqlx::query(
+            "INSERT OR IGNORE INTO idempotency_keys ( key, action, claimed_at ) VALUES ( ?, ?, ? )",
+        )
+        .bind(key)
+        .bind(action)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(claimed == 1)
+    }
+
+    pub(crate) async fn release_idempotency_key(&self, key: &str, action: &str) -> ExecResult {
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = ? AND action = ?")
+            .bind(key)
+            .bind(action)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Retire an item's tag token, giving it a fresh one unless `revoke` is set.
+    pub(crate) async fn retire_token(&self, item_id: usize, revoke: bool) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO retired_tokens ( token, item_id, retired_at, revoked )
+            SELECT token, id, ?, ? FROM garments WHERE id = ? AND token IS NOT NULL
+        "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(revoke)
+        .bind(item_id as i32)
+        .execute(&mut tx)
+        .await?;
+
+        let changed = sqlx::query(if revoke {
+            "UPDATE garments SET token = NULL WHERE id = ?"
+        } else {
+            "UPDATE garments SET token = lower(hex(randomblob(6))) WHERE id = ?"
+        })
+        .bind(item_id as i32)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Items with a return or warranty deadline on or after the given day.
+    pub(crate) async fn get_with_deadlines(&self, from: NaiveDate) -> sqlx::Result<Vec<Item>> {
+        sqlx::query_as(
+            "SELECT * FROM garments \
+             WHERE (return_by >= ?1 OR warranty_until >= ?1) \
+             AND deleted_at IS NULL AND wishlisted_at IS NULL",
+        )
+        .bind(from.to_string())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Items with an expected lifespan to measure them against.
+    pub(crate) async fn get_with_lifespans(&self) -> sqlx::Result<Vec<Item>> {
+        sqlx::query_as(
+            "SELECT * FROM garments \
+             WHERE (lifespan_months IS NOT NULL OR lifespan_wears IS NOT NULL) \
+             AND deleted_at IS NULL AND wishlisted_at IS NULL",
+        )
+        .fetch_all(&self.0)
+        .await
+    }
+
     pub(crate) async fn update_item(
         &self,
         Item {
@@ -191,13 +1211,43 @@ impl Connection {
             description,
             color,
             tags,
+            retailer,
+            product_url,
+            return_by,
+            warranty_until,
+            care_wash,
+            care_bleach,
+            care_dry,
+            care_iron,
+            care_dry_clean,
+            chest,
+            waist,
+            inseam,
+            sleeve,
+            wash_after,
+            quantity,
+            purchased_on,
+            lifespan_months,
+            lifespan_wears,
+            price,
+            currency,
+            condition,
+            tracking,
+            warmth,
+            seasons,
             ..
         }: Item,
     ) -> ExecResult {
-        sqlx::query(
+        let before = self.get_item(id).await?;
+        let changed = sqlx::query(
             r#"
             UPDATE garments
-            SET color = ?, name = ?, description = ?, tags = ?
+            SET color = ?, name = ?, description = ?, tags = ?, retailer = ?, product_url = ?,
+                return_by = ?, warranty_until = ?, care_wash = ?, care_bleach = ?, care_dry = ?,
+                care_iron = ?, care_dry_clean = ?, chest = ?, waist = ?, inseam = ?, sleeve = ?,
+                wash_after = ?, quantity = ?, purchased_on = ?, lifespan_months = ?,
+                lifespan_wears = ?, price = ?, currency = ?, condition = ?, tracking = ?,
+                warmth = ?, seasons = ?, modified_at = ?
             WHERE id = ?
         "#,
         )
@@ -205,33 +1255,2867 @@ impl Connection {
         .bind(name)
         .bind(description)
         .bind(tags.join(","))
+        .bind(retailer)
+        .bind(product_url)
+        .bind(return_by.map(|d| d.to_string()))
+        .bind(warranty_until.map(|d| d.to_string()))
+        .bind(care_wash)
+        .bind(care_bleach)
+        .bind(care_dry)
+        .bind(care_iron)
+        .bind(care_dry_clean)
+        .bind(chest)
+        .bind(waist)
+        .bind(inseam)
+        .bind(sleeve)
+        .bind(wash_after.map(|n| n as i32))
+        .bind(quantity.max(1) as i32)
+        .bind(purchased_on.map(|d| d.to_string()))
+        .bind(lifespan_months.map(|n| n as i32))
+        .bind(lifespan_wears.map(|n| n as i32))
+        .bind(price)
+        .bind(currency)
+        .bind(condition.map(Condition::as_str))
+        .bind(tracking.as_str())
+        .bind(warmth.map(Warmth::as_str))
+        .bind(seasons_text(&seasons))
+        .bind(Utc::now().to_rfc3339())
         .bind(id as i32)
         .execute(&self.0)
-        .await
+        .await?;
+
+        self.record_edits(&before).await?;
+        self.index_item(id).await?;
+        Ok(changed)
     }
 
-    pub(crate) async fn delete_item(&self, item_id: usize) -> ExecResult {
-        sqlx::query("DELETE FROM garments WHERE id = ?")
-            .bind(item_id as i32)
+    /// Update only the columns present in a patch.
+    pub(crate) async fn patch_item(&self, item_id: usize, patch: &ItemPatch) -> ExecResult {
+        enum Value {
+            Text(Option<String>),
+            Real(Option<f64>),
+            Int(Option<i32>),
+        }
+        use Value::{Int, Real, Text};
+
+        let date = |d: &Option<NaiveDate>| Text(d.map(|d| d.to_string()));
+        let columns = vec![
+            ("name", patch.name.clone().map(|v| Text(Some(v)))),
+            (
+                "description",
+                patch.description.clone().map(|v| Text(Some(v))),
+            ),
+            ("color", patch.color.clone().map(|v| Text(Some(v)))),
+            ("tags", patch.tags.as_ref().map(|t| Text(Some(t.join(","))))),
+            ("retailer", patch.retailer.clone().map(|v| Text(Some(v)))),
+            (
+                "product_url",
+                patch.product_url.clone().map(|v| Text(Some(v))),
+            ),
+            ("return_by", patch.return_by.as_ref().map(date)),
+            ("warranty_until", patch.warranty_until.as_ref().map(date)),
+            ("care_wash", patch.care_wash.clone().map(|v| Text(Some(v)))),
+            (
+                "care_bleach",
+                patch.care_bleach.clone().map(|v| Text(Some(v))),
+            ),
+            ("care_dry", patch.care_dry.clone().map(|v| Text(Some(v)))),
+            ("care_iron", patch.care_iron.clone().map(|v| Text(Some(v)))),
+            (
+                "care_dry_clean",
+                patch.care_dry_clean.clone().map(|v| Text(Some(v))),
+            ),
+            ("chest", patch.chest.map(Real)),
+            ("waist", patch.waist.map(Real)),
+            ("inseam", patch.inseam.map(Real)),
+            ("sleeve", patch.sleeve.map(Real)),
+            (
+                "wash_after",
+                patch.wash_after.map(|n| Int(n.map(|n| n as i32))),
+            ),
+            (
+                "quantity",
+                patch.quantity.map(|n| Int(Some(n.max(1) as i32))),
+            ),
+            ("purchased_on", patch.purchased_on.as_ref().map(date)),
+            (
+                "lifespan_months",
+                patch.lifespan_months.map(|n| Int(n.map(|n| n as i32))),
+            ),
+            (
+                "lifespan_wears",
+                patch.lifespan_wears.map(|n| Int(n.map(|n| n as i32))),
+            ),
+            ("price", patch.price.map(Real)),
+            ("currency", patch.currency.clone().map(|v| Text(Some(v)))),
+            (
+                "condition",
+                patch
+                    .condition
+                    .map(|c| Text(c.map(|c| c.as_str().to_string()))),
+            ),
+            (
+                "tracking",
+                patch.tracking.map(|t| Text(Some(t.as_str().to_string()))),
+            ),
+            (
+                "warmth",
+                patch
+                    .warmth
+                    .map(|w| Text(w.map(|w| w.as_str().to_string()))),
+            ),
+            (
+                "seasons",
+                patch
+                    .seasons
+                    .as_deref()
+                    .map(|s| Text(Some(seasons_text(s)))),
+            ),
+            ("modified_at", Some(Text(Some(Utc::now().to_rfc3339())))),
+        ]
+        .into_iter()
+        .filter_map(|(column, value)| value.map(|v| (column, v)))
+        .collect::<Vec<_>>();
+
+        // there's always the modification time, so check for anything else
+        if columns.len() == 1 {
+            return Ok(0);
+        }
+
+        let cmd = format!(
+            "UPDATE garments SET {} WHERE id = ?",
+            columns
+                .iter()
+                .map(|(column, _)| format!("{} = ?", column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let mut query = sqlx::query(&cmd);
+        for (_, value) in columns {
+            query = match value {
+                Text(v) => query.bind(v),
+                Real(v) => query.bind(v),
+                Int(v) => query.bind(v),
+            };
+        }
+
+        let before = self.get_item(item_id).await?;
+        let changed = query.bind(item_id as i32).execute(&self.0).await?;
+
+        self.record_edits(&before).await?;
+        self.index_item(item_id).await?;
+        Ok(changed)
+    }
+
+    /// Keep what an edit changed, given the item as it was before.
+    async fn record_edits(&self, before: &Item) -> sqlx::Result<()> {
+        let after = self.get_item(before.id).await?;
+        let now = Utc::now().to_rfc3339();
+
+        for Edit {
+            field,
+            before: old_value,
+            after: new_value,
+        } in edits::diff(before, &after)
+        {
+            sqlx::query(
+                "INSERT INTO item_edits ( item_id, edited_at, field, old_value, new_value ) \
+                 VALUES ( ?, ?, ?, ?, ? )",
+            )
+            .bind(before.id as i32)
+            .bind(&now)
+            .bind(field)
+            .bind(old_value)
+            .bind(new_value)
             .execute(&self.0)
-            .await
+            .await?;
+        }
+
+        Ok(())
     }
 
-    pub(crate) async fn log_wear(&self, item_id: usize) -> ExecResult {
-        sqlx::query(
-            "UPDATE garments SET count = count + 1, total = total + 1, wear = ? WHERE id = ?",
+    /// Everything edits have changed on an item, newest first, with when they were made.
+    pub(crate) async fn get_item_edits(&self, item_id: usize) -> sqlx::Result<Vec<(String, Edit)>> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT edited_at, field, old_value, new_value FROM item_edits \
+             WHERE item_id = ? ORDER BY edited_at DESC, id",
         )
-        .bind(Utc::now().to_rfc3339())
         .bind(item_id as i32)
-        .execute(&self.0)
-        .await
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(at, field, before, after)| {
+                (
+                    at,
+                    Edit {
+                        field,
+                        before,
+                        after,
+                    },
+                )
+            })
+            .collect())
     }
 
-    pub(crate) async fn log_wash(&self, item_id: usize) -> ExecResult {
-        sqlx::query("UPDATE garments SET count = 0, wash = ? WHERE id = ?")
-            .bind(Utc::now().to_rfc3339())
+    /// Replace the words an item can be searched by, and the color family it can be filtered by,
+    /// with those it has now.
+    pub(crate) async fn index_item(&self, item_id: usize) -> sqlx::Result<()> {
+        let mut tx = self.0.begin().await?;
+
+        // read everything, so the statement doesn't hold on to the table while we write
+        let row = sqlx::query_as::<_, (String, String, String, String)>(
+            "SELECT name, description, tags, color FROM garments WHERE id = ?",
+        )
+        .bind(item_id as i32)
+        .fetch_all(&mut tx)
+        .await?
+        .pop();
+
+        sqlx::query("DELETE FROM search_terms WHERE item_id = ?")
+            .bind(item_id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        if let Some((name, description, tags, color)) = row {
+            sqlx::query("UPDATE garments SET color_family = ? WHERE id = ?")
+                .bind(colors::family(&color))
+                .bind(item_id as i32)
+                .execute(&mut tx)
+                .await?;
+
+            for term in search::terms(&name, &description, &tags) {
+                sqlx::query("INSERT OR IGNORE INTO search_terms ( term, item_id ) VALUES ( ?, ? )")
+                    .bind(term)
+                    .bind(item_id as i32)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Items that have nothing in the search index, or no color family.
+    pub(crate) async fn get_unindexed_items(&self) -> sqlx::Result<Vec<usize>> {
+        let rows = sqlx::query_as::<_, (i32,)>(
+            r#"
+            SELECT id FROM garments
+            WHERE id NOT IN (SELECT item_id FROM search_terms) OR color_family IS NULL
+        "#,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id as usize).collect())
+    }
+
+    /// Put an item away for good, or bring it back with `false`. Archived items keep their
+    /// history but are left out of listings.
+    pub(crate) async fn archive_item(&self, item_id: usize, archived: bool) -> ExecResult {
+        sqlx::query("UPDATE garments SET archived_at = ? WHERE id = ?")
+            .bind(archived.then(|| Utc::now().to_rfc3339()))
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Move an item to the trash, or take it back out with `false`. Items in the trash are left
+    /// out of everything but the trash itself until they're restored or deleted for good.
+    pub(crate) async fn trash_item(&self, item_id: usize, trashed: bool) -> ExecResult {
+        sqlx::query("UPDATE garments SET deleted_at = ? WHERE id = ?")
+            .bind(trashed.then(|| Utc::now().to_rfc3339()))
             .bind(item_id as i32)
             .execute(&self.0)
             .await
     }
+
+    /// Put an item on the wishlist, as something wanted but not bought yet. Wishlist items are
+    /// left out of everything but the wishlist, and can't be worn or washed.
+    pub(crate) async fn wishlist_item(&self, item_id: usize) -> ExecResult {
+        sqlx::query("UPDATE garments SET wishlisted_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Take an item off the wishlist because it was bought on the given day. It counts as added
+    /// to the wardrobe from now on.
+    pub(crate) async fn purchase_item(&self, item_id: usize, on: NaiveDate) -> ExecResult {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "UPDATE garments \
+             SET wishlisted_at = NULL, purchased_on = ?, created_at = ?, modified_at = ? \
+             WHERE id = ? AND wishlisted_at IS NOT NULL",
+        )
+        .bind(on.to_string())
+        .bind(&now)
+        .bind(&now)
+        .bind(item_id as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    /// Items moved to the trash before a point in time, as `(id, name)`.
+    pub(crate) async fn get_trashed_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> sqlx::Result<Vec<(i32, String)>> {
+        sqlx::query_as(
+            "SELECT id, name FROM garments WHERE datetime(deleted_at) < datetime(?) ORDER BY id",
+        )
+        .bind(before.to_rfc3339())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Delete an item for good, along with everything recorded about it. Only items already in the
+    /// trash can be.
+    pub(crate) async fn delete_item(&self, item_id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM garments WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(item_id as i32)
+            .execute(&mut tx)
+            .await?;
+        if deleted == 0 {
+            return Ok(0);
+        }
+
+        // only some builds of SQLite enforce foreign keys, so this doesn't count on them to cascade
+        sqlx::query(
+            "DELETE FROM wear_metrics WHERE event_id IN ( SELECT id FROM wear_events WHERE item_id = ? )",
+        )
+        .bind(item_id as i32)
+        .execute(&mut tx)
+        .await?;
+        for table in ITEM_TABLES {
+            sqlx::query(&format!("DELETE FROM {} WHERE item_id = ?", table))
+                .bind(item_id as i32)
+                .execute(&mut tx)
+                .await?;
+        }
+        sqlx::query("UPDATE garments SET replaced_by = NULL WHERE replaced_by = ?")
+            .bind(item_id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Every item linked to this one through replacements, oldest first.
+    pub(crate) async fn get_lineage(&self, item_id: usize) -> sqlx::Result<Vec<Generation>> {
+        sqlx::query_as(
+            r#"
+            WITH RECURSIVE
+              newer ( id, generation ) AS (
+                SELECT ?1, 0
+                UNION
+                SELECT g.replaced_by, newer.generation + 1
+                FROM garments g JOIN newer ON g.id = newer.id
+                WHERE g.replaced_by IS NOT NULL AND newer.generation < 100
+              ),
+              older ( id, generation ) AS (
+                SELECT ?1, 0
+                UNION
+                SELECT g.id, older.generation - 1
+                FROM garments g JOIN older ON g.replaced_by = older.id
+                WHERE older.generation > -100
+              )
+            SELECT
+              garments.*,
+              lineage.generation,
+              ( SELECT MIN(worn_at) FROM wear_events WHERE item_id = garments.id ) AS first_wear
+            FROM garments
+            JOIN ( SELECT * FROM newer UNION SELECT * FROM older ) AS lineage
+              ON lineage.id = garments.id
+            ORDER BY lineage.generation, garments.id
+        "#,
+        )
+        .bind(item_id as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Record that an item has been replaced by another (or clear that link).
+    pub(crate) async fn set_replacement(
+        &self,
+        item_id: usize,
+        replaced_by: Option<usize>,
+    ) -> anyhow::Result<u64> {
+        if let Some(new_id) = replaced_by {
+            let older = self.get_lineage(item_id).await?;
+            if older
+                .iter()
+                .any(|g| g.generation <= 0 && g.item.id == new_id)
+            {
+                anyhow::bail!(
+                    "Item {} can't be replaced by one of its own predecessors",
+                    item_id
+                );
+            }
+        }
+
+        Ok(
+            sqlx::query("UPDATE garments SET replaced_by = ? WHERE id = ?")
+                .bind(replaced_by.map(|id| id as i32))
+                .bind(item_id as i32)
+                .execute(&self.0)
+                .await?,
+        )
+    }
+
+    pub(crate) async fn get_attachments(&self, item_id: usize) -> sqlx::Result<Vec<Attachment>> {
+        sqlx::query_as("SELECT * FROM attachments WHERE item_id = ? ORDER BY id")
+            .bind(item_id as i32)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// Items that have a photo.
+    pub(crate) async fn get_items_with_photos(&self) -> sqlx::Result<Vec<(i32,)>> {
+        sqlx::query_as("SELECT DISTINCT item_id FROM attachments WHERE content_type LIKE 'image/%'")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// The latest image attached to an item.
+    pub(crate) async fn get_photo(&self, item_id: usize) -> sqlx::Result<Attachment> {
+        sqlx::query_as(
+            "SELECT * FROM attachments WHERE item_id = ? AND content_type LIKE 'image/%' ORDER BY id DESC LIMIT 1",
+        )
+        .bind(item_id as i32)
+        .fetch_one(&self.0)
+        .await
+    }
+
+    pub(crate) async fn get_attachment(
+        &self,
+        item_id: usize,
+        attachment_id: usize,
+    ) -> sqlx::Result<Attachment> {
+        sqlx::query_as("SELECT * FROM attachments WHERE item_id = ? AND id = ?")
+            .bind(item_id as i32)
+            .bind(attachment_id as i32)
+            .fetch_one(&self.0)
+            .await
+    }
+
+    pub(crate) async fn add_attachment(
+        &self,
+        item_id: usize,
+        file_name: &str,
+        content_type: &str,
+        size: usize,
+        stored_as: &str,
+        digest: &str,
+    ) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO attachments (
+                item_id, file_name, content_type, size, stored_as, uploaded_at, digest
+            )
+            VALUES ( ?, ?, ?, ?, ?, ?, ? )
+        "#,
+        )
+        .bind(item_id as i32)
+        .bind(file_name)
+        .bind(content_type)
+        .bind(size as i64)
+        .bind(stored_as)
+        .bind(Utc::now().to_rfc3339())
+        .bind(digest)
+        .execute(&self.0)
+        .await
+    }
+
+    /// Other items that have a photo with the given digest attached, as `(item id, name)`.
+    pub(crate) async fn get_photo_duplicates(
+        &self,
+        digest: &str,
+        except_item: usize,
+    ) -> sqlx::Result<Vec<(i32, String)>> {
+        sqlx::query_as(
+            r#"
+            SELECT DISTINCT g.id, g.name
+            FROM attachments a JOIN garments g ON g.id = a.item_id
+            WHERE a.digest = ? AND a.item_id != ? AND a.content_type LIKE 'image/%'
+                AND g.deleted_at IS NULL
+            ORDER BY g.name
+        "#,
+        )
+        .bind(digest)
+        .bind(except_item as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Attachments stored before digests were recorded.
+    pub(crate) async fn get_undigested_attachments(&self) -> sqlx::Result<Vec<Attachment>> {
+        sqlx::query_as("SELECT * FROM attachments WHERE digest IS NULL")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub(crate) async fn set_attachment_digest(
+        &self,
+        attachment_id: usize,
+        digest: &str,
+    ) -> ExecResult {
+        sqlx::query("UPDATE attachments SET digest = ? WHERE id = ?")
+            .bind(digest)
+            .bind(attachment_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn delete_attachment(&self, attachment_id: usize) -> ExecResult {
+        sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(attachment_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Record a wear of an item, today or on the day in `details`. For an item with several
+    /// identical pieces, that's a wear of whichever piece is next in turn, so its counts are shared
+    /// between them. A wear from before the last wash or the last wear still counts towards the
+    /// total, but leaves those as they are.
+    pub(crate) async fn log_wear(&self, item_id: usize, details: &WearDetails) -> ExecResult {
+        self.log_wear_many(&[(item_id, details)]).await
+    }
+
+    /// Record a wear of each item, as `log_wear` does, all or none of them. Returns how many were
+    /// worn.
+    pub(crate) async fn log_wear_many(&self, wears: &[(usize, &WearDetails)]) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+        let worn = record_wears(&mut tx, wears).await?;
+        tx.commit().await?;
+        Ok(worn)
+    }
+
+    /// Take back the wear of an item that was logged last, whatever day it was for, along with what
+    /// was measured on it. The wear only comes off the count since the last wash if it was worn
+    /// after that wash, and the last wear goes back to the latest one still recorded, if any.
+    pub(crate) async fn undo_wear(&self, item_id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let latest: Vec<(i64, String, f64)> = sqlx::query_as(
+            r#"
+            SELECT id, worn_at, (
+                SELECT TOTAL(value) FROM wear_metrics m WHERE m.event_id = e.id AND m.kind = ?
+            )
+            FROM wear_events e WHERE item_id = ?
+            ORDER BY id DESC LIMIT 1
+        "#,
+        )
+        .bind(Metric::Hours.as_str())
+        .bind(item_id as i32)
+        .fetch_all(&mut tx)
+        .await?;
+        let (event_id, worn_at, hours) = match latest.into_iter().next() {
+            Some(event) => event,
+            None => return Ok(0),
+        };
+
+        sqlx::query("DELETE FROM wear_metrics WHERE event_id = ?")
+            .bind(event_id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM wear_events WHERE id = ?")
+            .bind(event_id)
+            .execute(&mut tx)
+            .await?;
+
+        let changed = sqlx::query(
+            r#"
+            UPDATE garments SET
+                total = MAX(total - 1, 0),
+                total_hours = MAX(total_hours - ?3, 0),
+                count = CASE WHEN wash IS NULL OR julianday(?1) >= julianday(wash)
+                    THEN MAX(count - 1, 0) ELSE count END,
+                wear = (
+                    SELECT worn_at FROM wear_events e WHERE e.item_id = garments.id
+                    ORDER BY julianday(worn_at) DESC, id DESC LIMIT 1
+                )
+            WHERE id = ?2
+        "#,
+        )
+        .bind(&worn_at)
+        .bind(item_id as i32)
+        .bind(hours)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Totals for each kind of metric recorded against an item's wears, as `(kind, total, wears)`.
+    /// Only wears after `since` are counted, if given.
+    pub(crate) async fn get_metric_totals(
+        &self,
+        item_id: usize,
+        since: Option<DateTime<Utc>>,
+    ) -> sqlx::Result<Vec<(String, f64, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT m.kind, SUM(m.value), COUNT(*)
+            FROM wear_metrics m JOIN wear_events e ON e.id = m.event_id
+            WHERE e.item_id = ? AND ( ? IS NULL OR datetime(e.worn_at) > datetime(?) )
+            GROUP BY m.kind
+            ORDER BY m.kind
+        "#,
+        )
+        .bind(item_id as i32)
+        .bind(since.map(|t| t.to_rfc3339()))
+        .bind(since.map(|t| t.to_rfc3339()))
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Number of wears recorded for an item after the given time.
+    pub(crate) async fn count_wears_since(
+        &self,
+        item_id: usize,
+        since: DateTime<Utc>,
+    ) -> sqlx::Result<i32> {
+        let (count,): (i32,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM wear_events WHERE item_id = ? AND datetime(worn_at) > datetime(?)",
+        )
+        .bind(item_id as i32)
+        .bind(since.to_rfc3339())
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub(crate) async fn log_resole(&self, item_id: usize) -> ExecResult {
+        sqlx::query("UPDATE garments SET resoled_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn worn_since(&self, since: DateTime<Utc>) -> sqlx::Result<bool> {
+        let (count,): (i32,) =
+            sqlx::query_as("SELECT COUNT(*) FROM garments WHERE datetime(wear) >= datetime(?)")
+                .bind(since.to_rfc3339())
+                .fetch_one(&self.0)
+                .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Every recorded wear, oldest first, as `(item id, timestamp)` pairs.
+    pub(crate) async fn get_wear_events(&self) -> sqlx::Result<Vec<(i32, String)>> {
+        sqlx::query_as("SELECT item_id, worn_at FROM wear_events ORDER BY datetime(worn_at)")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// The most recent wears of one item, newest first, as `(timestamp, note)`.
+    pub(crate) async fn get_item_wears(
+        &self,
+        item_id: usize,
+        limit: u32,
+    ) -> sqlx::Result<Vec<(String, String)>> {
+        sqlx::query_as(
+            "SELECT worn_at, note FROM wear_events WHERE item_id = ? ORDER BY datetime(worn_at) DESC, id DESC LIMIT ?",
+        )
+        .bind(item_id as i32)
+        .bind(limit as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// The most recent washes of one item, newest first, as
+    /// `(timestamp, method, temperature, detergent, cost)`.
+    pub(crate) async fn get_item_washes(
+        &self,
+        item_id: usize,
+        limit: u32,
+    ) -> sqlx::Result<Vec<(String, String, Option<i32>, String, Option<f64>)>> {
+        sqlx::query_as(
+            r#"
+            SELECT washed_at, method, temperature, detergent, cost FROM wash_events
+            WHERE item_id = ?
+            ORDER BY datetime(washed_at) DESC, id DESC
+            LIMIT ?
+        "#,
+        )
+        .bind(item_id as i32)
+        .bind(limit as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Every wear and wash of one item that is still on record, oldest first.
+    pub(crate) async fn get_item_history(&self, item_id: usize) -> sqlx::Result<Vec<Event>> {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT kind, at, detail FROM (
+              SELECT 'wear' AS kind, worn_at AS at, note AS detail FROM wear_events WHERE item_id = ?
+              UNION ALL
+              SELECT 'wash', washed_at, trim(method || ' ' || detergent) FROM wash_events WHERE item_id = ?
+            )
+            ORDER BY datetime(at)
+        "#,
+        )
+        .bind(item_id as i32)
+        .bind(item_id as i32)
+        .fetch_all(&self.0)
+        .await?;
+
+        let mut events = rows
+            .into_iter()
+            .filter_map(|(kind, at, detail)| {
+                Some(Event {
+                    kind: if kind == "wash" {
+                        Kind::Wash
+                    } else {
+                        Kind::Wear
+                    },
+                    at: DateTime::parse_from_rfc3339(&at).ok()?.with_timezone(&Utc),
+                    detail,
+                })
+            })
+            .collect::<Vec<_>>();
+        // SQLite only compares datetimes to the second
+        events.sort_by_key(|e| e.at);
+        Ok(events)
+    }
+
+    /// Monthly wear totals for events that have been pruned, as `(item id, month, wears)`.
+    pub(crate) async fn get_wear_summaries(&self) -> sqlx::Result<Vec<(i32, String, i32)>> {
+        sqlx::query_as("SELECT item_id, month, wears FROM wear_summaries ORDER BY month")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// Roll wear events from before `cutoff` up into per-month totals, then delete them. Returns
+    /// the number of events removed.
+    pub(crate) async fn summarize_wears_before(&self, cutoff: DateTime<Utc>) -> ExecResult {
+        let cutoff = cutoff.to_rfc3339();
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO wear_summaries ( item_id, month, wears )
+            SELECT item_id, strftime('%Y-%m', worn_at), COUNT(*)
+            FROM wear_events
+            WHERE datetime(worn_at) < datetime(?)
+            GROUP BY 1, 2
+            ON CONFLICT ( item_id, month ) DO UPDATE SET wears = wears + excluded.wears
+        "#,
+        )
+        .bind(&cutoff)
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM wear_metrics WHERE event_id IN (
+                SELECT id FROM wear_events WHERE datetime(worn_at) < datetime(?)
+            )
+        "#,
+        )
+        .bind(&cutoff)
+        .execute(&mut tx)
+        .await?;
+        let pruned = sqlx::query("DELETE FROM wear_events WHERE datetime(worn_at) < datetime(?)")
+            .bind(&cutoff)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(pruned)
+    }
+
+    /// Record a wash, today or on the day in `details`. Only a wash after the last one resets the
+    /// count, and then only down to the wears recorded since.
+    pub(crate) async fn log_wash(&self, item_id: usize, details: &WashDetails) -> ExecResult {
+        self.log_wash_many(&[(item_id, details)]).await
+    }
+
+    /// Record a wash of each item, as `log_wash` does, all or none of them. Returns how many were
+    /// washed.
+    pub(crate) async fn log_wash_many(&self, washes: &[(usize, &WashDetails)]) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+        let mut washed = 0;
+
+        for &(item_id, details) in washes {
+            let at = utils::logged_at(details.date, Local::now()).to_rfc3339();
+            let changed = sqlx::query(
+                r#"
+                UPDATE garments SET
+                    count = CASE WHEN wash IS NULL OR julianday(?1) >= julianday(wash)
+                        THEN MIN(count, (
+                            SELECT COUNT(*) FROM wear_events e
+                            WHERE e.item_id = garments.id AND julianday(e.worn_at) > julianday(?1)
+                        ))
+                        ELSE count END,
+                    wash = CASE WHEN wash IS NULL OR julianday(?1) >= julianday(wash)
+                        THEN ?1 ELSE wash END
+                WHERE id = ?2 AND wishlisted_at IS NULL
+            "#,
+            )
+            .bind(&at)
+            .bind(item_id as i32)
+            .execute(&mut tx)
+            .await?;
+
+            if changed > 0 {
+                sqlx::query(
+                    r#"
+                    INSERT INTO wash_events ( item_id, washed_at, method, temperature, detergent, cost )
+                    VALUES ( ?, ?, ?, ?, ?, ? )
+                "#,
+                )
+                .bind(item_id as i32)
+                .bind(&at)
+                .bind(details.method.map(WashMethod::as_str).unwrap_or_default())
+                .bind(details.temperature.map(|t| t as i32))
+                .bind(details.detergent.trim())
+                .bind(details.cost.filter(|c| c.is_finite() && *c >= 0.0))
+                .execute(&mut tx)
+                .await?;
+            }
+            washed += changed;
+        }
+
+        tx.commit().await?;
+        Ok(washed)
+    }
+
+    /// Every outfit, by name, with the items in it.
+    pub(crate) async fn get_outfits(&self) -> sqlx::Result<Vec<Outfit>> {
+        self.outfits(None).await
+    }
+
+    pub(crate) async fn get_outfit(&self, id: usize) -> sqlx::Result<Outfit> {
+        self.outfits(Some(id))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn outfits(&self, id: Option<usize>) -> sqlx::Result<Vec<Outfit>> {
+        let rows: Vec<OutfitRow> = sqlx::query_as(
+            r#"
+                SELECT outfits.id, outfits.name, garments.id, garments.name, garments.color
+                FROM outfits
+                LEFT JOIN outfit_items ON outfit_items.outfit_id = outfits.id
+                LEFT JOIN garments
+                    ON garments.id = outfit_items.item_id AND garments.deleted_at IS NULL
+                WHERE ?1 IS NULL OR outfits.id = ?1
+                ORDER BY outfits.name COLLATE NOCASE, outfits.id, garments.name COLLATE NOCASE
+            "#,
+        )
+        .bind(id.map(|id| id as i32))
+        .fetch_all(&self.0)
+        .await?;
+
+        let mut outfits = Vec::<Outfit>::new();
+        for (id, name, item_id, item_name, color) in rows {
+            let id = id as usize;
+            if outfits.last().is_none_or(|o| o.id != id) {
+                outfits.push(Outfit {
+                    id,
+                    name,
+                    items: Vec::new(),
+                });
+            }
+            if let (Some(outfit), Some(key), Some(name), Some(color)) =
+                (outfits.last_mut(), item_id, item_name, color)
+            {
+                outfit.items.push(OutfitItem {
+                    key: key as usize,
+                    name,
+                    color,
+                });
+            }
+        }
+
+        Ok(outfits)
+    }
+
+    /// Save a new outfit made of the given items, returning its id.
+    pub(crate) async fn add_outfit(&self, name: &str, item_ids: &[usize]) -> sqlx::Result<usize> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("INSERT INTO outfits ( name ) VALUES ( ? )")
+            .bind(name.trim())
+            .execute(&mut tx)
+            .await?;
+        let (id,): (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
+            .fetch_one(&mut tx)
+            .await?;
+        let id = id as usize;
+        for item_id in item_ids {
+            sqlx::query(
+                "INSERT OR IGNORE INTO outfit_items ( outfit_id, item_id ) \
+                 SELECT ?, id FROM garments WHERE id = ?",
+            )
+            .bind(id as i32)
+            .bind(*item_id as i32)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Rename an outfit and replace the items in it.
+    pub(crate) async fn update_outfit(
+        &self,
+        id: usize,
+        name: &str,
+        item_ids: &[usize],
+    ) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let changed = sqlx::query("UPDATE outfits SET name = ? WHERE id = ?")
+            .bind(name.trim())
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM outfit_items WHERE outfit_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        if changed > 0 {
+            for item_id in item_ids {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO outfit_items ( outfit_id, item_id ) \
+                     SELECT ?, id FROM garments WHERE id = ?",
+                )
+                .bind(id as i32)
+                .bind(*item_id as i32)
+                .execute(&mut tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    pub(crate) async fn delete_outfit(&self, id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("DELETE FROM outfit_items WHERE outfit_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM planned_wears WHERE outfit_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM outfits WHERE id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Items and outfits planned for each day from `from` to `to` inclusive, in the order they
+    /// were planned. Items in the trash are left out.
+    pub(crate) async fn get_planned_wears(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> sqlx::Result<Vec<PlannedRow>> {
+        sqlx::query_as(
+            r#"
+            SELECT p.id, p.planned_on, p.item_id, p.outfit_id, COALESCE(g.name, o.name), p.note
+            FROM planned_wears p
+            LEFT JOIN garments g ON g.id = p.item_id AND g.deleted_at IS NULL
+            LEFT JOIN outfits o ON o.id = p.outfit_id
+            WHERE p.planned_on BETWEEN ?1 AND ?2 AND COALESCE(g.id, o.id) IS NOT NULL
+            ORDER BY p.planned_on, p.id
+        "#,
+        )
+        .bind(from.to_string())
+        .bind(to.to_string())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Plan to wear an item or an outfit on a day; exactly one of them should be given.
+    pub(crate) async fn add_planned_wear(
+        &self,
+        on: NaiveDate,
+        item_id: Option<usize>,
+        outfit_id: Option<usize>,
+        note: &str,
+    ) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO planned_wears ( planned_on, item_id, outfit_id, note )
+            SELECT ?1, ?2, ?3, ?4 WHERE
+                EXISTS ( SELECT 1 FROM garments WHERE id = ?2 AND deleted_at IS NULL )
+                OR EXISTS ( SELECT 1 FROM outfits WHERE id = ?3 )
+        "#,
+        )
+        .bind(on.to_string())
+        .bind(item_id.map(|id| id as i32))
+        .bind(outfit_id.map(|id| id as i32))
+        .bind(note.trim())
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn delete_planned_wear(&self, id: usize) -> ExecResult {
+        sqlx::query("DELETE FROM planned_wears WHERE id = ?")
+            .bind(id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Log a planned wear as worn on the day it was planned for, along with every item in it if
+    /// it's an outfit, and take it off the plan. Plans for after `today` can't be worn yet.
+    /// Returns the day, and how many items were worn.
+    pub(crate) async fn wear_planned(
+        &self,
+        id: usize,
+        today: NaiveDate,
+    ) -> sqlx::Result<(NaiveDate, u64)> {
+        let mut tx = self.0.begin().await?;
+
+        let rows: Vec<(String, Option<i32>, String)> = sqlx::query_as(
+            r#"
+            SELECT p.planned_on, COALESCE(p.item_id, m.item_id), p.note
+            FROM planned_wears p
+            LEFT JOIN outfit_items m ON m.outfit_id = p.outfit_id
+                AND m.item_id IN ( SELECT id FROM garments WHERE deleted_at IS NULL )
+            WHERE p.id = ? AND p.planned_on <= ?
+        "#,
+        )
+        .bind(id as i32)
+        .bind(today.to_string())
+        .fetch_all(&mut tx)
+        .await?;
+        let (day, note) = match rows.first() {
+            Some((day, _, note)) => (parse_day(day)?, note.clone()),
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        sqlx::query("DELETE FROM planned_wears WHERE id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        let details = WearDetails {
+            date: Some(day),
+            note,
+            ..WearDetails::default()
+        };
+        let wears = rows
+            .iter()
+            .filter_map(|(_, item_id, _)| *item_id)
+            .map(|item_id| (item_id as usize, &details))
+            .collect::<Vec<_>>();
+        let worn = record_wears(&mut tx, &wears).await?;
+
+        tx.commit().await?;
+        Ok((day, worn))
+    }
+
+    /// Every trip, latest first, with its packing list.
+    pub(crate) async fn get_trips(&self) -> sqlx::Result<Vec<Trip>> {
+        self.trips(None).await
+    }
+
+    pub(crate) async fn get_trip(&self, id: usize) -> sqlx::Result<Trip> {
+        self.trips(Some(id))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn trips(&self, id: Option<usize>) -> sqlx::Result<Vec<Trip>> {
+        let trips: Vec<(i32, String, String, String, bool, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, name, starts_on, ends_on, log_wears != 0, logged_through FROM trips
+            WHERE ?1 IS NULL OR id = ?1
+            ORDER BY starts_on DESC, id
+        "#,
+        )
+        .bind(id.map(|id| id as i32))
+        .fetch_all(&self.0)
+        .await?;
+        let items: Vec<(i32, i32, String, String, bool)> = sqlx::query_as(
+            r#"
+            SELECT ti.trip_id, g.id, g.name, g.color, ti.packed != 0
+            FROM trip_items ti JOIN garments g ON g.id = ti.item_id
+            WHERE g.deleted_at IS NULL AND (?1 IS NULL OR ti.trip_id = ?1)
+            ORDER BY g.name COLLATE NOCASE, g.id
+        "#,
+        )
+        .bind(id.map(|id| id as i32))
+        .fetch_all(&self.0)
+        .await?;
+
+        trips
+            .into_iter()
+            .map(
+                |(id, name, starts_on, ends_on, log_wears, logged_through)| {
+                    Ok(Trip {
+                        id: id as usize,
+                        name,
+                        starts_on: parse_day(&starts_on)?,
+                        ends_on: parse_day(&ends_on)?,
+                        log_wears,
+                        logged_through: logged_through.as_deref().map(parse_day).transpose()?,
+                        items: items
+                            .iter()
+                            .filter(|(trip_id, ..)| *trip_id == id)
+                            .map(|(_, key, name, color, packed)| TripItem {
+                                key: *key as usize,
+                                name: name.clone(),
+                                color: color.clone(),
+                                packed: *packed,
+                            })
+                            .collect(),
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Start a trip with nothing packed for it yet, returning its id.
+    pub(crate) async fn add_trip(
+        &self,
+        name: &str,
+        starts_on: NaiveDate,
+        ends_on: NaiveDate,
+        log_wears: bool,
+    ) -> sqlx::Result<usize> {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO trips ( name, starts_on, ends_on, log_wears ) VALUES ( ?, ?, ?, ? )",
+        )
+        .bind(name.trim())
+        .bind(starts_on.to_string())
+        .bind(ends_on.to_string())
+        .bind(log_wears)
+        .execute(&mut tx)
+        .await?;
+        let (id,): (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
+            .fetch_one(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(id as usize)
+    }
+
+    pub(crate) async fn update_trip(
+        &self,
+        id: usize,
+        name: &str,
+        starts_on: NaiveDate,
+        ends_on: NaiveDate,
+        log_wears: bool,
+    ) -> ExecResult {
+        sqlx::query(
+            "UPDATE trips SET name = ?, starts_on = ?, ends_on = ?, log_wears = ? WHERE id = ?",
+        )
+        .bind(name.trim())
+        .bind(starts_on.to_string())
+        .bind(ends_on.to_string())
+        .bind(log_wears)
+        .bind(id as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn delete_trip(&self, id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("DELETE FROM trip_items WHERE trip_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM trips WHERE id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Put items on a trip's packing list, along with everything in the given outfits. Items
+    /// already on it stay as they are.
+    pub(crate) async fn add_trip_items(
+        &self,
+        id: usize,
+        item_ids: &[usize],
+        outfit_ids: &[usize],
+    ) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+        let mut added = 0;
+
+        for item_id in item_ids {
+            added += sqlx::query(
+                "INSERT OR IGNORE INTO trip_items ( trip_id, item_id ) \
+                 SELECT t.id, g.id FROM trips t, garments g WHERE t.id = ? AND g.id = ?",
+            )
+            .bind(id as i32)
+            .bind(*item_id as i32)
+            .execute(&mut tx)
+            .await?;
+        }
+        for outfit_id in outfit_ids {
+            added += sqlx::query(
+                "INSERT OR IGNORE INTO trip_items ( trip_id, item_id ) \
+                 SELECT t.id, m.item_id FROM trips t, outfit_items m \
+                 WHERE t.id = ? AND m.outfit_id = ?",
+            )
+            .bind(id as i32)
+            .bind(*outfit_id as i32)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(added)
+    }
+
+    pub(crate) async fn set_packed(&self, id: usize, item_id: usize, packed: bool) -> ExecResult {
+        sqlx::query("UPDATE trip_items SET packed = ? WHERE trip_id = ? AND item_id = ?")
+            .bind(packed)
+            .bind(id as i32)
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn remove_trip_item(&self, id: usize, item_id: usize) -> ExecResult {
+        sqlx::query("DELETE FROM trip_items WHERE trip_id = ? AND item_id = ?")
+            .bind(id as i32)
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// Trips set to log wears that have days up to `today` still to be logged.
+    pub(crate) async fn get_trips_to_log(&self, today: NaiveDate) -> sqlx::Result<Vec<(i32,)>> {
+        sqlx::query_as(
+            r#"
+            SELECT id FROM trips
+            WHERE log_wears != 0 AND starts_on <= ?1
+                AND (logged_through IS NULL OR logged_through < MIN(ends_on, ?1))
+        "#,
+        )
+        .bind(today.to_string())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Log a wear of everything packed for a trip on each of its days up to `today` that hasn't
+    /// been logged yet, with the trip's name as the note. Returns how many wears were logged.
+    pub(crate) async fn log_trip_wears(&self, id: usize, today: NaiveDate) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let rows: Vec<TripWearRow> = sqlx::query_as(
+            r#"
+            SELECT t.name, t.starts_on, t.ends_on, t.logged_through, ti.item_id
+            FROM trips t
+            LEFT JOIN trip_items ti ON ti.trip_id = t.id AND ti.packed != 0
+                AND ti.item_id IN ( SELECT id FROM garments WHERE deleted_at IS NULL )
+            WHERE t.id = ?
+        "#,
+        )
+        .bind(id as i32)
+        .fetch_all(&mut tx)
+        .await?;
+        let (name, days) = match rows.first() {
+            Some((name, starts_on, ends_on, logged_through, _)) => (
+                name.clone(),
+                trips::days_to_log(
+                    parse_day(starts_on)?,
+                    parse_day(ends_on)?,
+                    logged_through.as_deref().map(parse_day).transpose()?,
+                    today,
+                ),
+            ),
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        let details = days
+            .iter()
+            .map(|day| WearDetails {
+                date: Some(*day),
+                note: name.clone(),
+                ..WearDetails::default()
+            })
+            .collect::<Vec<_>>();
+        let wears = details
+            .iter()
+            .flat_map(|d| rows.iter().filter_map(move |r| Some((r.4? as usize, d))))
+            .collect::<Vec<_>>();
+        let worn = record_wears(&mut tx, &wears).await?;
+
+        if let Some(last) = days.last() {
+            sqlx::query("UPDATE trips SET logged_through = ? WHERE id = ?")
+                .bind(last.to_string())
+                .bind(id as i32)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(worn)
+    }
+
+    /// Total spent on cleaning an item.
+    pub(crate) async fn get_cleaning_cost(&self, item_id: usize) -> sqlx::Result<f64> {
+        let (total,): (f64,) =
+            sqlx::query_as("SELECT TOTAL(cost) FROM wash_events WHERE item_id = ?")
+                .bind(item_id as i32)
+                .fetch_one(&self.0)
+                .await?;
+
+        Ok(total)
+    }
+
+    /// An item's repairs and alterations, most recent first, as `(id, date, description, cost)`.
+    pub(crate) async fn get_repairs(
+        &self,
+        item_id: usize,
+    ) -> sqlx::Result<Vec<(i32, String, String, Option<f64>)>> {
+        sqlx::query_as(
+            "SELECT id, repaired_on, description, cost FROM repairs \
+             WHERE item_id = ? ORDER BY repaired_on DESC, id DESC",
+        )
+        .bind(item_id as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    pub(crate) async fn add_repair(
+        &self,
+        item_id: usize,
+        on: NaiveDate,
+        description: &str,
+        cost: Option<f64>,
+    ) -> ExecResult {
+        sqlx::query(
+            "INSERT INTO repairs ( item_id, repaired_on, description, cost ) \
+             SELECT id, ?, ?, ? FROM garments WHERE id = ?",
+        )
+        .bind(on.to_string())
+        .bind(description.trim())
+        .bind(cost)
+        .bind(item_id as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn delete_repair(&self, item_id: usize, repair_id: usize) -> ExecResult {
+        sqlx::query("DELETE FROM repairs WHERE id = ? AND item_id = ?")
+            .bind(repair_id as i32)
+            .bind(item_id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    /// How many items there were at a point in time: created by then, and not yet archived or
+    /// deleted. Items from before creation times were recorded count as always having been there.
+    pub(crate) async fn count_items_at(&self, at: DateTime<Utc>) -> sqlx::Result<u32> {
+        let (count,): (i32,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM garments
+            WHERE deleted_at IS NULL AND wishlisted_at IS NULL
+                AND (created_at IS NULL OR datetime(created_at) < datetime(?1))
+                AND (archived_at IS NULL OR datetime(archived_at) >= datetime(?1))
+        "#,
+        )
+        .bind(at.to_rfc3339())
+        .fetch_one(&self.0)
+        .await?;
+        Ok(count.max(0) as u32)
+    }
+
+    /// Items created, or archived with `archived`, within a period, as `(id, name)`.
+    pub(crate) async fn get_items_changed_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        archived: bool,
+    ) -> sqlx::Result<Vec<(i32, String)>> {
+        let column = if archived {
+            "archived_at"
+        } else {
+            "created_at"
+        };
+        sqlx::query_as(&format!(
+            r#"
+            SELECT id, name FROM garments
+            WHERE deleted_at IS NULL AND wishlisted_at IS NULL
+                AND datetime({0}) >= datetime(?) AND datetime({0}) < datetime(?)
+            ORDER BY datetime({0}), id
+        "#,
+            column
+        ))
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// How many times each item was worn within a period, as `(id, name, wears)`. Wears that have
+    /// been rolled up into monthly totals count if their month starts within the period.
+    pub(crate) async fn get_wears_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> sqlx::Result<Vec<(i32, String, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT g.id, g.name, SUM(w.wears) FROM (
+                SELECT item_id, COUNT(*) AS wears FROM wear_events
+                WHERE datetime(worn_at) >= datetime(?1) AND datetime(worn_at) < datetime(?2)
+                GROUP BY item_id
+                UNION ALL
+                SELECT item_id, wears FROM wear_summaries
+                WHERE date(month || '-01') >= date(?1) AND date(month || '-01') < date(?2)
+            ) w JOIN garments g ON g.id = w.item_id
+            WHERE g.deleted_at IS NULL
+            GROUP BY g.id
+        "#,
+        )
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Cleaning spend for each year, most recent first, as `(year, total, paid cleanings)`.
+    pub(crate) async fn get_annual_cleaning_spend(
+        &self,
+        archived: Archived,
+    ) -> sqlx::Result<Vec<(String, f64, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT strftime('%Y', washed_at), TOTAL(cost), COUNT(cost)
+            FROM wash_events
+            WHERE cost IS NOT NULL
+                AND (? OR item_id NOT IN (SELECT id FROM garments WHERE archived_at IS NOT NULL))
+            GROUP BY 1
+            ORDER BY 1 DESC
+        "#,
+        )
+        .bind(archived == Archived::Included)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// The items that cost the most to clean in a given year, as `(item id, name, total)`.
+    pub(crate) async fn get_cleaning_spend_by_item(
+        &self,
+        year: &str,
+        archived: Archived,
+    ) -> sqlx::Result<Vec<(i32, String, f64)>> {
+        sqlx::query_as(
+            r#"
+            SELECT g.id, g.name, TOTAL(w.cost)
+            FROM wash_events w JOIN garments g ON g.id = w.item_id
+            WHERE w.cost IS NOT NULL AND strftime('%Y', w.washed_at) = ?
+                AND (? OR g.archived_at IS NULL) AND g.deleted_at IS NULL
+            GROUP BY g.id
+            ORDER BY 3 DESC
+        "#,
+        )
+        .bind(year)
+        .bind(archived == Archived::Included)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// How many times an item has been washed with each method, as `(method, washes)`. Washes
+    /// logged without a method are counted under an empty string.
+    pub(crate) async fn get_wash_counts(&self, item_id: usize) -> sqlx::Result<Vec<(String, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT method, COUNT(*) FROM wash_events
+            WHERE item_id = ?
+            GROUP BY method
+            ORDER BY COUNT(*) DESC
+        "#,
+        )
+        .bind(item_id as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Outstanding tasks for one item, as `(task, note)` pairs.
+    pub(crate) async fn get_tasks(&self, item_id: usize) -> sqlx::Result<Vec<(String, String)>> {
+        sqlx::query_as("SELECT task, note FROM item_tasks WHERE item_id = ? ORDER BY flagged_at")
+            .bind(item_id as i32)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// Every outstanding task, oldest first, as `(item id, item name, task, note, flagged at)`.
+    pub(crate) async fn get_all_tasks(
+        &self,
+    ) -> sqlx::Result<Vec<(i32, String, String, String, String)>> {
+        sqlx::query_as(
+            r#"
+            SELECT g.id, g.name, t.task, t.note, t.flagged_at
+            FROM item_tasks t JOIN garments g ON g.id = t.item_id
+            WHERE g.deleted_at IS NULL
+            ORDER BY t.task, datetime(t.flagged_at)
+        "#,
+        )
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Flag an item as needing some work, or update the note on an existing flag. Items that don't
+    /// exist or are in the trash can't be flagged, and change nothing.
+    pub(crate) async fn flag_task(&self, item_id: usize, task: Task, note: &str) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO item_tasks ( item_id, task, note, flagged_at )
+            SELECT ?1, ?2, ?3, ?4
+            WHERE EXISTS ( SELECT 1 FROM garments WHERE id = ?1 AND deleted_at IS NULL )
+            ON CONFLICT ( item_id, task ) DO UPDATE SET note = excluded.note
+        "#,
+        )
+        .bind(item_id as i32)
+        .bind(task.as_str())
+        .bind(note.trim())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn clear_task(&self, item_id: usize, task: Task) -> ExecResult {
+        sqlx::query("DELETE FROM item_tasks WHERE item_id = ? AND task = ?")
+            .bind(item_id as i32)
+            .bind(task.as_str())
+            .execute(&self.0)
+            .await
+    }
+
+    /// When anything in the wardrobe was last edited, worn or washed. Deleted items leave no
+    /// trace, so removing one doesn't count.
+    pub(crate) async fn last_change(&self) -> sqlx::Result<Option<DateTime<Utc>>> {
+        let (latest,): (Option<String>,) = sqlx::query_as(
+            r#"
+            SELECT strftime('%Y-%m-%dT%H:%M:%SZ', MAX(changed)) FROM (
+              SELECT MAX(datetime(modified_at)) AS changed FROM garments
+              UNION ALL SELECT MAX(datetime(worn_at)) FROM wear_events
+              UNION ALL SELECT MAX(datetime(washed_at)) FROM wash_events
+            )
+        "#,
+        )
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok(latest
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Utc)))
+    }
+
+    /// How many wears each category is meant to get in a week.
+    pub(crate) async fn get_rotation_goals(&self) -> sqlx::Result<Vec<(String, i32)>> {
+        sqlx::query_as("SELECT category, weekly_wears FROM rotation_goals ORDER BY category")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub(crate) async fn set_rotation_goal(&self, category: &str, weekly_wears: u32) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO rotation_goals ( category, weekly_wears ) VALUES ( ?, ? )
+            ON CONFLICT ( category ) DO UPDATE SET weekly_wears = excluded.weekly_wears
+        "#,
+        )
+        .bind(category.trim().to_lowercase())
+        .bind(weekly_wears as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn delete_rotation_goal(&self, category: &str) -> ExecResult {
+        sqlx::query("DELETE FROM rotation_goals WHERE category = ?")
+            .bind(category)
+            .execute(&self.0)
+            .await
+    }
+
+    /// How many wears items in each category go between washes, unless they say otherwise.
+    pub(crate) async fn get_wear_limits(&self) -> sqlx::Result<Vec<(String, i32)>> {
+        sqlx::query_as("SELECT category, wears FROM wear_limits ORDER BY category")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub(crate) async fn set_wear_limit(&self, category: &str, wears: u32) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO wear_limits ( category, wears ) VALUES ( ?, ? )
+            ON CONFLICT ( category ) DO UPDATE SET wears = excluded.wears
+        "#,
+        )
+        .bind(category.trim().to_lowercase())
+        .bind(wears as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    pub(crate) async fn delete_wear_limit(&self, category: &str) -> ExecResult {
+        sqlx::query("DELETE FROM wear_limits WHERE category = ?")
+            .bind(category)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn get_custom_fields(&self) -> sqlx::Result<Vec<Field>> {
+        let fields: Vec<(i32, String, String)> =
+            sqlx::query_as("SELECT id, name, kind FROM custom_fields ORDER BY name")
+                .fetch_all(&self.0)
+                .await?;
+
+        Ok(fields
+            .into_iter()
+            .map(|(id, name, kind)| Field {
+                id: id as usize,
+                name,
+                kind: custom::Kind::parse(&kind),
+            })
+            .collect())
+    }
+
+    pub(crate) async fn add_custom_field(&self, name: &str, kind: custom::Kind) -> ExecResult {
+        sqlx::query("INSERT OR IGNORE INTO custom_fields ( name, kind ) VALUES ( ?, ? )")
+            .bind(name.trim())
+            .bind(kind.as_str())
+            .execute(&self.0)
+            .await
+    }
+
+    /// Remove a custom field along with every item's value for it.
+    pub(crate) async fn delete_custom_field(&self, id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let mut changed = sqlx::query("DELETE FROM custom_values WHERE field_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        changed += sqlx::query("DELETE FROM custom_fields WHERE id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// An item's custom field values, as `(field id, value)`.
+    pub(crate) async fn get_custom_values(
+        &self,
+        item_id: usize,
+    ) -> sqlx::Result<Vec<(usize, String)>> {
+        let values: Vec<(i32, String)> =
+            sqlx::query_as("SELECT field_id, value FROM custom_values WHERE item_id = ?")
+                .bind(item_id as i32)
+                .fetch_all(&self.0)
+                .await?;
+
+        Ok(values
+            .into_iter()
+            .map(|(id, value)| (id as usize, value))
+            .collect())
+    }
+
+    /// Set some of an item's custom field values. Empty values are removed.
+    pub(crate) async fn set_custom_values(
+        &self,
+        item_id: usize,
+        values: &[(usize, String)],
+    ) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+        let changed = write_custom_values(&mut tx, item_id, values).await?;
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Every category in use by an item that isn't in the trash or on the wishlist, for suggesting
+    /// goals and limits.
+    pub(crate) async fn get_categories(&self) -> sqlx::Result<Vec<(String,)>> {
+        sqlx::query_as(&format!(
+            "SELECT DISTINCT {0} FROM garments \
+             WHERE {0} != '' AND deleted_at IS NULL AND wishlisted_at IS NULL ORDER BY 1",
+            CATEGORY
+        ))
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Wears since `since` for each category that had any, as `(category, wears)`. Items in the
+    /// trash don't count.
+    pub(crate) async fn get_wears_by_category(
+        &self,
+        since: DateTime<Utc>,
+    ) -> sqlx::Result<Vec<(String, i32)>> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}, COUNT(*)
+            FROM wear_events e JOIN garments g ON g.id = e.item_id
+            WHERE datetime(e.worn_at) >= datetime(?) AND g.deleted_at IS NULL
+            GROUP BY 1
+        "#,
+            CATEGORY
+        ))
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// How many times each item (or just the one given) was worn in each of the last `weeks`
+    /// weeks before `now`, as `(item id, weeks ago, wears)`. Week 0 is the seven days up to `now`.
+    pub(crate) async fn get_weekly_wears(
+        &self,
+        item_id: Option<usize>,
+        now: DateTime<Utc>,
+        weeks: u32,
+    ) -> sqlx::Result<Vec<(i32, i32, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT item_id, CAST((julianday(?2) - julianday(worn_at)) / 7 AS INTEGER), COUNT(*)
+            FROM wear_events
+            WHERE (?1 IS NULL OR item_id = ?1)
+                AND julianday(worn_at) > julianday(?2) - 7 * ?3
+                AND julianday(worn_at) <= julianday(?2)
+            GROUP BY 1, 2
+        "#,
+        )
+        .bind(item_id.map(|id| id as i32))
+        .bind(now.to_rfc3339())
+        .bind(weeks as i32)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Wears per item and local day from `from` to `to` inclusive, as `(item id, day, wears)`, for
+    /// one item or all of them.
+    pub(crate) async fn get_daily_wears(
+        &self,
+        item_id: Option<usize>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> sqlx::Result<Vec<(i32, String, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT item_id, date(worn_at, 'localtime') AS day, COUNT(*)
+            FROM wear_events
+            WHERE (?1 IS NULL OR item_id = ?1) AND day BETWEEN ?2 AND ?3
+            GROUP BY 1, 2
+            ORDER BY 2, 1
+        "#,
+        )
+        .bind(item_id.map(|id| id as i32))
+        .bind(from.to_string())
+        .bind(to.to_string())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// What was worn on each local day from `from` to `to` inclusive, by day and then name, as
+    /// `(day, item id, name, color, wears)`. Items in the trash are left out.
+    pub(crate) async fn get_worn_by_day(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> sqlx::Result<Vec<(String, i32, String, String, i32)>> {
+        sqlx::query_as(
+            r#"
+            SELECT date(e.worn_at, 'localtime') AS day, g.id, g.name, g.color, COUNT(*)
+            FROM wear_events e JOIN garments g ON g.id = e.item_id
+            WHERE g.deleted_at IS NULL AND day BETWEEN ?1 AND ?2
+            GROUP BY 1, 2
+            ORDER BY 1, g.name COLLATE NOCASE, g.id
+        "#,
+        )
+        .bind(from.to_string())
+        .bind(to.to_string())
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Saved views of the index, by name, as `(id, name, query string, sends alerts)`.
+    pub(crate) async fn get_saved_views(&self) -> sqlx::Result<Vec<(i32, String, String, bool)>> {
+        sqlx::query_as(
+            "SELECT id, name, query, alert != 0 FROM views ORDER BY name COLLATE NOCASE, id",
+        )
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// The query string a saved view shows the index with.
+    pub(crate) async fn get_saved_view(&self, id: usize) -> sqlx::Result<String> {
+        let (query,): (String,) = sqlx::query_as("SELECT query FROM views WHERE id = ?")
+            .bind(id as i32)
+            .fetch_one(&self.0)
+            .await?;
+        Ok(query)
+    }
+
+    pub(crate) async fn add_saved_view(&self, name: &str, query: &str) -> ExecResult {
+        sqlx::query("INSERT INTO views ( name, query ) VALUES ( ?, ? )")
+            .bind(name)
+            .bind(query)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn update_saved_view(&self, id: usize, name: &str, query: &str) -> ExecResult {
+        sqlx::query("UPDATE views SET name = ?, query = ? WHERE id = ?")
+            .bind(name)
+            .bind(query)
+            .bind(id as i32)
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn delete_saved_view(&self, id: usize) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("DELETE FROM view_alert_matches WHERE view_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM views WHERE id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Turn alerts for a saved view on or off. Turning them on counts everything it already
+    /// matches as seen, so only what matches afterwards is reported.
+    pub(crate) async fn set_view_alert(
+        &self,
+        id: usize,
+        alert: bool,
+        matching: &[usize],
+    ) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let changed = sqlx::query("UPDATE views SET alert = ? WHERE id = ?")
+            .bind(alert)
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM view_alert_matches WHERE view_id = ?")
+            .bind(id as i32)
+            .execute(&mut tx)
+            .await?;
+        if alert {
+            for item_id in matching {
+                sqlx::query("INSERT INTO view_alert_matches ( view_id, item_id ) VALUES ( ?, ? )")
+                    .bind(id as i32)
+                    .bind(*item_id as i32)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Record what a view matches now, returning the items it didn't match at the last check.
+    /// Items that stop matching are forgotten, so they're reported again if they come back.
+    pub(crate) async fn update_alert_matches(
+        &self,
+        view_id: usize,
+        matching: &[usize],
+    ) -> sqlx::Result<Vec<usize>> {
+        let mut tx = self.0.begin().await?;
+
+        let seen: Vec<(i32,)> =
+            sqlx::query_as("SELECT item_id FROM view_alert_matches WHERE view_id = ?")
+                .bind(view_id as i32)
+                .fetch_all(&mut tx)
+                .await?;
+        let new = matching
+            .iter()
+            .copied()
+            .filter(|id| !seen.contains(&(*id as i32,)))
+            .collect::<Vec<_>>();
+
+        sqlx::query("DELETE FROM view_alert_matches WHERE view_id = ?")
+            .bind(view_id as i32)
+            .execute(&mut tx)
+            .await?;
+        for item_id in matching {
+            sqlx::query("INSERT INTO view_alert_matches ( view_id, item_id ) VALUES ( ?, ? )")
+                .bind(view_id as i32)
+                .bind(*item_id as i32)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(new)
+    }
+
+    /// The days of the week laundry gets done on.
+    pub(crate) async fn get_laundry_schedule(&self) -> sqlx::Result<Vec<Weekday>> {
+        let days: Vec<(i32,)> =
+            sqlx::query_as("SELECT weekday FROM laundry_schedule ORDER BY weekday")
+                .fetch_all(&self.0)
+                .await?;
+
+        Ok(days
+            .into_iter()
+            .filter_map(|(d,)| laundry::WEEK.get(d as usize).copied())
+            .collect())
+    }
+
+    pub(crate) async fn set_laundry_schedule(&self, days: &[Weekday]) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        let mut changed = sqlx::query("DELETE FROM laundry_schedule")
+            .execute(&mut tx)
+            .await?;
+
+        for day in days {
+            changed +=
+                sqlx::query("INSERT OR IGNORE INTO laundry_schedule ( weekday ) VALUES ( ? )")
+                    .bind(day.num_days_from_monday() as i32)
+                    .execute(&mut tx)
+                    .await?;
+        }
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Laundry days that have been completed, most recent first, as `(day, items washed)`.
+    pub(crate) async fn get_laundry_days(
+        &self,
+        since: NaiveDate,
+    ) -> sqlx::Result<Vec<(NaiveDate, i32)>> {
+        let days: Vec<(String, i32)> =
+            sqlx::query_as("SELECT day, washed FROM laundry_days WHERE day >= ? ORDER BY day DESC")
+                .bind(since.to_string())
+                .fetch_all(&self.0)
+                .await?;
+
+        Ok(days
+            .into_iter()
+            .filter_map(|(day, washed)| day.parse().ok().map(|d| (d, washed)))
+            .collect())
+    }
+
+    /// Record a laundry day as done, adding to the tally if it was already marked.
+    pub(crate) async fn complete_laundry_day(&self, day: NaiveDate, washed: usize) -> ExecResult {
+        sqlx::query(
+            r#"
+            INSERT INTO laundry_days ( day, completed_at, washed )
+            VALUES ( ?1, ?2, ?3 )
+            ON CONFLICT ( day ) DO UPDATE SET completed_at = ?2, washed = washed + ?3
+        "#,
+        )
+        .bind(day.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .bind(washed as i32)
+        .execute(&self.0)
+        .await
+    }
+
+    /// Put a load in the washing machine, replacing whatever was there.
+    pub(crate) async fn start_machine_load(&self, ids: &[usize]) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+
+        sqlx::query("DELETE FROM machine_load")
+            .execute(&mut tx)
+            .await?;
+
+        let now = Utc::now().to_rfc3339();
+        let mut added = 0;
+        for id in ids {
+            added += sqlx::query(
+                "INSERT OR IGNORE INTO machine_load ( item_id, started_at ) VALUES ( ?, ? )",
+            )
+            .bind(*id as i32)
+            .bind(&now)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(added)
+    }
+
+    /// What's in the washing machine, as `(id, name)`, and when its cycle finished if it has.
+    pub(crate) async fn get_machine_load(
+        &self,
+    ) -> sqlx::Result<(Vec<(i32, String)>, Option<DateTime<Utc>>)> {
+        let rows: Vec<(i32, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT garments.id, garments.name, machine_load.finished_at
+            FROM machine_load JOIN garments ON garments.id = machine_load.item_id
+            ORDER BY garments.name
+        "#,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        let finished = rows
+            .iter()
+            .find_map(|(_, _, f)| f.as_deref())
+            .and_then(|f| DateTime::parse_from_rfc3339(f).ok())
+            .map(|f| f.with_timezone(&Utc));
+
+        Ok((
+            rows.into_iter().map(|(id, name, _)| (id, name)).collect(),
+            finished,
+        ))
+    }
+
+    pub(crate) async fn finish_machine_load(&self) -> ExecResult {
+        sqlx::query("UPDATE machine_load SET finished_at = ? WHERE finished_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.0)
+            .await
+    }
+
+    pub(crate) async fn clear_machine_load(&self) -> ExecResult {
+        sqlx::query("DELETE FROM machine_load")
+            .execute(&self.0)
+            .await
+    }
+
+    /// Find records that disagree with each other and fix them, returning how many rows each kind
+    /// of problem was found in.
+    pub(crate) async fn repair(&self) -> sqlx::Result<Vec<(&'static str, u64)>> {
+        let mut tx = self.0.begin().await?;
+        let mut found = Vec::new();
+
+        for (problem, table, condition, fix) in REPAIRS {
+            let fixed = sqlx::query(&match fix {
+                Some(fix) => format!("UPDATE {} SET {} WHERE {}", table, fix, condition),
+                None => format!("DELETE FROM {} WHERE {}", table, condition),
+            })
+            .execute(&mut tx)
+            .await?;
+            found.push((*problem, fixed));
+        }
+
+        // tags are stored joined by commas, with no empty ones or spaces around them
+        let tags: Vec<(i32, String)> = sqlx::query_as("SELECT id, tags FROM garments")
+            .fetch_all(&mut tx)
+            .await?;
+        let mut retagged = 0;
+        for (id, tags) in tags {
+            let tidy = tags
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(",");
+            if tidy != tags {
+                retagged += sqlx::query("UPDATE garments SET tags = ? WHERE id = ?")
+                    .bind(tidy)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+        found.push(("items with empty or padded tags", retagged));
+
+        tx.commit().await?;
+        Ok(found)
+    }
+
+    /// Attachments of items that no longer exist, whose files should be removed with them.
+    pub(crate) async fn get_orphaned_attachments(&self) -> sqlx::Result<Vec<Attachment>> {
+        sqlx::query_as("SELECT * FROM attachments WHERE item_id NOT IN ( SELECT id FROM garments )")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub(crate) async fn get_all_attachments(&self) -> sqlx::Result<Vec<Attachment>> {
+        sqlx::query_as("SELECT * FROM attachments ORDER BY id")
+            .fetch_all(&self.0)
+            .await
+    }
+
+    /// Keep a notification to send once quiet hours are over.
+    pub(crate) async fn hold_notification(&self, subject: &str, body: &str) -> ExecResult {
+        sqlx::query("INSERT INTO held_notifications ( subject, body, held_at ) VALUES ( ?, ?, ? )")
+            .bind(subject)
+            .bind(body)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.0)
+            .await
+    }
+
+    /// Take every held notification, oldest first, as `(subject, body)` pairs.
+    pub(crate) async fn take_held_notifications(&self) -> sqlx::Result<Vec<(String, String)>> {
+        let mut tx = self.0.begin().await?;
+        let held: Vec<(i32, String, String)> =
+            sqlx::query_as("SELECT id, subject, body FROM held_notifications ORDER BY id")
+                .fetch_all(&mut tx)
+                .await?;
+        if let Some((last, _, _)) = held.last() {
+            sqlx::query("DELETE FROM held_notifications WHERE id <= ?")
+                .bind(last)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(held
+            .into_iter()
+            .map(|(_, subject, body)| (subject, body))
+            .collect())
+    }
+
+    pub(crate) async fn get_settings(&self) -> sqlx::Result<Settings> {
+        let pairs = sqlx::query_as("SELECT key, value FROM settings")
+            .fetch_all(&self.0)
+            .await?;
+
+        Ok(Settings::from_pairs(pairs))
+    }
+
+    pub(crate) async fn save_settings(&self, settings: &Settings) -> ExecResult {
+        let mut tx = self.0.begin().await?;
+        let mut changed = 0;
+
+        for (key, value) in settings.to_pairs() {
+            changed += sqlx::query(
+                r#"
+                INSERT INTO settings ( key, value ) VALUES ( ?, ? )
+                ON CONFLICT ( key ) DO UPDATE SET value = excluded.value
+            "#,
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        self.set_collation(settings.collation);
+        Ok(changed)
+    }
+
+    /// Items with at least one measurement, other than those in the trash or on the wishlist,
+    /// narrowed down to those within `(min, max)` centimetres for each of the given measurements.
+    pub(crate) async fn get_by_measurements(
+        &self,
+        ranges: &[(Measurement, f64, f64)],
+    ) -> sqlx::Result<Vec<Item>> {
+        let mut cmd = "SELECT * FROM garments WHERE COALESCE(".to_string();
+        cmd += &Measurement::ALL
+            .iter()
+            .map(|m| m.key())
+            .collect::<Vec<_>>()
+            .join(", ");
+        cmd += ") IS NOT NULL AND deleted_at IS NULL AND wishlisted_at IS NULL";
+
+        for (m, _, _) in ranges {
+            cmd += &format!(" AND {} BETWEEN ? AND ?", m.key());
+        }
+        cmd += " ORDER BY name";
+
+        let mut query = sqlx::query_as(&cmd);
+        for (_, min, max) in ranges {
+            query = query.bind(min).bind(max);
+        }
+
+        query.fetch_all(&self.0).await
+    }
+}
+
+/// What to narrow a listing of items down to. Everything set must match.
+#[derive(Default)]
+pub(crate) struct ListFilter {
+    pub(crate) view: Option<View>,
+    /// Words to look for in names, descriptions and tags
+    pub(crate) search: Option<String>,
+    /// A tag the item must carry, ignoring case
+    pub(crate) tag: Option<String>,
+    pub(crate) color: Option<ColorFilter>,
+    /// Only items not worn in at least this many days, including those never worn
+    pub(crate) unworn_days: Option<u32>,
+    /// Anything else, in the filter language
+    pub(crate) query: Query,
+    pub(crate) archived: Archived,
+    /// Items in the trash instead of everything else
+    pub(crate) deleted: bool,
+    /// Items on the wishlist instead of owned ones, except in the trash, which has both
+    pub(crate) wishlist: bool,
+}
+
+/// Which items to cover, going by whether they've been archived.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Archived {
+    /// Only current items, as almost everything wants
+    #[default]
+    Hidden,
+    /// Current and archived items both, for reports that shouldn't lose history
+    Included,
+    /// Only archived items
+    Only,
+}
+
+impl Archived {
+    /// Archived items as well as current ones if `include` is set.
+    pub(crate) fn included(include: bool) -> Self {
+        if include {
+            Self::Included
+        } else {
+            Self::Hidden
+        }
+    }
+}
+
+impl ListFilter {
+    /// SQL conditions for the filter, and the values to bind to their parameters in order.
+    fn conditions(&self) -> (Vec<String>, Vec<String>) {
+        let (mut conditions, mut values) = (Vec::new(), Vec::new());
+
+        conditions.push(
+            match self.deleted {
+                true => "deleted_at IS NOT NULL",
+                false => "deleted_at IS NULL",
+            }
+            .to_string(),
+        );
+        if !self.deleted {
+            conditions.push(
+                match self.wishlist {
+                    true => "wishlisted_at IS NOT NULL",
+                    false => "wishlisted_at IS NULL",
+                }
+                .to_string(),
+            );
+        }
+        match self.archived {
+            Archived::Hidden => conditions.push("archived_at IS NULL".to_string()),
+            Archived::Included => {}
+            Archived::Only => conditions.push("archived_at IS NOT NULL".to_string()),
+        }
+
+        if let Some(view) = self.view {
+            conditions.push(
+                match view {
+                    View::Unworn => {
+                        "(wear IS NULL OR datetime(wear) < datetime('now', '-90 days'))"
+                    }
+                    View::New => "datetime(created_at) >= datetime('now', '-30 days')",
+                    View::RecentlyWashed => "datetime(wash) >= datetime('now', '-7 days')",
+                }
+                .to_string(),
+            );
+        }
+
+        let terms = self
+            .search
+            .clone()
+            .map(Term::Words)
+            .into_iter()
+            .chain(
+                self.tag
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(|t| Term::Tag(t.to_string())),
+            )
+            .chain(self.color.clone().map(Term::Color))
+            .chain(self.unworn_days.map(Term::Unworn));
+
+        for term in terms.collect::<Vec<_>>().iter().chain(&self.query.0) {
+            for (condition, mut bound) in term.conditions() {
+                conditions.push(condition);
+                values.append(&mut bound);
+            }
+        }
+
+        (conditions, values)
+    }
+}
+
+/// Add an item as part of a larger transaction, returning its id.
+async fn insert_item(
+    tx: &mut Tx,
+    Item {
+        name,
+        description,
+        color,
+        tags,
+        retailer,
+        product_url,
+        return_by,
+        warranty_until,
+        care_wash,
+        care_bleach,
+        care_dry,
+        care_iron,
+        care_dry_clean,
+        chest,
+        waist,
+        inseam,
+        sleeve,
+        wash_after,
+        quantity,
+        purchased_on,
+        lifespan_months,
+        lifespan_wears,
+        price,
+        currency,
+        condition,
+        tracking,
+        warmth,
+        seasons,
+        ..
+    }: Item,
+) -> sqlx::Result<usize> {
+    let now = Utc::now().to_rfc3339();
+    let category = tags.first().map(|t| t.trim().to_lowercase());
+
+    sqlx::query(
+        r#"
+        INSERT INTO garments (
+            name, description, color, tags, retailer, product_url, return_by, warranty_until,
+            care_wash, care_bleach, care_dry, care_iron, care_dry_clean, chest, waist, inseam,
+            sleeve, wash_after, quantity, purchased_on, lifespan_months, lifespan_wears, price,
+            currency, condition, tracking, warmth, seasons, token, created_at, modified_at
+        )
+        VALUES (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+            -- items without their own limit get their category's
+            coalesce(?, (SELECT wears FROM wear_limits WHERE category = ?)),
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, lower(hex(randomblob(6))), ?, ?
+        )
+    "#,
+    )
+    .bind(name)
+    .bind(description)
+    .bind(color)
+    .bind(tags.join(","))
+    .bind(retailer)
+    .bind(product_url)
+    .bind(return_by.map(|d| d.to_string()))
+    .bind(warranty_until.map(|d| d.to_string()))
+    .bind(care_wash)
+    .bind(care_bleach)
+    .bind(care_dry)
+    .bind(care_iron)
+    .bind(care_dry_clean)
+    .bind(chest)
+    .bind(waist)
+    .bind(inseam)
+    .bind(sleeve)
+    .bind(wash_after.map(|n| n as i32))
+    .bind(category)
+    .bind(quantity.max(1) as i32)
+    .bind(purchased_on.map(|d| d.to_string()))
+    .bind(lifespan_months.map(|n| n as i32))
+    .bind(lifespan_wears.map(|n| n as i32))
+    .bind(price)
+    .bind(currency)
+    .bind(condition.map(Condition::as_str))
+    .bind(tracking.as_str())
+    .bind(warmth.map(Warmth::as_str))
+    .bind(seasons_text(&seasons))
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await?;
+    let id = sqlx::query_as::<_, (i64,)>("SELECT last_insert_rowid()")
+        .fetch_all(&mut *tx)
+        .await?
+        .pop()
+        .map_or(0, |(id,)| id);
+
+    Ok(id as usize)
+}
+
+/// Set custom field values as part of a larger transaction, as `set_custom_values` does.
+async fn write_custom_values(
+    tx: &mut Tx,
+    item_id: usize,
+    values: &[(usize, String)],
+) -> ExecResult {
+    let mut changed = 0;
+
+    for (field_id, value) in values {
+        changed += if value.is_empty() {
+            sqlx::query("DELETE FROM custom_values WHERE item_id = ? AND field_id = ?")
+                .bind(item_id as i32)
+                .bind(*field_id as i32)
+                .execute(&mut *tx)
+                .await?
+        } else {
+            sqlx::query(
+                r#"
+                INSERT INTO custom_values ( item_id, field_id, value ) VALUES ( ?, ?, ? )
+                ON CONFLICT ( item_id, field_id ) DO UPDATE SET value = excluded.value
+            "#,
+            )
+            .bind(item_id as i32)
+            .bind(*field_id as i32)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?
+        };
+    }
+
+    Ok(changed)
+}
+
+/// Record wears as part of a larger transaction, returning how many items were worn.
+async fn record_wears(tx: &mut Tx, wears: &[(usize, &WearDetails)]) -> ExecResult {
+    let mut worn = 0;
+
+    for &(item_id, details) in wears {
+        let at = utils::logged_at(details.date, Local::now()).to_rfc3339();
+        let changed = sqlx::query(
+            r#"
+            UPDATE garments SET
+                total = total + 1,
+                total_hours = total_hours + ?3,
+                count = CASE WHEN wash IS NULL OR julianday(?1) >= julianday(wash)
+                    THEN count + 1 ELSE count END,
+                wear = CASE WHEN wear IS NULL OR julianday(?1) > julianday(wear)
+                    THEN ?1 ELSE wear END
+            WHERE id = ?2 AND wishlisted_at IS NULL
+        "#,
+        )
+        .bind(&at)
+        .bind(item_id as i32)
+        .bind(
+            details
+                .metrics()
+                .find(|(metric, _)| *metric == Metric::Hours)
+                .map_or(0., |(_, hours)| hours),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if changed > 0 {
+            sqlx::query("INSERT INTO wear_events ( item_id, worn_at, note ) VALUES ( ?, ?, ? )")
+                .bind(item_id as i32)
+                .bind(&at)
+                .bind(details.note.trim())
+                .execute(&mut *tx)
+                .await?;
+            let (event_id,): (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
+                .fetch_one(&mut *tx)
+                .await?;
+
+            for (metric, value) in details.metrics() {
+                sqlx::query(
+                    "INSERT INTO wear_metrics ( event_id, kind, value ) VALUES ( ?, ?, ? )",
+                )
+                .bind(event_id)
+                .bind(metric.as_str())
+                .bind(value)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        worn += changed;
+    }
+
+    Ok(worn)
+}
+
+/// The expression a listing is ordered by, and whether that order is really ascending.
+fn sort_expression(order: &SortItems, mut ascending: bool) -> (&'static str, bool) {
+    let expression = match order {
+        SortItems::Name => "name",
+        SortItems::Count => "count",
+        SortItems::Total => "total",
+        SortItems::Description => "description",
+        // the first tag, as they were entered
+        SortItems::Tag => CATEGORY,
+        SortItems::Condition => CONDITION,
+
+        // values stored as datetimes are (to the user) in reverse sort order
+        SortItems::Wear => {
+            ascending ^= true;
+            "datetime(wear)"
+        }
+        SortItems::Wash => {
+            ascending ^= true;
+            "datetime(wash)"
+        }
+        SortItems::Modified => {
+            ascending ^= true;
+            "datetime(modified_at)"
+        }
+    };
+    (expression, ascending)
+}
+
+/// An item's seasons as they're stored: comma-separated in calendar order, or empty for all
+/// year.
+fn seasons_text(seasons: &[Season]) -> String {
+    Season::ALL
+        .iter()
+        .filter(|s| seasons.contains(s))
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Whether a sort key can be missing, as dates are for items never worn or washed.
+fn nullable(order: &SortItems) -> bool {
+    matches!(
+        order,
+        SortItems::Wear | SortItems::Wash | SortItems::Modified | SortItems::Condition
+    )
+}
+
+/// What to add to a sort key to compare it using a collation, if it's text that people type.
+fn collate(order: &SortItems, collation: Collation) -> String {
+    match order {
+        SortItems::Name | SortItems::Description => format!(" COLLATE {}", collation.sql()),
+        _ => String::new(),
+    }
+}
+
+fn list_query(
+    conditions: &[String],
+    order: &[SortItems],
+    ascending: bool,
+    nulls: Nulls,
+    collation: Collation,
+) -> String {
+    let mut cmd = format!("SELECT *, {} FROM garments", CLEANING_COST);
+
+    if !conditions.is_empty() {
+        cmd += " WHERE ";
+        cmd += &conditions.join(" AND ");
+    }
+
+    let columns = order
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let (expression, ascending) = sort_expression(column, ascending || i > 0);
+            let direction = if ascending { "ASC" } else { "DESC" };
+
+            // group missing values at one end, whichever way the rest are sorted
+            match nullable(column) {
+                true => format!(
+                    "CASE WHEN {expr} IS NULL THEN 1 ELSE 0 END {nulls}, {expr} {dir}",
+                    expr = expression,
+                    nulls = if nulls == Nulls::Last { "ASC" } else { "DESC" },
+                    dir = direction
+                ),
+                false => format!("{}{} {}", expression, collate(column, collation), direction),
+            }
+        })
+        .collect::<Vec<_>>();
+    if !columns.is_empty() {
+        cmd += " ORDER BY ";
+        cmd += &columns.join(", ");
+    }
+
+    cmd
+}
+
+/// Where a page of a listing starts: after an item, or before one, by its sort key and ID. Handed
+/// to clients as an opaque string, so that the page is next to that item even if others were added
+/// since.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Cursor {
+    /// Null for the very end of the listing
+    key: serde_json::Value,
+    id: usize,
+    #[serde(default)]
+    before: bool,
+}
+
+impl Cursor {
+    /// The last page of a listing, however long it is.
+    pub(crate) fn last() -> Self {
+        Self {
+            key: serde_json::Value::Null,
+            id: 0,
+            before: true,
+        }
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub(crate) fn decode(s: &str) -> Option<Self> {
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| s.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+            .collect::<Option<Vec<_>>>()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A new database in a directory of its own.
+    async fn scratch(name: &str) -> anyhow::Result<Connection> {
+        let directory = std::env::temp_dir().join(format!("wear-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory).await;
+        Connection::new(&directory, OsStr::new("wear.db")).await
+    }
+
+    async fn count(conn: &Connection, table: &str) -> sqlx::Result<i32> {
+        let (n,): (i32,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(&conn.0)
+            .await?;
+        Ok(n)
+    }
+
+    #[tokio::test]
+    async fn delete_item() -> anyhow::Result<()> {
+        let conn = scratch("delete-item").await?;
+        (&conn.0)
+            .execute(
+                r#"
+                INSERT INTO garments ( id, name, color, deleted_at )
+                VALUES ( 1, 'old', '#000000', '2020-01-01T00:00:00+00:00' );
+                INSERT INTO garments ( id, name, color, replaced_by ) VALUES ( 2, 'new', '#000000', 1 );
+                INSERT INTO wear_events ( id, item_id, worn_at ) VALUES ( 1, 1, '2019-06-01T00:00:00+00:00' );
+                INSERT INTO wear_metrics ( event_id, kind, value ) VALUES ( 1, 'hours', 2 );
+                INSERT INTO item_tasks ( item_id, task, flagged_at ) VALUES ( 1, 'mend', '2019-06-01' );
+                INSERT INTO attachments ( item_id, file_name, content_type, size, stored_as, uploaded_at )
+                VALUES ( 1, 'a.jpg', 'image/jpeg', 1, 'a', '2019-06-01' );
+            "#,
+            )
+            .await?;
+
+        assert_eq!(conn.delete_item(1).await?, 1);
+        for table in &["wear_events", "wear_metrics", "item_tasks", "attachments"] {
+            assert_eq!(count(&conn, table).await?, 0, "{}", table);
+        }
+        let (replaced_by,): (Option<i32>,) =
+            sqlx::query_as("SELECT replaced_by FROM garments WHERE id = 2")
+                .fetch_one(&conn.0)
+                .await?;
+        assert_eq!(replaced_by, None);
+
+        // the id isn't handed out again
+        (&conn.0)
+            .execute("INSERT INTO garments ( name, color ) VALUES ( 'newer', '#000000' )")
+            .await?;
+        let (id,): (i32,) = sqlx::query_as("SELECT MAX(id) FROM garments")
+            .fetch_one(&conn.0)
+            .await?;
+        assert_eq!(id, 3);
+
+        // only from the trash
+        assert_eq!(conn.delete_item(2).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrate_keeps_history() -> anyhow::Result<()> {
+        let directory = std::env::temp_dir().join(format!("wear-migrate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory).await;
+        fs::create_dir_all(&directory).await?;
+        let file = directory.join("wear.db");
+
+        // a database from before items were rebuilt, with something referring to one
+        let pool = SqlitePool::new(&url(&file)?).await?;
+        (&pool).execute(include_str!("./schema.sql")).await?;
+        for (_, migration) in &MIGRATIONS[..42] {
+            (&pool).execute(*migration).await?;
+        }
+        (&pool)
+            .execute(
+                r#"
+                PRAGMA user_version = 42;
+                INSERT INTO garments ( id, name, color ) VALUES ( 1, 'shirt', '#000000' );
+                INSERT INTO wear_events ( item_id, worn_at ) VALUES ( 1, '2020-01-01T00:00:00+00:00' );
+            "#,
+            )
+            .await?;
+        pool.close().await;
+
+        let conn = Connection::new(&directory, OsStr::new("wear.db")).await?;
+        assert_eq!(count(&conn, "garments").await?, 1);
+        assert_eq!(count(&conn, "wear_events").await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repair_planned_wears() -> anyhow::Result<()> {
+        let conn = scratch("repair-planned-wears").await?;
+        // records that refer to nothing can only be made with foreign keys off
+        conn.0
+            .acquire()
+            .await?
+            .execute(
+                r#"
+                PRAGMA foreign_keys = OFF;
+                INSERT INTO garments ( id, name, color ) VALUES ( 1, 'shirt', '#000000' );
+                INSERT INTO planned_wears ( planned_on, item_id ) VALUES ( '2020-01-01', 1 );
+                INSERT INTO planned_wears ( planned_on, item_id ) VALUES ( '2020-01-02', 2 );
+                INSERT INTO planned_wears ( planned_on, outfit_id ) VALUES ( '2020-01-03', 1 );
+                PRAGMA foreign_keys = ON;
+            "#,
+            )
+            .await?;
+
+        // with no outfits at all, a plan for an item that exists is still fine
+        let found = conn.repair().await?;
+        assert!(found.contains(&("planned wears of items or outfits that no longer exist", 2)));
+        let (planned_on,): (String,) = sqlx::query_as("SELECT planned_on FROM planned_wears")
+            .fetch_one(&conn.0)
+            .await?;
+        assert_eq!(planned_on, "2020-01-01");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn flag_task() -> anyhow::Result<()> {
+        let conn = scratch("flag-task").await?;
+        (&conn.0)
+            .execute(
+                r#"
+                INSERT INTO garments ( id, name, color ) VALUES ( 1, 'shirt', '#000000' );
+                INSERT INTO garments ( id, name, color, deleted_at )
+                VALUES ( 2, 'old', '#000000', '2020-01-01T00:00:00+00:00' );
+            "#,
+            )
+            .await?;
+
+        assert_eq!(conn.flag_task(1, Task::Mend, "").await?, 1);
+        assert_eq!(conn.flag_task(1, Task::Mend, "button").await?, 1);
+        assert_eq!(conn.flag_task(2, Task::Mend, "").await?, 0);
+        assert_eq!(conn.flag_task(3, Task::Mend, "").await?, 0);
+        assert_eq!(count(&conn, "item_tasks").await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn undo_backdated_wear() -> anyhow::Result<()> {
+        let conn = scratch("undo-backdated-wear").await?;
+        (&conn.0)
+            .execute("INSERT INTO garments ( id, name, color ) VALUES ( 1, 'shirt', '#000000' )")
+            .await?;
+
+        let today = Local::today().naive_local();
+        conn.log_wear(1, &WearDetails::default()).await?;
+        let backdated = WearDetails {
+            date: Some(today - chrono::Duration::days(10)),
+            ..WearDetails::default()
+        };
+        conn.log_wear(1, &backdated).await?;
+
+        // the stray backdated tap comes off, and today's wear stays
+        assert_eq!(conn.undo_wear(1).await?, 1);
+        let item = conn.get_item(1).await?;
+        assert_eq!(item.total_count, 1);
+        assert_eq!(
+            item.last_wear
+                .map(|w| w.with_timezone(&Local).date().naive_local()),
+            Some(today)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cost_per_wear_counts_cleaning() -> anyhow::Result<()> {
+        let conn = scratch("cost-per-wear").await?;
+        (&conn.0)
+            .execute(
+                r#"
+                INSERT INTO garments ( id, name, color, total, price )
+                VALUES ( 1, 'shirt', '#000000', 2, 10 );
+                INSERT INTO garments ( id, name, color, total, price, currency )
+                VALUES ( 2, 'coat', '#000000', 2, 10, 'JPY' );
+                INSERT INTO wash_events ( item_id, washed_at, cost ) VALUES ( 1, '2020-01-01', 4 );
+                INSERT INTO wash_events ( item_id, washed_at, cost ) VALUES ( 2, '2020-01-01', 4 );
+            "#,
+            )
+            .await?;
+
+        assert_eq!(conn.get_item(1).await?.cost_per_wear("USD"), Some(7.));
+        // cleaning paid in another currency doesn't add to the price
+        let listed = conn.get_all(&[SortItems::Name], true).await?;
+        assert_eq!(listed[0].name, "coat");
+        assert_eq!(listed[0].cost_per_wear("USD"), Some(5.));
+        assert_eq!(listed[1].cost_per_wear("USD"), Some(7.));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pages_both_ways() -> anyhow::Result<()> {
+        let conn = scratch("pages").await?;
+        for name in &["a", "b", "c", "d", "e"] {
+            sqlx::query("INSERT INTO garments ( name, color ) VALUES ( ?, '#000000' )")
+                .bind(name)
+                .execute(&conn.0)
+                .await?;
+        }
+        let filter = ListFilter::default();
+        let order = Some(SortItems::Name);
+        let names = |items: &[Item]| items.iter().map(|i| i.name.clone()).collect::<Vec<_>>();
+
+        let (items, prev, next) = conn.get_page(&filter, &order, true, None, 2).await?;
+        assert_eq!(
+            (names(&items), prev.is_some()),
+            (vec!["a".into(), "b".into()], false)
+        );
+        let (items, prev, next) = conn
+            .get_page(&filter, &order, true, next.as_ref(), 2)
+            .await?;
+        assert_eq!(names(&items), vec!["c", "d"]);
+        let (items, _, end) = conn
+            .get_page(&filter, &order, true, next.as_ref(), 2)
+            .await?;
+        assert_eq!((names(&items), end), (vec!["e".into()], None));
+
+        let (items, prev, next) = conn
+            .get_page(&filter, &order, true, prev.as_ref(), 2)
+            .await?;
+        assert_eq!((names(&items), prev), (vec!["a".into(), "b".into()], None));
+        assert!(next.is_some());
+
+        let (items, prev, next) = conn
+            .get_page(&filter, &order, true, Some(&Cursor::last()), 2)
+            .await?;
+        assert_eq!((names(&items), next), (vec!["d".into(), "e".into()], None));
+        let (items, _, _) = conn
+            .get_page(&filter, &order, true, prev.as_ref(), 2)
+            .await?;
+        assert_eq!(names(&items), vec!["b", "c"]);
+        Ok(())
+    }
 }
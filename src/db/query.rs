@@ -0,0 +1,289 @@
+//! A small filter language for bookmarkable searches, e.g. `tag:wool color:#112233 count>3
+//! unworn>30d`. Terms are separated by spaces and must all match. Values with spaces can be
+//! quoted, as in `tag:"rain gear"`, and bare words search names, descriptions and tags.
+//!
+//! Supported terms:
+//!
+//! * `tag:NAME` - carries the tag, ignoring case
+//! * `color:#RRGGBB` or `color:FAMILY` - exactly that color, or any in a family like "black"
+//! * `count` or `total` compared with `>`, `>=`, `<`, `<=` or `=` to a number - wears since the
+//!   last wash, or ever
+//! * `unworn>N` - not worn in more than N days (or `Nw` weeks), or never
+//! * `needs:wash` - worn as many times since the last wash as it should be between washes, or
+//!   for items with several pieces, that many times each
+//! * `condition:RATING` - last rated `new`, `good`, `worn` or `retire-soon`
+//! * `care:dry-clean` - not to be washed at home, only dry cleaned
+
+use {
+    super::super::{colors::ColorFilter, condition::Condition, search},
+    std::{fmt, str::FromStr},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn operator(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Equal => "=",
+            Self::GreaterOrEqual => ">=",
+            Self::Greater => ">",
+        }
+    }
+}
+
+/// One condition an item must meet.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Term {
+    /// Words that each start one of the item's words
+    Words(String),
+    Tag(String),
+    Color(ColorFilter),
+    Count(Comparison, i64),
+    Total(Comparison, i64),
+    /// Not worn in more than this many days, or ever
+    Unworn(u32),
+    /// Worn at least as often as it goes between washes
+    NeedsWash,
+    Condition(Condition),
+    /// Can't be washed, only dry cleaned
+    DryCleanOnly,
+}
+
+impl Term {
+    /// SQL conditions for the term, with the values to bind to their parameters.
+    pub(crate) fn conditions(&self) -> Vec<(String, Vec<String>)> {
+        match self {
+            Self::Words(words) => search::patterns(words)
+                .into_iter()
+                .map(|pattern| {
+                    (
+                        "id IN (SELECT item_id FROM search_terms WHERE term GLOB ?)".to_string(),
+                        vec![pattern],
+                    )
+                })
+                .collect(),
+            Self::Tag(tag) => vec![(
+                "instr(',' || lower(tags) || ',', ?) > 0".into(),
+                vec![format!(",{},", tag.to_lowercase())],
+            )],
+            Self::Color(ColorFilter::Exact(hex)) => {
+                vec![("lower(color) = ?".into(), vec![hex.clone()])]
+            }
+            Self::Color(ColorFilter::Family(family)) => {
+                vec![("color_family = ?".into(), vec![family.clone()])]
+            }
+            // the columns are integers, so the bound text is compared as a number
+            Self::Count(comparison, n) => vec![(
+                format!("count {} ?", comparison.operator()),
+                vec![n.to_string()],
+            )],
+            Self::Total(comparison, n) => vec![(
+                format!("total {} ?", comparison.operator()),
+                vec![n.to_string()],
+            )],
+            Self::Unworn(days) => vec![(
+                "(wear IS NULL OR datetime(wear) < datetime('now', ?))".into(),
+                vec![format!("-{} days", days)],
+            )],
+            Self::NeedsWash => vec![(
+                "(wash_after IS NOT NULL AND count >= wash_after * quantity)".into(),
+                Vec::new(),
+            )],
+            Self::Condition(condition) => {
+                vec![("condition = ?".into(), vec![condition.as_str().into()])]
+            }
+            Self::DryCleanOnly => vec![(
+                "(care_wash = 'no' AND care_dry_clean != 'no')".into(),
+                Vec::new(),
+            )],
+        }
+    }
+}
+
+/// Why a filter couldn't be understood.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error<T>(message: String) -> Result<T, ParseError> {
+    Err(ParseError(message))
+}
+
+/// A parsed filter: every term must match.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Query(pub(crate) Vec<Term>);
+
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        split(input)?
+            .iter()
+            .map(|token| parse_term(token))
+            .collect::<Result<_, _>>()
+            .map(Query)
+    }
+}
+
+/// Split on spaces outside double quotes, dropping the quotes.
+fn split(input: &str) -> Result<Vec<String>, ParseError> {
+    let (mut tokens, mut current, mut quoted) = (Vec::new(), String::new(), false);
+
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if quoted {
+        return error("Missing closing quote".into());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, ParseError> {
+    let split_at = match token.find([':', '<', '>', '=']) {
+        Some(i) => i,
+        None => return Ok(Term::Words(token.to_string())),
+    };
+    let (key, rest) = token.split_at(split_at);
+
+    let (comparison, value) = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+        (":", Comparison::Equal),
+    ]
+    .iter()
+    .find_map(|(op, comparison)| rest.strip_prefix(op).map(|value| (*comparison, value)))
+    .unwrap_or((Comparison::Equal, rest));
+
+    if value.is_empty() {
+        return error(format!("Nothing to compare \"{}\" with", key));
+    }
+    let number = || {
+        value
+            .parse::<i64>()
+            .or_else(|_| error(format!("\"{}\" is not a number", value)))
+    };
+
+    match (key.to_lowercase().as_str(), rest.starts_with(':')) {
+        ("tag", true) => Ok(Term::Tag(value.to_string())),
+        ("color", true) => ColorFilter::new(value)
+            .map(Term::Color)
+            .ok_or_else(|| ParseError("Missing color".into())),
+        ("count", _) => Ok(Term::Count(comparison, number()?)),
+        ("total", _) => Ok(Term::Total(comparison, number()?)),
+        ("unworn", _) if matches!(comparison, Comparison::Greater | Comparison::GreaterOrEqual) => {
+            let (digits, per_unit) = match value.to_lowercase() {
+                v if v.ends_with('w') => (v.trim_end_matches('w').to_string(), 7),
+                v => (v.trim_end_matches('d').to_string(), 1),
+            };
+            digits
+                .parse::<u32>()
+                .ok()
+                .and_then(|n| n.checked_mul(per_unit))
+                .map(Term::Unworn)
+                .ok_or_else(|| ParseError(format!("\"{}\" is not a number of days", value)))
+        }
+        ("unworn", _) => error("Use unworn> with a number of days, like unworn>30d".into()),
+        ("needs", true) if value.eq_ignore_ascii_case("wash") => Ok(Term::NeedsWash),
+        ("needs", _) => error("Use needs:wash for items due a wash".into()),
+        ("care", true) if value.eq_ignore_ascii_case("dry-clean") => Ok(Term::DryCleanOnly),
+        ("care", _) => error("Use care:dry-clean for items that can only be dry cleaned".into()),
+        ("condition", true) => value
+            .to_lowercase()
+            .parse()
+            .map(Term::Condition)
+            .or_else(|_| error("Use condition: with new, good, worn or retire-soon".into())),
+        ("tag", _) | ("color", _) | ("condition", _) => {
+            error(format!("Use {}: to filter by {}", key, key))
+        }
+        _ => error(format!("Unknown filter \"{}\"", key)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let query = "tag:wool color:#112233 count>3 unworn>30d oxford".parse::<Query>();
+        assert_eq!(
+            query,
+            Ok(Query(vec![
+                Term::Tag("wool".into()),
+                Term::Color(ColorFilter::Exact("#112233".into())),
+                Term::Count(Comparison::Greater, 3),
+                Term::Unworn(30),
+                Term::Words("oxford".into()),
+            ]))
+        );
+
+        let query =
+            "  tag:\"rain gear\" total<=10 unworn>2w color:Black needs:wash condition:Retire-Soon \
+             care:dry-clean"
+                .parse::<Query>();
+        assert_eq!(
+            query,
+            Ok(Query(vec![
+                Term::Tag("rain gear".into()),
+                Term::Total(Comparison::LessOrEqual, 10),
+                Term::Unworn(14),
+                Term::Color(ColorFilter::Family("black".into())),
+                Term::NeedsWash,
+                Term::Condition(Condition::RetireSoon),
+                Term::DryCleanOnly,
+            ]))
+        );
+
+        assert_eq!("".parse::<Query>(), Ok(Query::default()));
+    }
+
+    #[test]
+    fn errors() {
+        for bad in &[
+            "size:m",
+            "count>many",
+            "count>",
+            "unworn<30",
+            "tag=wool",
+            "tag:\"rain",
+            "needs:iron",
+            "condition:mint",
+            "condition>good",
+            "care:hand",
+        ] {
+            assert!(bad.parse::<Query>().is_err(), "{}", bad);
+        }
+    }
+}
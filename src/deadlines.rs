@@ -0,0 +1,102 @@
+use {
+    super::Item,
+    chrono::{Duration, NaiveDate},
+    serde::Serialize,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Deadline {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) kind: &'static str,
+    pub(crate) date: NaiveDate,
+    pub(crate) days_left: i64,
+}
+
+/// Every return window and warranty that hasn't lapsed as of `today`, soonest first.
+pub(crate) fn upcoming(items: &[Item], today: NaiveDate) -> Vec<Deadline> {
+    let mut deadlines = items
+        .iter()
+        .flat_map(|item| {
+            vec![
+                ("Return by", item.return_by),
+                ("Warranty until", item.warranty_until),
+            ]
+            .into_iter()
+            .filter_map(move |(kind, date)| {
+                date.filter(|d| *d >= today).map(|date| Deadline {
+                    key: item.id,
+                    name: item.name.clone(),
+                    kind,
+                    date,
+                    days_left: (date - today).num_days(),
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    deadlines.sort_by_key(|d| d.date);
+    deadlines
+}
+
+/// The subset of `upcoming` deadlines falling within the next `days` days.
+pub(crate) fn due_within(items: &[Item], today: NaiveDate, days: u32) -> Vec<Deadline> {
+    let horizon = today + Duration::days(i64::from(days));
+
+    upcoming(items, today)
+        .into_iter()
+        .filter(|d| d.date <= horizon)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(id: usize, return_by: Option<NaiveDate>, warranty_until: Option<NaiveDate>) -> Item {
+        Item {
+            id,
+            name: format!("item {}", id),
+            return_by,
+            warranty_until,
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn sorted_and_lapsed_dropped() {
+        let today = NaiveDate::from_ymd(2020, 6, 1);
+        let items = vec![
+            item(
+                1,
+                Some(NaiveDate::from_ymd(2020, 5, 1)),
+                Some(NaiveDate::from_ymd(2022, 5, 1)),
+            ),
+            item(2, Some(NaiveDate::from_ymd(2020, 6, 10)), None),
+        ];
+
+        let found = upcoming(&items, today);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            (found[0].key, found[0].kind, found[0].days_left),
+            (2, "Return by", 9)
+        );
+        assert_eq!((found[1].key, found[1].kind), (1, "Warranty until"));
+    }
+
+    #[test]
+    fn within_horizon() {
+        let today = NaiveDate::from_ymd(2020, 6, 1);
+        let items = vec![
+            item(1, Some(NaiveDate::from_ymd(2020, 6, 8)), None),
+            item(2, Some(NaiveDate::from_ymd(2020, 6, 9)), None),
+        ];
+
+        let found = due_within(&items, today, 7);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].key, 1);
+    }
+}
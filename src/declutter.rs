@@ -0,0 +1,164 @@
+//! "What if I let these go?": the wardrobe's numbers as they are, next to what they would be
+//! without a chosen set of items. Nothing is changed; it's only worked out for the page.
+
+use {
+    super::{colors, Item},
+    serde::Serialize,
+    std::collections::{BTreeSet, HashMap},
+};
+
+/// How far back wears count as recent.
+pub(crate) const RECENT_DAYS: i64 = 90;
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Stats {
+    pub(crate) items: usize,
+    pub(crate) categories: usize,
+    pub(crate) color_families: usize,
+    /// Wears in the last `RECENT_DAYS` days
+    pub(crate) recent_wears: u32,
+    /// Items not worn at all in that time
+    pub(crate) unworn: usize,
+    /// What the items cost, counting only prices in the wardrobe's currency
+    pub(crate) value: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Comparison {
+    pub(crate) now: Stats,
+    pub(crate) after: Stats,
+    /// Categories that would have nothing left in them
+    pub(crate) emptied: Vec<String>,
+    /// How much of the recent wear went to the items that would go, in percent
+    pub(crate) wear_share: u32,
+}
+
+/// An item's category, which is its first tag.
+fn category(item: &Item) -> Option<String> {
+    item.tags
+        .first()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+}
+
+fn stats<'a>(
+    items: impl Iterator<Item = &'a Item>,
+    recent: &HashMap<usize, u32>,
+    currency: &str,
+) -> Stats {
+    let (mut categories, mut families) = (BTreeSet::new(), BTreeSet::new());
+    let mut stats = Stats::default();
+
+    for item in items {
+        stats.items += 1;
+        categories.extend(category(item));
+        families.extend(colors::family(&item.color));
+        match recent.get(&item.id) {
+            Some(n) if *n > 0 => stats.recent_wears += n,
+            _ => stats.unworn += 1,
+        }
+        if item.currency.is_empty() || item.currency == currency {
+            stats.value += item.price.unwrap_or_default();
+        }
+    }
+
+    stats.categories = categories.len();
+    stats.color_families = families.len();
+    stats
+}
+
+/// Compare the wardrobe with and without the `removed` items, given each item's recent wears and
+/// the currency prices are counted in.
+pub(crate) fn compare(
+    items: &[Item],
+    recent: &HashMap<usize, u32>,
+    removed: &[usize],
+    currency: &str,
+) -> Comparison {
+    let kept = || items.iter().filter(|i| !removed.contains(&i.id));
+    let now = stats(items.iter(), recent, currency);
+    let after = stats(kept(), recent, currency);
+
+    let left = kept().filter_map(category).collect::<BTreeSet<_>>();
+    let emptied = items
+        .iter()
+        .filter(|i| removed.contains(&i.id))
+        .filter_map(category)
+        .filter(|c| !left.contains(c))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let wear_share = match now.recent_wears {
+        0 => 0,
+        total => (total - after.recent_wears) * 100 / total,
+    };
+
+    Comparison {
+        now,
+        after,
+        emptied,
+        wear_share,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(id: usize, tag: &str, color: &str, price: f64) -> Item {
+        Item {
+            id,
+            name: format!("item {}", id),
+            tags: vec![tag.into()],
+            color: color.into(),
+            price: Some(price),
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn without_some_items() {
+        let items = vec![
+            item(1, "top", "#000000", 20.),
+            item(2, "Top", "#ffffff", 30.),
+            item(3, "shoes", "#000000", 100.),
+            Item {
+                currency: "EUR".into(),
+                ..item(4, "bottom", "#0000ff", 50.)
+            },
+        ];
+        let recent = vec![(1, 6), (3, 2)].into_iter().collect();
+
+        let comparison = compare(&items, &recent, &[1, 3], "USD");
+
+        assert_eq!(
+            comparison.now,
+            Stats {
+                items: 4,
+                categories: 3,
+                color_families: 3,
+                recent_wears: 8,
+                unworn: 2,
+                value: 150.,
+            }
+        );
+        assert_eq!(
+            comparison.after,
+            Stats {
+                items: 2,
+                categories: 2,
+                color_families: 2,
+                recent_wears: 0,
+                unworn: 2,
+                value: 30.,
+            }
+        );
+        assert_eq!(comparison.emptied, vec!["shoes".to_string()]);
+        assert_eq!(comparison.wear_share, 100);
+
+        assert_eq!(compare(&items, &recent, &[], "USD").wear_share, 0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Checks that an installation is in working order. `wear doctor` runs all of them and says what
+//! to do about anything that isn't; the server runs the quick ones before it starts listening.
+
+use {
+    super::{db, location, media::Media, template, utils},
+    std::{
+        env,
+        path::{Path, PathBuf},
+    },
+    tokio::fs,
+};
+
+/// How one check turned out, with an explanation or a suggested fix.
+enum Outcome {
+    Fine(String),
+    Warning(String),
+    Problem(String),
+}
+
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Fine(_) => "ok",
+            Self::Warning(_) => "warning",
+            Self::Problem(_) => "PROBLEM",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Fine(m) | Self::Warning(m) | Self::Problem(m) => m,
+        }
+    }
+}
+
+fn templates() -> Outcome {
+    match template::init() {
+        Ok(_) => Outcome::Fine("all templates parse".into()),
+        Err(e) => Outcome::Problem(format!(
+            "{}. The templates are built in, so this build is broken; reinstall wear.",
+            e
+        )),
+    }
+}
+
+fn public_url(url: Option<&str>) -> Outcome {
+    match url {
+        None => Outcome::Fine("not set; links in notifications will use the host and port".into()),
+        Some(url) if utils::is_web_url(url) => Outcome::Fine(url.into()),
+        Some(url) => Outcome::Problem(format!(
+            "{} is not a web address; pass something like --public-url https://wear.example.com",
+            url
+        )),
+    }
+}
+
+/// Whether a command can be found, the same way it will be when it's run.
+async fn notify_command(command: Option<&Path>) -> Outcome {
+    let command = match command {
+        Some(c) => c,
+        None => return Outcome::Fine("not set; notifications are only logged".into()),
+    };
+
+    let candidates = match command.components().count() {
+        // a bare name is looked up on the PATH
+        1 => env::var_os("PATH")
+            .map(|paths| {
+                env::split_paths(&paths)
+                    .map(|dir| dir.join(command))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => vec![command.to_path_buf()],
+    };
+
+    for candidate in candidates {
+        if let Ok(metadata) = fs::metadata(&candidate).await {
+            if !metadata.is_file() {
+                continue;
+            }
+            if !is_executable(&metadata) {
+                return Outcome::Problem(format!(
+                    "{} is not executable; try chmod +x {}",
+                    candidate.display(),
+                    candidate.display()
+                ));
+            }
+            return Outcome::Fine(candidate.display().to_string());
+        }
+    }
+
+    Outcome::Problem(format!(
+        "{} was not found; check the path given to --notify-command",
+        command.display()
+    ))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_: &std::fs::Metadata) -> bool {
+    true
+}
+
+async fn database(file: &Path) -> Outcome {
+    if fs::metadata(file).await.is_err() {
+        return Outcome::Warning(format!(
+            "{} doesn't exist yet; it will be created when the server starts",
+            file.display()
+        ));
+    }
+
+    let health = match db::health(file).await {
+        Ok(h) => h,
+        Err(e) => {
+            return Outcome::Problem(format!(
+                "could not read {}: {}. Check that it is a wear database and that you can write to it and its directory.",
+                file.display(),
+                e
+            ))
+        }
+    };
+
+    if health.integrity != "ok" {
+        Outcome::Problem(format!(
+            "{} is damaged ({}); restore it from a backup",
+            file.display(),
+            health.integrity
+        ))
+    } else if health.schema_version > health.latest_version {
+        Outcome::Problem(format!(
+            "{} is at schema version {}, newer than this build knows ({}); upgrade wear",
+            file.display(),
+            health.schema_version,
+            health.latest_version
+        ))
+    } else if health.schema_version < health.latest_version {
+        Outcome::Warning(format!(
+            "{} is at schema version {} of {}; the rest will be applied when the server starts (see wear migrate --dry-run)",
+            file.display(),
+            health.schema_version,
+            health.latest_version
+        ))
+    } else {
+        Outcome::Fine(format!(
+            "{} is at schema version {}",
+            file.display(),
+            health.schema_version
+        ))
+    }
+}
+
+async fn media_directory(directory: &Path) -> Outcome {
+    if fs::metadata(directory).await.is_err() {
+        return Outcome::Warning(format!(
+            "{} doesn't exist yet; it will be created when the server starts",
+            directory.display()
+        ));
+    }
+
+    let writable = match Media::new(directory.to_path_buf()).await {
+        Ok(media) => media.check_writable().await,
+        Err(e) => Err(e),
+    };
+    match writable {
+        Ok(()) => Outcome::Fine(format!("{} is writable", directory.display())),
+        Err(e) => Outcome::Problem(format!(
+            "cannot store files in {}: {}. Check its owner and permissions.",
+            directory.display(),
+            e
+        )),
+    }
+}
+
+/// Run every check, print the results, and say whether there were any problems.
+pub(crate) async fn run(
+    data_path: Option<PathBuf>,
+    public_url: Option<&str>,
+    notify_command: Option<&Path>,
+) -> bool {
+    let mut checks = vec![
+        ("Templates", templates()),
+        ("Public URL", self::public_url(public_url)),
+        (
+            "Notification command",
+            self::notify_command(notify_command).await,
+        ),
+    ];
+
+    match location::database_file(data_path).await {
+        Ok((directory, file_name)) => {
+            checks.push(("Database", database(&directory.join(file_name)).await));
+            checks.push((
+                "Media directory",
+                media_directory(&directory.join("media")).await,
+            ));
+        }
+        Err(e) => checks.push((
+            "Data location",
+            Outcome::Problem(format!("{}; pass a usable --data-path", e)),
+        )),
+    }
+
+    for (name, outcome) in &checks {
+        println!("{:>7}  {}: {}", outcome.label(), name, outcome.message());
+    }
+
+    !checks
+        .iter()
+        .any(|(_, outcome)| matches!(outcome, Outcome::Problem(_)))
+}
+
+/// The checks worth doing on every start: stop on anything that would break the server later,
+/// and mention anything that would only break notifications. The database checks its own schema
+/// version when it's opened.
+pub(crate) async fn preflight(
+    public_url: Option<&str>,
+    notify_command: Option<&Path>,
+    media: &Media,
+) -> anyhow::Result<()> {
+    if let Outcome::Problem(message) = self::public_url(public_url) {
+        anyhow::bail!("Public URL: {}", message);
+    }
+
+    if let Err(e) = media.check_writable().await {
+        anyhow::bail!("Cannot store uploaded files: {}", e);
+    }
+
+    if let Outcome::Problem(message) = self::notify_command(notify_command).await {
+        eprintln!("Warning: notification command: {}", message);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! What each edit of an item changed, field by field, kept so an item's changes page can show its
+//! details as they were before. Wears and washes aren't edits; the history page has those.
+
+use {super::Item, serde::Serialize, serde_json::Value};
+
+/// Fields that change by using an item rather than by editing it, or that aren't edited at all.
+const NOT_EDITS: &[&str] = &[
+    "id",
+    "count",
+    "total_count",
+    "total_hours",
+    "last_wear",
+    "last_wash",
+    "token",
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Edit {
+    pub(crate) field: String,
+    /// The values as shown on the page; empty when there wasn't one
+    pub(crate) before: String,
+    pub(crate) after: String,
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The fields that differ between two versions of an item, by name.
+pub(crate) fn diff(before: &Item, after: &Item) -> Vec<Edit> {
+    let (before, after) = match (serde_json::to_value(before), serde_json::to_value(after)) {
+        (Ok(Value::Object(before)), Ok(Value::Object(after))) => (before, after),
+        _ => return Vec::new(),
+    };
+
+    before
+        .iter()
+        .filter(|(field, _)| !NOT_EDITS.contains(&field.as_str()))
+        .filter_map(|(field, old)| {
+            let new = after.get(field)?;
+            if old == new {
+                return None;
+            }
+            Some(Edit {
+                field: field.clone(),
+                before: text(old),
+                after: text(new),
+            })
+        })
+        .collect()
+}
+
+/// A field's name as shown on the page, so `wash_after` is "Wash after".
+pub(crate) fn label(field: &str) -> String {
+    let words = field.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn changed_fields() {
+        let before = Item {
+            id: 4,
+            name: "Coat".into(),
+            tags: vec!["outerwear".into()],
+            price: Some(120.),
+            count: 2,
+            ..Item::default()
+        };
+        let after = Item {
+            name: "Wool coat".into(),
+            tags: vec!["outerwear".into(), "wool".into()],
+            price: None,
+            count: 3,
+            ..before.clone()
+        };
+
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                Edit {
+                    field: "name".into(),
+                    before: "Coat".into(),
+                    after: "Wool coat".into(),
+                },
+                Edit {
+                    field: "price".into(),
+                    before: "120.0".into(),
+                    after: "".into(),
+                },
+                Edit {
+                    field: "tags".into(),
+                    before: "outerwear".into(),
+                    after: "outerwear, wool".into(),
+                },
+            ]
+        );
+        assert!(diff(&before, &before).is_empty());
+        assert_eq!(label("wash_after"), "Wash after");
+    }
+}
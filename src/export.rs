@@ -0,0 +1,232 @@
+use {
+    super::{
+        db::{Archived, Connection, ListFilter},
+        import,
+        media::Media,
+        Item, Nulls,
+    },
+    serde_json::{json, Map, Value},
+    std::{
+        collections::{HashMap, HashSet},
+        io::{Cursor, Write},
+    },
+    warp::http::{Response, StatusCode},
+    zip::{write::FileOptions, CompressionMethod, ZipWriter},
+};
+
+/// Build a copy of the wardrobe that is safe to share: names and descriptions are dropped and
+/// items are renumbered, while tags, colors, counts and the timing of every wear are kept.
+pub(crate) fn anonymized(
+    items: &[Item],
+    wears: &[(i32, String)],
+    summaries: &[(i32, String, i32)],
+) -> Value {
+    let mut events: HashMap<usize, Vec<&str>> = HashMap::new();
+    for (id, worn_at) in wears {
+        events.entry(*id as usize).or_default().push(worn_at);
+    }
+
+    let mut monthly: HashMap<usize, Map<String, Value>> = HashMap::new();
+    for (id, month, count) in summaries {
+        monthly
+            .entry(*id as usize)
+            .or_default()
+            .insert(month.clone(), json!(count));
+    }
+
+    let items = items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            json!({
+                "id": idx + 1,
+                "tags": item.tags.iter().filter(|t| !t.is_empty()).collect::<Vec<_>>(),
+                "color": item.color,
+                "count": item.count,
+                "totalCount": item.total_count,
+                "lastWear": item.last_wear,
+                "lastWash": item.last_wash,
+                "wears": events.remove(&item.id).unwrap_or_default(),
+                "monthlyWears": monthly.remove(&item.id).unwrap_or_default(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "items": items })
+}
+
+/// Names for photos in an archive, given each one's item name and file extension: the item's
+/// name as the photo import matches it, with `-2`, `-3` and so on after the first so that no two
+/// collide.
+pub(crate) fn photo_names<'a>(photos: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    photos
+        .into_iter()
+        .map(|(name, extension)| {
+            let stem = match import::slugify(name) {
+                s if s.is_empty() => "item".to_string(),
+                s => s,
+            };
+            let name_for = |n: usize| match (n, extension) {
+                (1, "") => stem.clone(),
+                (1, ext) => format!("{}.{}", stem, ext),
+                (n, "") => format!("{}-{}", stem, n),
+                (n, ext) => format!("{}-{}.{}", stem, n, ext),
+            };
+            let name = (1..)
+                .map(name_for)
+                .find(|n| !taken.contains(n))
+                .unwrap_or_default();
+            taken.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+fn zip_photos(files: Vec<(String, Vec<u8>)>) -> zip::result::ZipResult<Vec<u8>> {
+    // photos are compressed already, so deflating them again only costs time
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        archive.start_file(name, options)?;
+        archive.write_all(&data)?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+fn fail(e: impl std::fmt::Display) -> warp::Rejection {
+    eprintln!("photo export: {}", e);
+    warp::reject::not_found()
+}
+
+/// A zip of every photo of a current or archived item, named so that the photo import can put
+/// them back. The archive is put together in memory before it is sent, since a zip's index goes
+/// at the end.
+pub(crate) async fn photos(
+    conn: Connection,
+    media: Media,
+) -> Result<Response<Vec<u8>>, warp::Rejection> {
+    let filter = ListFilter {
+        archived: Archived::Included,
+        ..ListFilter::default()
+    };
+    let items = conn
+        .get_view(&filter, &[], true, Nulls::Last)
+        .await
+        .map_err(fail)?
+        .into_iter()
+        .map(|item| (item.id, item.name))
+        .collect::<HashMap<_, _>>();
+    let photos = conn
+        .get_all_attachments()
+        .await
+        .map_err(fail)?
+        .into_iter()
+        .filter(|a| a.content_type.starts_with("image/") && items.contains_key(&a.item_id))
+        .collect::<Vec<_>>();
+
+    let extensions = photos
+        .iter()
+        .map(|a| {
+            Media::extension_for(&a.content_type)
+                .or_else(|| a.file_name.rsplit_once('.').map(|(_, ext)| ext))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let names = photo_names(
+        photos
+            .iter()
+            .zip(&extensions)
+            .map(|(a, ext)| (items[&a.item_id].as_str(), *ext)),
+    );
+
+    let mut files = Vec::with_capacity(photos.len());
+    for (photo, name) in photos.iter().zip(names) {
+        match media.read(&photo.stored_as).await {
+            Ok(data) => files.push((name, data)),
+            Err(e) => eprintln!("Could not read {} for export: {}", photo.stored_as, e),
+        }
+    }
+
+    let archive = tokio::task::spawn_blocking(move || zip_photos(files))
+        .await
+        .map_err(fail)?
+        .map_err(fail)?;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/zip")
+        .header(
+            "Content-Disposition",
+            "attachment; filename=\"wear-photos.zip\"",
+        )
+        .body(archive)
+        .map_err(fail)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_photos_after_items() {
+        let names = photo_names(vec![
+            ("Blue Oxford Shirt", "jpg"),
+            ("Blue oxford shirt", "jpg"),
+            ("Blue Oxford Shirt", "png"),
+            ("Blue Oxford Shirt", "jpg"),
+            ("!!!", "webp"),
+        ]);
+
+        assert_eq!(
+            names,
+            vec![
+                "blue-oxford-shirt.jpg",
+                "blue-oxford-shirt-2.jpg",
+                "blue-oxford-shirt.png",
+                "blue-oxford-shirt-3.jpg",
+                "item.webp",
+            ]
+        );
+    }
+
+    fn item(id: usize, name: &str) -> Item {
+        Item {
+            id,
+            name: name.into(),
+            description: format!("my favorite {}", name),
+            color: "#112233".into(),
+            tags: vec!["wool".into(), "winter".into()],
+            token: "abcdef".into(),
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn strips_identifying_fields() {
+        let items = vec![item(7, "Grandpa's cardigan"), item(9, "Blue scarf")];
+        let wears = vec![(7, "2020-01-01T00:00:00+00:00".to_string())];
+        let summaries = vec![(9, "2018-12".to_string(), 4)];
+
+        let out = anonymized(&items, &wears, &summaries).to_string();
+
+        assert!(!out.contains("cardigan"));
+        assert!(!out.contains("scarf"));
+        assert!(!out.contains("favorite"));
+        assert!(!out.contains("abcdef"));
+        assert!(out.contains("winter"));
+        assert!(out.contains("2020-01-01T00:00:00+00:00"));
+    }
+
+    #[test]
+    fn renumbers_items() {
+        let items = vec![item(7, "a"), item(9, "b")];
+        let wears = vec![(9, "2020-01-01T00:00:00+00:00".to_string())];
+
+        let out = anonymized(&items, &wears, &[]);
+
+        assert_eq!(out["items"][0]["id"], 1);
+        assert_eq!(out["items"][1]["id"], 2);
+        assert_eq!(out["items"][1]["wears"][0], "2020-01-01T00:00:00+00:00");
+    }
+}
@@ -0,0 +1,61 @@
+//! Repairs records that have come to disagree with each other, whether through bugs, crashes or
+//! editing the database by hand: counts that don't match the recorded wears, rows left behind by
+//! deleted items, times that can't be read, and attachments without files. `wear fsck` runs it,
+//! and so does the server before it starts if asked to.
+
+use {
+    super::{db::Connection, media::Media, thumbnails},
+    std::io,
+};
+
+/// Fix everything that can be fixed, returning a description of each kind of problem found and
+/// how many times.
+pub(crate) async fn run(conn: &Connection, media: &Media) -> anyhow::Result<Vec<(String, u64)>> {
+    let mut found = conn
+        .repair()
+        .await?
+        .into_iter()
+        .map(|(problem, n)| (problem.to_string(), n))
+        .collect::<Vec<_>>();
+
+    let orphaned = conn.get_orphaned_attachments().await?;
+    for attachment in &orphaned {
+        media.remove(&attachment.stored_as).await;
+        thumbnails::remove(media, attachment).await;
+        conn.delete_attachment(attachment.id).await?;
+    }
+    found.push((
+        "attachments of items that no longer exist".into(),
+        orphaned.len() as u64,
+    ));
+
+    // only a file that's definitely gone counts; the store being unreachable doesn't
+    let mut missing = 0;
+    for attachment in conn.get_all_attachments().await? {
+        if let Err(e) = media.read(&attachment.stored_as).await {
+            if e.kind() == io::ErrorKind::NotFound {
+                thumbnails::remove(media, &attachment).await;
+                conn.delete_attachment(attachment.id).await?;
+                missing += 1;
+            }
+        }
+    }
+    found.push(("attachments whose files are missing".into(), missing));
+
+    found.retain(|(_, n)| *n > 0);
+    Ok(found)
+}
+
+/// Run the repairs and print what was fixed.
+pub(crate) async fn report(conn: &Connection, media: &Media) -> anyhow::Result<()> {
+    let found = run(conn, media).await?;
+
+    if found.is_empty() {
+        println!("No problems found.");
+    }
+    for (problem, n) in found {
+        println!("Fixed {}: {}", n, problem);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,313 @@
+//! A read-only GraphQL view of the wardrobe, for frontends that want nested, selective data
+//! instead of whole rendered pages.
+
+use {
+    super::{
+        colors::ColorFilter,
+        db::{Connection, ListFilter},
+        Nulls, SortItems, View,
+    },
+    async_graphql::{
+        Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Result, SimpleObject,
+    },
+    std::collections::BTreeMap,
+};
+
+pub(crate) type WardrobeSchema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Most history entries a single field will return.
+const MAX_HISTORY: i32 = 500;
+
+pub(crate) async fn execute(
+    request: async_graphql::Request,
+    schema: WardrobeSchema,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&schema.execute(request).await))
+}
+
+pub(crate) fn schema(conn: Connection) -> WardrobeSchema {
+    async_graphql::Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(conn)
+        .limit_depth(6)
+        .limit_complexity(500)
+        .finish()
+}
+
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
+enum ItemSort {
+    Name,
+    Count,
+    Wear,
+    Wash,
+    Total,
+    Tag,
+    Description,
+    Modified,
+    Condition,
+}
+
+impl From<ItemSort> for SortItems {
+    fn from(sort: ItemSort) -> Self {
+        match sort {
+            ItemSort::Name => Self::Name,
+            ItemSort::Count => Self::Count,
+            ItemSort::Wear => Self::Wear,
+            ItemSort::Wash => Self::Wash,
+            ItemSort::Total => Self::Total,
+            ItemSort::Tag => Self::Tag,
+            ItemSort::Description => Self::Description,
+            ItemSort::Modified => Self::Modified,
+            ItemSort::Condition => Self::Condition,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
+enum ItemView {
+    Unworn,
+    New,
+    RecentlyWashed,
+}
+
+impl From<ItemView> for View {
+    fn from(view: ItemView) -> Self {
+        match view {
+            ItemView::Unworn => Self::Unworn,
+            ItemView::New => Self::New,
+            ItemView::RecentlyWashed => Self::RecentlyWashed,
+        }
+    }
+}
+
+#[derive(Default, InputObject)]
+struct ItemFilter {
+    /// Only items with this tag
+    tag: Option<String>,
+    /// Only items of this `#rrggbb` color, or in this color family (e.g. "black")
+    color: Option<String>,
+    /// Only items whose name contains this, ignoring case
+    name: Option<String>,
+    /// Only items that have (or haven't) been worn since their last wash
+    dirty: Option<bool>,
+    /// One of the canned index views
+    view: Option<ItemView>,
+}
+
+#[derive(SimpleObject)]
+struct Tag {
+    name: String,
+    items: i32,
+}
+
+#[derive(SimpleObject)]
+struct Wear {
+    worn_at: String,
+    note: Option<String>,
+}
+
+#[derive(SimpleObject)]
+struct Wash {
+    washed_at: String,
+    method: Option<String>,
+    temperature: Option<i32>,
+    detergent: Option<String>,
+    cost: Option<f64>,
+}
+
+struct Item(super::Item);
+
+#[Object]
+impl Item {
+    async fn id(&self) -> i32 {
+        self.0.id as i32
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    async fn color(&self) -> &str {
+        &self.0.color
+    }
+
+    async fn tags(&self) -> Vec<&str> {
+        self.0
+            .tags
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect()
+    }
+
+    /// Wears since the last wash
+    async fn count(&self) -> i32 {
+        self.0.count as i32
+    }
+
+    async fn total_count(&self) -> i32 {
+        self.0.total_count as i32
+    }
+
+    /// Hours of use logged with its wears
+    async fn total_hours(&self) -> f64 {
+        self.0.total_hours
+    }
+
+    /// `wears` or `hours`
+    async fn tracking(&self) -> &str {
+        self.0.tracking.as_str()
+    }
+
+    /// `light`, `medium` or `warm`, if it's been rated
+    async fn warmth(&self) -> Option<&str> {
+        self.0.warmth.map(|w| w.as_str())
+    }
+
+    /// Seasons it's worn in; none means all year
+    async fn seasons(&self) -> Vec<&str> {
+        self.0.seasons.iter().map(|s| s.as_str()).collect()
+    }
+
+    async fn last_wear(&self) -> Option<String> {
+        self.0.last_wear.map(|t| t.to_rfc3339())
+    }
+
+    async fn last_wash(&self) -> Option<String> {
+        self.0.last_wash.map(|t| t.to_rfc3339())
+    }
+
+    async fn retailer(&self) -> &str {
+        &self.0.retailer
+    }
+
+    async fn replaced_by(&self) -> Option<i32> {
+        self.0.replaced_by.map(|id| id as i32)
+    }
+
+    /// Individual wear timestamps, newest first
+    async fn wears(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50)] limit: i32,
+    ) -> async_graphql::Result<Vec<String>> {
+        let conn = ctx.data::<Connection>()?;
+        let wears = conn
+            .get_item_wears(self.0.id, limit.clamp(0, MAX_HISTORY) as u32)
+            .await?;
+
+        Ok(wears.into_iter().map(|(t, _)| t).collect())
+    }
+
+    /// Wears with any notes left on them, newest first
+    async fn wear_history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50)] limit: i32,
+    ) -> async_graphql::Result<Vec<Wear>> {
+        let conn = ctx.data::<Connection>()?;
+        let wears = conn
+            .get_item_wears(self.0.id, limit.clamp(0, MAX_HISTORY) as u32)
+            .await?;
+
+        Ok(wears
+            .into_iter()
+            .map(|(worn_at, note)| Wear {
+                worn_at,
+                note: Some(note).filter(|n| !n.is_empty()),
+            })
+            .collect())
+    }
+
+    /// Washes, newest first
+    async fn washes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50)] limit: i32,
+    ) -> async_graphql::Result<Vec<Wash>> {
+        let conn = ctx.data::<Connection>()?;
+        let washes = conn
+            .get_item_washes(self.0.id, limit.clamp(0, MAX_HISTORY) as u32)
+            .await?;
+
+        let non_empty = |s: String| Some(s).filter(|s| !s.is_empty());
+        Ok(washes
+            .into_iter()
+            .map(|(washed_at, method, temperature, detergent, cost)| Wash {
+                washed_at,
+                method: non_empty(method),
+                temperature,
+                detergent: non_empty(detergent),
+                cost,
+            })
+            .collect())
+    }
+}
+
+pub(crate) struct Query;
+
+#[Object]
+impl Query {
+    async fn items(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<ItemFilter>,
+        sort: Option<ItemSort>,
+        #[graphql(default)] descending: bool,
+    ) -> async_graphql::Result<Vec<Item>> {
+        let conn = ctx.data::<Connection>()?;
+        let filter = filter.unwrap_or_default();
+        let items = conn
+            .get_view(
+                &ListFilter {
+                    view: filter.view.map(View::from),
+                    tag: filter.tag.clone(),
+                    color: filter.color.as_deref().and_then(ColorFilter::new),
+                    ..ListFilter::default()
+                },
+                &sort.map(SortItems::from).into_iter().collect::<Vec<_>>(),
+                !descending,
+                Nulls::Last,
+            )
+            .await?;
+
+        let name = filter.name.as_ref().map(|n| n.to_lowercase());
+        Ok(items
+            .into_iter()
+            .filter(|i| match &name {
+                Some(n) => i.name.to_lowercase().contains(n),
+                None => true,
+            })
+            .filter(|i| filter.dirty.is_none_or(|dirty| (i.count > 0) == dirty))
+            .map(Item)
+            .collect())
+    }
+
+    async fn item(&self, ctx: &Context<'_>, id: i32) -> async_graphql::Result<Option<Item>> {
+        let conn = ctx.data::<Connection>()?;
+        match conn.get_item(id.max(0) as usize).await {
+            Ok(item) => Ok(Some(Item(item))),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every tag in use, with how many items carry it
+    async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Tag>> {
+        let conn = ctx.data::<Connection>()?;
+        let mut tags = BTreeMap::<String, i32>::new();
+        for item in conn.get_all(&[], true).await? {
+            for tag in item.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+                *tags.entry(tag.to_string()).or_default() += 1;
+            }
+        }
+
+        Ok(tags
+            .into_iter()
+            .map(|(name, items)| Tag { name, items })
+            .collect())
+    }
+}
@@ -0,0 +1,175 @@
+//! An item's wears and washes in one timeline, how long it usually goes between washes, and how
+//! often it's been worn lately. Wears are each logged on their own, so an item put on twice in a
+//! day counts twice; where that would skew things, wears are counted by day instead.
+
+use {
+    chrono::{DateTime, Local, NaiveDate, Utc},
+    schemars::JsonSchema,
+    serde::Serialize,
+    std::collections::HashMap,
+};
+
+/// How many weeks of wears the index and item pages chart.
+pub(crate) const SPARKLINE_WEEKS: u32 = 26;
+
+#[derive(Clone, Copy, Debug, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Kind {
+    Wear,
+    Wash,
+}
+
+/// One wear or wash.
+#[derive(Debug, JsonSchema, Serialize)]
+pub(crate) struct Event {
+    pub(crate) kind: Kind,
+    pub(crate) at: DateTime<Utc>,
+    /// The note left on a wear, or the method and detergent of a wash
+    pub(crate) detail: String,
+}
+
+/// The local day something happened on.
+fn day(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&Local).date().naive_local()
+}
+
+/// Wears, days and days worn between each pair of consecutive washes, from events in
+/// chronological order. Whatever came before the first wash isn't a full cycle, so it isn't
+/// counted.
+fn cycles(events: &[Event]) -> Vec<(u32, i64, u32)> {
+    let mut cycles = Vec::new();
+    let mut current: Option<(u32, DateTime<Utc>, Vec<NaiveDate>)> = None;
+
+    for event in events {
+        match (event.kind, current.as_mut()) {
+            (Kind::Wear, Some((wears, _, days))) => {
+                *wears += 1;
+                if days.last() != Some(&day(event.at)) {
+                    days.push(day(event.at));
+                }
+            }
+            (Kind::Wear, None) => (),
+            (Kind::Wash, previous) => {
+                if let Some((wears, since, days)) = previous {
+                    cycles.push((*wears, (event.at - *since).num_days(), days.len() as u32));
+                }
+                current = Some((0, event.at, Vec::new()));
+            }
+        }
+    }
+
+    cycles
+}
+
+fn median(mut values: Vec<i64>) -> Option<f64> {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((values[mid - 1] + values[mid]) as f64 / 2.),
+        _ => Some(values[mid] as f64),
+    }
+}
+
+/// How an item is usually washed: the median wears, days and separate days worn between washes,
+/// over however many complete cycles there are.
+#[derive(Debug, JsonSchema, PartialEq, Serialize)]
+pub(crate) struct Typical {
+    pub(crate) cycles: usize,
+    pub(crate) wears: f64,
+    pub(crate) days: f64,
+    pub(crate) days_worn: f64,
+}
+
+pub(crate) fn typical(events: &[Event]) -> Option<Typical> {
+    let cycles = cycles(events);
+    Some(Typical {
+        cycles: cycles.len(),
+        wears: median(cycles.iter().map(|(w, _, _)| i64::from(*w)).collect())?,
+        days: median(cycles.iter().map(|(_, d, _)| *d).collect())?,
+        days_worn: median(cycles.iter().map(|(_, _, n)| i64::from(*n)).collect())?,
+    })
+}
+
+/// Each item's wears per week, oldest week first, from `(item, weeks ago, wears)` counts. Items
+/// that weren't worn at all in that time are left out.
+pub(crate) fn weekly(counts: &[(i32, i32, i32)], weeks: u32) -> HashMap<usize, Vec<u32>> {
+    let mut series = HashMap::new();
+    for &(item_id, weeks_ago, wears) in counts {
+        if weeks_ago < 0 || weeks_ago as u32 >= weeks {
+            continue;
+        }
+        let slots = series
+            .entry(item_id as usize)
+            .or_insert_with(|| vec![0; weeks as usize]);
+        slots[weeks as usize - 1 - weeks_ago as usize] += wears.max(0) as u32;
+    }
+    series
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, chrono::TimeZone};
+
+    fn event(kind: Kind, day: u32) -> Event {
+        Event {
+            kind,
+            at: Utc.ymd(2020, 5, day).and_hms(8, 0, 0),
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn between_washes() {
+        let events = [
+            event(Kind::Wear, 1),
+            event(Kind::Wash, 2),
+            event(Kind::Wear, 3),
+            event(Kind::Wear, 4),
+            event(Kind::Wash, 6),
+            event(Kind::Wear, 7),
+            event(Kind::Wear, 8),
+            event(Kind::Wear, 9),
+            event(Kind::Wear, 10),
+            event(Kind::Wash, 14),
+            event(Kind::Wear, 15),
+        ];
+
+        assert_eq!(cycles(&events), vec![(2, 4, 2), (4, 8, 4)]);
+        assert_eq!(
+            typical(&events),
+            Some(Typical {
+                cycles: 2,
+                wears: 3.,
+                days: 6.,
+                days_worn: 3.,
+            })
+        );
+        assert_eq!(typical(&events[..4]), None);
+    }
+
+    #[test]
+    fn same_day_wears() {
+        let again = |day| Event {
+            at: Utc.ymd(2020, 5, day).and_hms(8, 30, 0),
+            ..event(Kind::Wear, day)
+        };
+        let events = [
+            event(Kind::Wash, 1),
+            event(Kind::Wear, 2),
+            again(2),
+            event(Kind::Wear, 3),
+            event(Kind::Wash, 4),
+        ];
+
+        assert_eq!(cycles(&events), vec![(3, 3, 2)]);
+    }
+
+    #[test]
+    fn weekly_series() {
+        let series = weekly(&[(1, 0, 2), (1, 3, 1), (2, 1, 4), (2, 4, 9)], 4);
+
+        assert_eq!(series[&1], vec![1, 0, 0, 2]);
+        assert_eq!(series[&2], vec![0, 0, 4, 0]);
+    }
+}
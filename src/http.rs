@@ -0,0 +1,118 @@
+//! One request at a time to other services, like an S3 bucket or a weather provider, over a
+//! fresh connection each. There are few enough of them that a pooling client isn't worth it.
+
+use {
+    super::media,
+    hyper::{body::HttpBody, client::conn, Body, Request, StatusCode, Uri},
+    std::{
+        io,
+        net::{self, ToSocketAddrs},
+        time::Duration,
+    },
+    tokio::{
+        io::{AsyncRead, AsyncWrite},
+        net::TcpStream,
+    },
+};
+
+/// How long to wait for a connection to one address of a host.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a whole request can take, from connecting to the last of the response.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The most of a response that's read: enough for the largest file that can be stored, with some
+/// room to spare.
+const MAX_BODY_SIZE: u64 = media::MAX_FILE_SIZE + 1024 * 32;
+
+pub(crate) fn other(e: impl std::fmt::Display) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Send a request to the host of `endpoint`, over TLS unless its scheme is plain http, returning
+/// the status and the whole body of the response. Gives up if that takes too long, or if the body
+/// is too big.
+pub(crate) async fn send(
+    endpoint: &Uri,
+    request: Request<Body>,
+) -> io::Result<(StatusCode, Vec<u8>)> {
+    tokio::time::timeout(TIMEOUT, send_now(endpoint, request))
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "{} did not respond within {}s",
+                    endpoint.host().unwrap_or_default(),
+                    TIMEOUT.as_secs()
+                ),
+            )
+        })?
+}
+
+async fn send_now(endpoint: &Uri, request: Request<Body>) -> io::Result<(StatusCode, Vec<u8>)> {
+    let host = endpoint.host().unwrap_or_default();
+    let tls = endpoint.scheme_str() != Some("http");
+    let port = endpoint.port_u16().unwrap_or(if tls { 443 } else { 80 });
+
+    // connect with std and hand the socket over: tokio's own connect goes through net2, which
+    // assumes a layout for socket addresses that newer compilers don't use
+    let address = (host.to_string(), port);
+    let tcp = tokio::task::spawn_blocking(move || connect(address))
+        .await
+        .map_err(other)??;
+    tcp.set_nonblocking(true)?;
+    let tcp = TcpStream::from_std(tcp)?;
+    if tls {
+        let stream = async_native_tls::connect(host, tcp).await.map_err(other)?;
+        exchange(stream, host, request).await
+    } else {
+        exchange(tcp, host, request).await
+    }
+}
+
+/// Connect to the first address of a host that answers in time.
+fn connect(address: impl ToSocketAddrs) -> io::Result<net::TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match net::TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| other("host has no addresses")))
+}
+
+async fn exchange<S>(
+    stream: S,
+    host: &str,
+    request: Request<Body>,
+) -> io::Result<(StatusCode, Vec<u8>)>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = conn::handshake(stream).await.map_err(other)?;
+    let host = host.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection to {} failed: {}", host, e);
+        }
+    });
+
+    let mut response = sender.send_request(request).await.map_err(other)?;
+    let too_big = || other(format!("response is over {} bytes", MAX_BODY_SIZE));
+    if response.body().size_hint().lower() > MAX_BODY_SIZE {
+        return Err(too_big());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.body_mut().data().await {
+        body.extend_from_slice(&chunk.map_err(other)?);
+        if body.len() as u64 > MAX_BODY_SIZE {
+            return Err(too_big());
+        }
+    }
+
+    Ok((response.status(), body))
+}
@@ -0,0 +1,77 @@
+//! Protection against the same form or request being submitted twice, e.g. when a flaky
+//! connection makes the browser retry. Clients send a key with each mutating request (as an
+//! `Idempotency-Key` header, or an `idempotency_key` query parameter from a form), and a request
+//! whose key was already used for the same action is acknowledged without doing anything.
+
+use {
+    super::db::Connection,
+    serde::Deserialize,
+    sha2::{Digest, Sha256},
+    std::{
+        future::Future,
+        sync::atomic::{AtomicU64, Ordering},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    warp::{Filter, Rejection},
+};
+
+#[derive(Deserialize)]
+struct KeyQuery {
+    idempotency_key: Option<String>,
+}
+
+/// The key sent with a request, if any.
+pub(crate) fn key() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("idempotency-key")
+        .and(warp::query::<KeyQuery>())
+        .map(|header: Option<String>, query: KeyQuery| {
+            header
+                .or(query.idempotency_key)
+                .filter(|k| !k.trim().is_empty())
+        })
+}
+
+/// A fresh key to embed in a rendered form.
+pub(crate) fn new_key() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let digest = format!(
+        "{:x}",
+        Sha256::digest(format!("{}:{}", nanos, count).as_bytes())
+    );
+    digest[..24].to_string()
+}
+
+/// Run `run` unless a request with the same key already ran this action. Returns `None` for a
+/// repeat. Requests without a key always run.
+pub(crate) async fn once<T>(
+    conn: &Connection,
+    key: Option<String>,
+    action: &str,
+    run: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<Option<T>> {
+    let key = match key {
+        Some(key) => key,
+        None => return run.await.map(Some),
+    };
+
+    if !conn.claim_idempotency_key(&key, action).await? {
+        return Ok(None);
+    }
+
+    match run.await {
+        Ok(result) => Ok(Some(result)),
+        Err(e) => {
+            // let a retry have another go
+            if let Err(release) = conn.release_idempotency_key(&key, action).await {
+                eprintln!("could not release idempotency key {}: {}", key, release);
+            }
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Bulk imports. A zip of images is unpacked and each file is attached to the item whose ID or
+//! name matches its file name, so `12.jpg`, `blue-oxford-shirt.png` and `Blue Oxford Shirt 2.jpeg`
+//! all find their way home. A CSV of items, with a header row naming the columns, adds one item
+//! per row.
+
+use {
+    super::{
+        colors, db::Connection, media, media::Media, template::WithTemplate, thumbnails, Item,
+    },
+    serde_json::{json, Map, Value},
+    std::io::{Cursor, Read},
+    tokio::stream::StreamExt,
+    warp::{multipart::FormData, Buf},
+};
+
+/// Largest archive that will be accepted in one upload.
+pub(crate) const MAX_ARCHIVE_SIZE: u64 = 100 * 1024 * 1024;
+
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("gif", "image/gif"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("webp", "image/webp"),
+];
+
+/// Reduce a name to lowercase words joined by dashes.
+pub(crate) fn slugify(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Split a file name into its slugified stem and lowercase extension.
+fn stem_and_extension(file_name: &str) -> (String, String) {
+    match file_name.rfind('.') {
+        Some(dot) if dot > 0 => (
+            slugify(&file_name[..dot]),
+            file_name[dot + 1..].to_lowercase(),
+        ),
+        _ => (slugify(file_name), String::new()),
+    }
+}
+
+/// Find the item a photo belongs to, by ID or by name, ignoring a trailing sequence number.
+fn match_item(file_name: &str, items: &[Item]) -> Option<usize> {
+    let (stem, _) = stem_and_extension(file_name);
+    let unnumbered = match stem.rfind('-') {
+        Some(dash) if stem[dash + 1..].chars().all(|c| c.is_ascii_digit()) => &stem[..dash],
+        _ => stem.as_str(),
+    };
+
+    let by_id = |s: &str| {
+        s.parse::<usize>()
+            .ok()
+            .filter(|id| items.iter().any(|i| i.id == *id))
+    };
+    let by_name = |s: &str| items.iter().find(|i| slugify(&i.name) == s).map(|i| i.id);
+
+    by_id(&stem)
+        .or_else(|| by_name(&stem))
+        .or_else(|| by_name(unnumbered))
+}
+
+fn content_type_for(file_name: &str) -> Option<&'static str> {
+    let (_, extension) = stem_and_extension(file_name);
+    IMAGE_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, t)| *t)
+}
+
+/// Most entries an archive can have, folders included.
+const MAX_ENTRIES: usize = 2000;
+
+/// Most an archive can unpack to altogether. Each file is unpacked and stored before the next,
+/// so this bounds the work an upload can make, not the memory it takes.
+const MAX_UNPACKED_SIZE: u64 = 4 * MAX_ARCHIVE_SIZE;
+
+type Archive = zip::ZipArchive<Cursor<Vec<u8>>>;
+
+/// A file in the archive: its name, and the item and content type to attach it as or why it
+/// won't be.
+type Entry = (String, Result<(usize, &'static str), String>);
+
+/// Where a file in the archive should go, from its name and declared size alone: the item and
+/// content type to attach it as, or why it won't be. Folders and the metadata that some archivers
+/// add are skipped altogether.
+fn entry_target(
+    archive: &mut Archive,
+    index: usize,
+    items: &[Item],
+) -> zip::result::ZipResult<Option<Entry>> {
+    let file = archive.by_index(index)?;
+    let path = file.name().to_string();
+    let name = path.rsplit('/').next().unwrap_or_default().to_string();
+
+    if file.is_dir() || name.is_empty() || name.starts_with('.') || path.contains("__MACOSX/") {
+        return Ok(None);
+    }
+
+    let target = content_type_for(&name)
+        .ok_or_else(|| "not a supported image".to_string())
+        .and_then(|t| match file.size() {
+            size if size > media::MAX_FILE_SIZE => Err("too large".to_string()),
+            _ => Ok(t),
+        })
+        .and_then(|t| {
+            match_item(&name, items)
+                .map(|id| (id, t))
+                .ok_or_else(|| "no matching item".to_string())
+        });
+    Ok(Some((name, target)))
+}
+
+/// Unpack one file, reading no more than `budget` bytes of it; the declared size can't be trusted.
+fn unpack(archive: &mut Archive, index: usize, budget: u64) -> Result<Vec<u8>, String> {
+    let limit = budget.min(media::MAX_FILE_SIZE);
+    let file = archive.by_index(index).map_err(|e| e.to_string())?;
+    let mut contents = Vec::new();
+    file.take(limit + 1)
+        .read_to_end(&mut contents)
+        .map_err(|e| e.to_string())?;
+
+    match contents.len() as u64 {
+        n if n > media::MAX_FILE_SIZE => Err("too large".into()),
+        n if n > limit => Err("the archive unpacks to too much".into()),
+        _ => Ok(contents),
+    }
+}
+
+fn fail(e: impl std::fmt::Display) -> warp::Rejection {
+    eprintln!("photo import: {}", e);
+    warp::reject::not_found()
+}
+
+/// Attach every image in an uploaded zip to its matching item, and report what happened.
+pub(crate) async fn photos(
+    mut form: FormData,
+    conn: Connection,
+    media: Media,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let mut archive = Vec::new();
+    while let Some(part) = form.next().await {
+        let mut part = part.map_err(fail)?;
+        if part.name() != "archive" {
+            continue;
+        }
+
+        while let Some(chunk) = part.data().await {
+            archive.extend_from_slice(chunk.map_err(fail)?.bytes());
+        }
+    }
+
+    let mut archive =
+        tokio::task::spawn_blocking(move || zip::ZipArchive::new(Cursor::new(archive)))
+            .await
+            .map_err(fail)?
+            .map_err(fail)?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(fail(format!(
+            "archive has {} entries, more than {}",
+            archive.len(),
+            MAX_ENTRIES
+        )));
+    }
+    let items = conn.get_all(&[], true).await.map_err(fail)?;
+
+    let (mut matched, mut unmatched) = (Vec::new(), Vec::new());
+    let mut unpacked = 0;
+    for index in 0..archive.len() {
+        let (file_name, target) = match entry_target(&mut archive, index, &items).map_err(fail)? {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        // each file is unpacked only once it's known where it goes, and stored before the next
+        let budget = MAX_UNPACKED_SIZE - unpacked;
+        let contents = match target {
+            Ok(target) => {
+                let (returned, contents) = tokio::task::spawn_blocking(move || {
+                    let contents = unpack(&mut archive, index, budget);
+                    (archive, contents)
+                })
+                .await
+                .map_err(fail)?;
+                archive = returned;
+                contents.map(|data| (target, data))
+            }
+            Err(reason) => Err(reason),
+        };
+
+        let (item_id, content_type, data) = match contents {
+            Ok(((id, t), data)) => (id, t, data),
+            Err(reason) => {
+                unmatched.push(json!({ "file": file_name, "reason": reason }));
+                continue;
+            }
+        };
+        unpacked += data.len() as u64;
+
+        let extension = Media::extension_for(content_type).unwrap_or_default();
+        let digest = Media::digest(&data);
+        let duplicates = conn
+            .get_photo_duplicates(&digest, item_id)
+            .await
+            .map_err(fail)?;
+        let stored_as = media.save(item_id, extension, &data).await.map_err(fail)?;
+        if let Err(e) = thumbnails::create(&media, &stored_as, content_type, &data).await {
+            eprintln!("Could not make thumbnails of {}: {}", file_name, e);
+        }
+        conn.add_attachment(
+            item_id,
+            &file_name,
+            content_type,
+            data.len(),
+            &stored_as,
+            &digest,
+        )
+        .await
+        .map_err(fail)?;
+
+        let name = items.iter().find(|i| i.id == item_id).map(|i| &i.name);
+        matched.push(json!({
+            "file": file_name,
+            "key": item_id,
+            "name": name,
+            "duplicates": duplicates
+                .iter()
+                .map(|(key, name)| json!({ "key": key, "name": name }))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    Ok(WithTemplate {
+        name: "import",
+        value: json!({
+            "imported": true,
+            "matched": matched,
+            "unmatched": unmatched,
+        }),
+    })
+}
+
+/// Largest CSV file that will be accepted in one upload.
+pub(crate) const MAX_CSV_SIZE: u64 = 1024 * 1024;
+
+/// Columns a CSV can fill in; anything else is ignored.
+const CSV_COLUMNS: &[&str] = &[
+    "name",
+    "description",
+    "color",
+    "tags",
+    "retailer",
+    "product_url",
+    "purchased_on",
+    "price",
+    "quantity",
+    "currency",
+];
+
+/// Split CSV text into rows of fields, allowing quoted fields with commas, doubled quotes and line
+/// breaks in them.
+fn csv_rows(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let (mut rows, mut row, mut field) = (Vec::new(), Vec::new(), String::new());
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("a quoted field is never closed".into());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // blank lines don't count as items
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    Ok(rows)
+}
+
+/// Read items from CSV text. Rows that can't be used are reported by their number, not counting
+/// the header or blank lines, rather than stopping the whole import.
+fn read_csv(text: &str) -> Result<(Vec<Item>, Vec<String>), String> {
+    let mut rows = csv_rows(text.trim_start_matches('\u{feff}'))?.into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| "the file is empty".to_string())?
+        .iter()
+        .map(|h| h.trim().to_lowercase().replace(' ', "_"))
+        .collect::<Vec<_>>();
+    if !header.iter().any(|h| h == "name") {
+        return Err("the first row needs a \"name\" column".into());
+    }
+
+    let (mut items, mut problems) = (Vec::new(), Vec::new());
+    for (index, row) in rows.enumerate() {
+        let mut fields = Map::new();
+        fields.insert("description".into(), Value::String(String::new()));
+        fields.insert("tags".into(), Value::String(String::new()));
+        for (column, value) in header.iter().zip(row) {
+            if CSV_COLUMNS.contains(&column.as_str()) && !value.trim().is_empty() {
+                fields.insert(column.clone(), Value::String(value.trim().to_string()));
+            }
+        }
+
+        let item = serde_json::from_value::<Item>(Value::Object(fields))
+            .map_err(|e| e.to_string())
+            .and_then(|item| item.validate().map(|_| item).map_err(|e| e.to_string()))
+            .and_then(|item| match colors::is_valid(&item.color) {
+                true => Ok(item),
+                false => Err(format!("{} is not a #rrggbb color", item.color)),
+            });
+        match item {
+            Ok(item) => items.push(item),
+            Err(e) => problems.push(format!("row {}: {}", index + 1, e)),
+        }
+    }
+
+    Ok((items, problems))
+}
+
+/// Add every item in an uploaded CSV, and report what happened.
+pub(crate) async fn items(
+    mut form: FormData,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let mut data = Vec::new();
+    while let Some(part) = form.next().await {
+        let mut part = part.map_err(fail)?;
+        if part.name() != "csv" {
+            continue;
+        }
+
+        while let Some(chunk) = part.data().await {
+            data.extend_from_slice(chunk.map_err(fail)?.bytes());
+        }
+    }
+
+    let (mut added, mut problems) = (0, Vec::new());
+    match String::from_utf8(data)
+        .map_err(|_| "the file is not UTF-8 text".to_string())
+        .and_then(|text| read_csv(&text))
+    {
+        Ok((items, unreadable)) => {
+            problems = unreadable;
+            for item in items {
+                let name = item.name.clone();
+                match conn.new_item(item).await {
+                    Ok(_) => added += 1,
+                    Err(e) => problems.push(format!("{}: {}", name, e)),
+                }
+            }
+        }
+        Err(e) => problems.push(e),
+    }
+
+    Ok(WithTemplate {
+        name: "welcome",
+        value: json!({
+            "step": 3,
+            "imported": true,
+            "added": added,
+            "problems": problems,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                id: 3,
+                name: "Blue Oxford Shirt".into(),
+                ..Item::default()
+            },
+            Item {
+                id: 12,
+                name: "Boots".into(),
+                ..Item::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn by_id_or_name() {
+        let items = items();
+
+        assert_eq!(match_item("12.jpg", &items), Some(12));
+        assert_eq!(match_item("blue-oxford-shirt.png", &items), Some(3));
+        assert_eq!(match_item("Blue Oxford Shirt 2.JPEG", &items), Some(3));
+        assert_eq!(match_item("boots_1.webp", &items), Some(12));
+        assert_eq!(match_item("7.jpg", &items), None);
+        assert_eq!(match_item("sandals.jpg", &items), None);
+    }
+
+    #[test]
+    fn unpacked_within_limits() {
+        use zip::{write::FileOptions, ZipWriter};
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, size) in [
+            ("boots.jpg", 1000),
+            ("notes.txt", 10),
+            ("__MACOSX/._12.jpg", 10),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut writer, &vec![0; size]).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let items = items();
+
+        assert_eq!(
+            entry_target(&mut archive, 0, &items).unwrap(),
+            Some(("boots.jpg".into(), Ok((12, "image/jpeg"))))
+        );
+        assert_eq!(
+            entry_target(&mut archive, 1, &items).unwrap(),
+            Some(("notes.txt".into(), Err("not a supported image".into())))
+        );
+        assert_eq!(entry_target(&mut archive, 2, &items).unwrap(), None);
+
+        assert_eq!(unpack(&mut archive, 0, 1000).map(|c| c.len()), Ok(1000));
+        assert_eq!(
+            unpack(&mut archive, 0, 999),
+            Err("the archive unpacks to too much".into())
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let text = "Name,Color,Tags,Notes\r\n\"Shirt, blue\",#1f2a44,\"shirts,cotton\",x\r\n\r\nSocks,,,\n,#ffffff,,\nHat,navy,,\n";
+        let (items, problems) = read_csv(text).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "Shirt, blue");
+        assert_eq!(items[0].color, "#1f2a44");
+        assert_eq!(items[0].tags, vec!["shirts", "cotton"]);
+        assert_eq!(items[1].name, "Socks");
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("row 3:"), "{}", problems[0]);
+        assert!(problems[1].starts_with("row 4:"), "{}", problems[1]);
+
+        assert!(read_csv("color\n#ffffff\n").is_err());
+        assert!(read_csv("name\n\"Shirt\n").is_err());
+    }
+
+    #[test]
+    fn only_images() {
+        assert_eq!(content_type_for("boots.JPG"), Some("image/jpeg"));
+        assert_eq!(content_type_for("receipt.pdf"), None);
+        assert_eq!(content_type_for("jpg"), None);
+    }
+}
@@ -0,0 +1,246 @@
+use {
+    super::{
+        attachments, balance, db::Connection, deadlines, laundry, media::Media, notify::Notifier,
+        view_matches,
+    },
+    chrono::{Datelike, Duration, Local, Utc, Weekday},
+};
+
+/// Nudge the user to log today's outfit if they haven't yet.
+pub(crate) async fn logging_reminder(conn: Connection, notifier: Notifier, public_url: String) {
+    let midnight = Local::today()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_else(Local::now)
+        .with_timezone(&Utc);
+
+    match conn.worn_since(midnight).await {
+        Ok(true) => (),
+        Ok(false) => {
+            notifier
+                .send(
+                    "Nothing logged today",
+                    &format!("What are you wearing? {}/quick", public_url),
+                )
+                .await
+        }
+        Err(e) => eprintln!("logging reminder: could not check today's wears: {}", e),
+    }
+}
+
+/// Compact wear events older than the retention period into monthly summaries.
+pub(crate) async fn prune_wear_events(conn: Connection, retention_years: u32) {
+    let cutoff = Utc::now() - Duration::days(365 * i64::from(retention_years));
+
+    match conn.summarize_wears_before(cutoff).await {
+        Ok(0) => (),
+        Ok(n) => eprintln!(
+            "Summarized and pruned {} wear event(s) older than {}",
+            n,
+            cutoff.date()
+        ),
+        Err(e) => eprintln!("Could not prune old wear events: {}", e),
+    }
+}
+
+/// Delete items for good once they've been in the trash longer than the given number of days.
+pub(crate) async fn purge_trash(conn: Connection, media: Media, days: u32) {
+    let cutoff = Utc::now() - Duration::days(i64::from(days));
+
+    let items = match conn.get_trashed_before(cutoff).await {
+        Ok(i) => i,
+        Err(e) => return eprintln!("Could not look up old items in the trash: {}", e),
+    };
+
+    // the files go only once the item has, so a failed delete doesn't leave it without them;
+    // deleting it deletes its attachments' records too, so they're looked up first
+    for (id, name) in items {
+        let files = match conn.get_attachments(id as usize).await {
+            Ok(a) => a,
+            Err(e) => {
+                eprintln!("Could not purge item {} from the trash: {}", id, e);
+                continue;
+            }
+        };
+        match conn.delete_item(id as usize).await {
+            // restored since it was looked up
+            Ok(0) => (),
+            Ok(_) => {
+                attachments::remove_files(&files, &media).await;
+                eprintln!("Purged item {} ({}) from the trash", id, name);
+            }
+            Err(e) => eprintln!("Could not purge item {} from the trash: {}", id, e),
+        }
+    }
+}
+
+/// Warn about return windows and warranties that are about to run out.
+pub(crate) async fn deadline_notice(
+    conn: Connection,
+    notifier: Notifier,
+    public_url: String,
+    days: u32,
+) {
+    let today = Local::today().naive_local();
+
+    let items = match conn.get_with_deadlines(today).await {
+        Ok(i) => i,
+        Err(e) => return eprintln!("deadline notice: could not retrieve collection: {}", e),
+    };
+
+    let due = deadlines::due_within(&items, today, days);
+    if due.is_empty() {
+        return;
+    }
+
+    let mut body = due
+        .iter()
+        .map(|d| format!("{}: {} {}", d.name, d.kind.to_lowercase(), d.date))
+        .collect::<Vec<_>>()
+        .join("\n");
+    body += &format!("\n{}/deadlines", public_url);
+
+    notifier
+        .send(&format!("{} deadline(s) coming up", due.len()), &body)
+        .await;
+}
+
+/// On a scheduled laundry day, say what needs washing.
+pub(crate) async fn laundry_reminder(conn: Connection, notifier: Notifier, public_url: String) {
+    let today = Local::today().naive_local();
+
+    let (schedule, done) = match (
+        conn.get_laundry_schedule().await,
+        conn.get_laundry_days(today).await,
+    ) {
+        (Ok(s), Ok(d)) => (s, d),
+        (Err(e), _) | (_, Err(e)) => {
+            return eprintln!("laundry reminder: could not retrieve schedule: {}", e)
+        }
+    };
+
+    if !schedule.contains(&today.weekday()) || !done.is_empty() {
+        return;
+    }
+
+    let items = match conn.get_all(&[], true).await {
+        Ok(i) => i,
+        Err(e) => return eprintln!("laundry reminder: could not retrieve collection: {}", e),
+    };
+
+    let loads = laundry::suggest_loads(&items);
+    if loads.is_empty() {
+        return;
+    }
+
+    let mut body = loads
+        .iter()
+        .map(|l| format!("{}: {} item(s)", l.label, l.items.len()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    body += &format!("\n{}/laundry", public_url);
+
+    notifier
+        .send(&format!("Laundry day: {} load(s)", loads.len()), &body)
+        .await;
+}
+
+/// Once a week, compare the last seven days' wears per category with the rotation goals.
+pub(crate) async fn balance_report(
+    conn: Connection,
+    notifier: Notifier,
+    public_url: String,
+    day: Weekday,
+) {
+    if Local::today().weekday() != day {
+        return;
+    }
+
+    let (goals, wears) = match (
+        conn.get_rotation_goals().await,
+        conn.get_wears_by_category(Utc::now() - Duration::days(7))
+            .await,
+    ) {
+        (Ok(g), Ok(w)) => (g, w),
+        (Err(e), _) | (_, Err(e)) => {
+            return eprintln!("balance report: could not retrieve wears: {}", e)
+        }
+    };
+
+    let counts = |pairs: Vec<(String, i32)>| {
+        pairs
+            .into_iter()
+            .map(|(category, n)| (category, n.max(0) as u32))
+            .collect::<Vec<_>>()
+    };
+    let lines = balance::compare(&counts(goals), &counts(wears));
+
+    if let Some((subject, body)) = balance::message(&lines) {
+        notifier
+            .send(&subject, &format!("{}\n{}/settings", body, public_url))
+            .await;
+    }
+}
+
+/// Check saved views with alerts turned on, and say which items have started matching them
+/// since the last check.
+pub(crate) async fn view_alerts(conn: Connection, notifier: Notifier, public_url: String) {
+    let views = match conn.get_saved_views().await {
+        Ok(v) => v,
+        Err(e) => return eprintln!("view alerts: could not retrieve saved views: {}", e),
+    };
+
+    for (id, name, query, alert) in views {
+        if !alert {
+            continue;
+        }
+
+        let items = match view_matches(&conn, &query).await {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("view alerts: could not check {}: {}", name, e);
+                continue;
+            }
+        };
+        let ids = items.iter().map(|i| i.id).collect::<Vec<_>>();
+
+        let new = match conn.update_alert_matches(id as usize, &ids).await {
+            Ok(n) => n,
+            Err(e) => {
+                eprintln!("view alerts: could not record matches for {}: {}", name, e);
+                continue;
+            }
+        };
+        if new.is_empty() {
+            continue;
+        }
+
+        let mut body = items
+            .iter()
+            .filter(|i| new.contains(&i.id))
+            .map(|i| i.name.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        body += &format!("\n{}/view/{}", public_url, id);
+
+        notifier
+            .send(&format!("{}: {} new item(s)", name, new.len()), &body)
+            .await;
+    }
+}
+
+/// Log the day's wears for trips that are set to log them, catching up on any days missed.
+pub(crate) async fn trip_wears(conn: Connection) {
+    let today = Local::today().naive_local();
+
+    let trips = match conn.get_trips_to_log(today).await {
+        Ok(t) => t,
+        Err(e) => return eprintln!("trip wears: could not look up trips: {}", e),
+    };
+
+    for (id,) in trips {
+        match conn.log_trip_wears(id as usize, today).await {
+            Ok(n) => eprintln!("Logged {} wear(s) for trip {}", n, id),
+            Err(e) => eprintln!("trip wears: could not log wears for trip {}: {}", id, e),
+        }
+    }
+}
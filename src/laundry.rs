@@ -0,0 +1,296 @@
+//! Recurring laundry days: when the next one is, which loads to run on it, and a calendar feed so
+//! the schedule can live alongside everything else. Also a planner that sorts what's due a wash
+//! into darks, lights and delicates.
+
+use {
+    super::{care, colors, Item, WashDetails, WashMethod},
+    chrono::{Datelike, Duration, NaiveDate, Weekday},
+    serde::Serialize,
+    std::collections::BTreeMap,
+};
+
+pub(crate) const WEEK: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// The first scheduled day on or after `today` that hasn't already been done.
+pub(crate) fn upcoming(
+    schedule: &[Weekday],
+    today: NaiveDate,
+    done: &[NaiveDate],
+) -> Option<NaiveDate> {
+    (0..14)
+        .map(|n| today + Duration::days(n))
+        .find(|d| schedule.contains(&d.weekday()) && !done.contains(d))
+}
+
+/// How an item should be washed, going by its care label.
+pub(crate) fn wash_details(item: &Item) -> Option<WashDetails> {
+    let (method, temperature) = match item.care_wash.as_str() {
+        "no" => return None,
+        "hand" => (WashMethod::Hand, None),
+        code => (WashMethod::Machine, code.parse().ok()),
+    };
+
+    Some(WashDetails {
+        method: Some(method),
+        temperature,
+        ..WashDetails::default()
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct LoadItem {
+    key: usize,
+    name: String,
+    care: Vec<care::Symbol>,
+}
+
+/// A group of dirty items that can go in the wash together.
+#[derive(Debug, Serialize)]
+pub(crate) struct Load {
+    pub(crate) label: String,
+    pub(crate) items: Vec<LoadItem>,
+    /// Whether it's washed by hand rather than in the machine
+    pub(crate) hand: bool,
+}
+
+/// Split everything that's been worn since its last wash into loads by washing instructions.
+///
+/// Items that can't be washed at home are left out entirely.
+pub(crate) fn suggest_loads(items: &[Item]) -> Vec<Load> {
+    // hand washing sorts after every machine temperature
+    let mut loads = BTreeMap::<(bool, Option<u32>), Vec<LoadItem>>::new();
+
+    for item in items.iter().filter(|i| i.count > 0) {
+        if let Some(details) = wash_details(item) {
+            let key = (
+                details.method == Some(WashMethod::Hand),
+                details.temperature,
+            );
+            loads.entry(key).or_default().push(LoadItem {
+                key: item.id,
+                name: item.name.clone(),
+                care: care::symbols(item),
+            });
+        }
+    }
+
+    loads
+        .into_iter()
+        .map(|((hand, temperature), items)| Load {
+            label: match (hand, temperature) {
+                (true, _) => "Hand wash".into(),
+                (false, Some(t)) => format!("Machine wash, {}°C", t),
+                (false, None) => "Machine wash, no care label".into(),
+            },
+            items,
+            hand,
+        })
+        .collect()
+}
+
+/// Colors at least this light go in with the lights.
+const LIGHT: f64 = 0.6;
+
+/// Where an item goes when sorting the wash. Delicates are kept apart whatever their color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Pile {
+    Darks,
+    Lights,
+    Delicates,
+}
+
+impl Pile {
+    fn of(item: &Item, details: &WashDetails) -> Self {
+        let gentle = details.method == Some(WashMethod::Hand)
+            || (details.temperature.is_some_and(|t| t <= 30)
+                && matches!(item.care_dry.as_str(), "no-tumble" | "flat"));
+
+        match colors::lightness(&item.color) {
+            _ if gentle => Self::Delicates,
+            Some(l) if l >= LIGHT => Self::Lights,
+            // colors that can't be read are washed as darks, to be safe
+            _ => Self::Darks,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Darks => "Darks",
+            Self::Lights => "Lights",
+            Self::Delicates => "Delicates",
+        }
+    }
+}
+
+/// Sort items into darks, lights and delicates, each washed no hotter than the coolest of its
+/// items' care labels allow. Items that can't be washed at home are left out.
+pub(crate) fn plan<'a>(items: impl IntoIterator<Item = &'a Item>) -> Vec<Load> {
+    let mut piles = BTreeMap::<Pile, (Vec<LoadItem>, Option<u32>, bool)>::new();
+
+    for item in items {
+        if let Some(details) = wash_details(item) {
+            let (load, temperature, hand) = piles.entry(Pile::of(item, &details)).or_default();
+            load.push(LoadItem {
+                key: item.id,
+                name: item.name.clone(),
+                care: care::symbols(item),
+            });
+            *hand |= details.method == Some(WashMethod::Hand);
+            *temperature = match (*temperature, details.temperature) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+
+    piles
+        .into_iter()
+        .map(|(pile, (items, temperature, hand))| Load {
+            label: match (hand, temperature) {
+                (true, _) => format!("{}, hand wash", pile.label()),
+                (false, Some(t)) => format!("{}, {}°C", pile.label(), t),
+                (false, None) => pile.label().to_string(),
+            },
+            items,
+            hand,
+        })
+        .collect()
+}
+
+/// An iCalendar feed with a single weekly recurring event for the laundry schedule.
+pub(crate) fn calendar(schedule: &[Weekday], today: NaiveDate) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".into(),
+        "PRODID:-//wear//laundry//EN".into(),
+    ];
+
+    if let Some(start) = upcoming(schedule, today, &[]) {
+        let days = WEEK
+            .iter()
+            .filter(|d| schedule.contains(d))
+            .map(|d| d.to_string()[..2].to_uppercase())
+            .collect::<Vec<_>>();
+
+        lines.extend(vec![
+            "BEGIN:VEVENT".into(),
+            "UID:laundry-day@wear".into(),
+            format!("DTSTAMP:{}T000000Z", today.format("%Y%m%d")),
+            format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+            format!("RRULE:FREQ=WEEKLY;BYDAY={}", days.join(",")),
+            "SUMMARY:Laundry day".into(),
+            "END:VEVENT".into(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".into());
+    lines.join("\r\n") + "\r\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(id: usize, count: usize, care_wash: &str) -> Item {
+        Item {
+            id,
+            name: format!("item {}", id),
+            count,
+            care_wash: care_wash.into(),
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn next_undone_day() {
+        // a Monday
+        let today = NaiveDate::from_ymd(2020, 6, 1);
+        let schedule = [Weekday::Mon, Weekday::Thu];
+
+        assert_eq!(upcoming(&schedule, today, &[]), Some(today));
+        assert_eq!(
+            upcoming(&schedule, today, &[today]),
+            Some(NaiveDate::from_ymd(2020, 6, 4))
+        );
+        assert_eq!(upcoming(&[], today, &[]), None);
+    }
+
+    #[test]
+    fn loads_by_instructions() {
+        let items = vec![
+            item(1, 2, "40"),
+            item(2, 1, "hand"),
+            item(3, 1, "30"),
+            item(4, 0, "30"),
+            item(5, 3, "no"),
+            item(6, 1, "40"),
+            item(7, 1, ""),
+        ];
+
+        let loads = suggest_loads(&items)
+            .into_iter()
+            .map(|l| (l.label, l.items.iter().map(|i| i.key).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            loads,
+            vec![
+                ("Machine wash, no care label".to_string(), vec![7]),
+                ("Machine wash, 30°C".to_string(), vec![3]),
+                ("Machine wash, 40°C".to_string(), vec![1, 6]),
+                ("Hand wash".to_string(), vec![2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn planned_by_color_and_care() {
+        let colored = |id, care_wash: &str, color: &str, care_dry: &str| Item {
+            color: color.into(),
+            care_dry: care_dry.into(),
+            ..item(id, 1, care_wash)
+        };
+        let items = vec![
+            colored(1, "40", "#000080", ""),
+            colored(2, "60", "#ffffff", "tumble"),
+            colored(3, "30", "#f5f5dc", ""),
+            colored(4, "30", "#222222", "flat"),
+            colored(5, "hand", "#ffffff", ""),
+            colored(6, "no", "#000000", ""),
+            colored(7, "", "#333333", ""),
+        ];
+
+        let loads = plan(&items)
+            .into_iter()
+            .map(|l| (l.label, l.items.iter().map(|i| i.key).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            loads,
+            vec![
+                ("Darks, 40°C".to_string(), vec![1, 7]),
+                ("Lights, 30°C".to_string(), vec![2, 3]),
+                ("Delicates, hand wash".to_string(), vec![4, 5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_event() {
+        // a Tuesday
+        let today = NaiveDate::from_ymd(2020, 6, 2);
+        let ics = calendar(&[Weekday::Sat, Weekday::Wed], today);
+
+        assert!(ics.contains("DTSTART;VALUE=DATE:20200603\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=WE,SA\r\n"));
+        assert!(!calendar(&[], today).contains("VEVENT"));
+    }
+}
@@ -0,0 +1,120 @@
+use {super::Item, chrono::NaiveDate, serde::Serialize};
+
+/// How much of its expected life an item has used up, in percent, before it's reported as
+/// nearing the end.
+pub(crate) const NEARING_END: u32 = 80;
+
+const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Life {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) age: Option<String>,
+    /// Percent of the expected lifespan used up by time since purchase
+    pub(crate) by_time: Option<u32>,
+    /// Percent of the expected lifespan used up by wears
+    pub(crate) by_wears: Option<u32>,
+}
+
+impl Life {
+    fn used(&self) -> u32 {
+        self.by_time.max(self.by_wears).unwrap_or(0)
+    }
+}
+
+/// Roughly how old something bought `days` ago is.
+fn age(days: i64) -> String {
+    let (n, unit) = match days {
+        d if d < 60 => (d, "day"),
+        d if d < 730 => ((d as f64 / DAYS_PER_MONTH) as i64, "month"),
+        d => (d / 365, "year"),
+    };
+
+    format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// How far through its expected life an item is as of `today`. Either measure is left out if the
+/// item doesn't have what it needs.
+pub(crate) fn of(item: &Item, today: NaiveDate) -> Life {
+    let days = item.purchased_on.map(|d| (today - d).num_days().max(0));
+
+    Life {
+        key: item.id,
+        name: item.name.clone(),
+        age: days.map(age),
+        by_time: days
+            .zip(item.lifespan_months.filter(|m| *m > 0))
+            .map(|(days, months)| {
+                (days as f64 * 100.0 / (f64::from(months) * DAYS_PER_MONTH)).round() as u32
+            }),
+        by_wears: item
+            .lifespan_wears
+            .filter(|w| *w > 0)
+            // each piece of a set gets its own share of the wears
+            .map(|wears| {
+                (item.total_count * 100 / (wears as usize * item.quantity.max(1) as usize)) as u32
+            }),
+    }
+}
+
+/// Items that have used up at least `NEARING_END` percent of their life by either measure, most
+/// worn out first.
+pub(crate) fn nearing_end(items: &[Item], today: NaiveDate) -> Vec<Life> {
+    let mut lives = items
+        .iter()
+        .map(|item| of(item, today))
+        .filter(|life| life.used() >= NEARING_END)
+        .collect::<Vec<_>>();
+
+    lives.sort_by_key(|life| std::cmp::Reverse(life.used()));
+    lives
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn by_time_and_wears() {
+        let today = NaiveDate::from_ymd(2021, 1, 1);
+        let items = vec![
+            Item {
+                id: 1,
+                purchased_on: Some(NaiveDate::from_ymd(2020, 1, 1)),
+                lifespan_months: Some(24),
+                total_count: 90,
+                lifespan_wears: Some(100),
+                ..Item::default()
+            },
+            Item {
+                id: 2,
+                purchased_on: Some(NaiveDate::from_ymd(2016, 1, 1)),
+                lifespan_months: Some(48),
+                ..Item::default()
+            },
+            Item {
+                id: 3,
+                total_count: 10,
+                lifespan_wears: Some(100),
+                ..Item::default()
+            },
+        ];
+
+        let first = of(&items[0], today);
+        assert_eq!(first.age.as_deref(), Some("12 months"));
+        assert_eq!((first.by_time, first.by_wears), (Some(50), Some(90)));
+
+        let found = nearing_end(&items, today);
+        assert_eq!(found.iter().map(|l| l.key).collect::<Vec<_>>(), vec![2, 1]);
+
+        // the wears of a pack are spread over its pieces
+        let pack = Item {
+            total_count: 90,
+            quantity: 3,
+            ..items[0].clone()
+        };
+        assert_eq!(of(&pack, today).by_wears, Some(30));
+    }
+}
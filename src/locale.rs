@@ -0,0 +1,128 @@
+//! Number and currency formatting for the handful of locales the settings page offers, so prices
+//! read naturally without pulling in a full localization library.
+
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Locale {
+    pub(crate) code: &'static str,
+    pub(crate) name: &'static str,
+    decimal: char,
+    group: &'static str,
+    /// Whether the currency symbol follows the amount
+    symbol_after: bool,
+}
+
+const fn locale(
+    code: &'static str,
+    name: &'static str,
+    decimal: char,
+    group: &'static str,
+    symbol_after: bool,
+) -> Locale {
+    Locale {
+        code,
+        name,
+        decimal,
+        group,
+        symbol_after,
+    }
+}
+
+pub(crate) const LOCALES: &[Locale] = &[
+    locale("en-US", "English (United States)", '.', ",", false),
+    locale("en-GB", "English (United Kingdom)", '.', ",", false),
+    locale("de-DE", "Deutsch (Deutschland)", ',', ".", true),
+    locale("fr-FR", "Français (France)", ',', "\u{202f}", true),
+    locale("es-ES", "Español (España)", ',', ".", true),
+    locale("ja-JP", "日本語 (日本)", '.', ",", false),
+];
+
+/// Currency codes the settings page offers, with their symbol and usual number of decimals.
+pub(crate) const CURRENCIES: &[(&str, &str, usize)] = &[
+    ("USD", "$", 2),
+    ("EUR", "€", 2),
+    ("GBP", "£", 2),
+    ("JPY", "¥", 0),
+    ("CAD", "CA$", 2),
+    ("AUD", "A$", 2),
+    ("CHF", "CHF", 2),
+    ("SEK", "kr", 2),
+];
+
+impl Locale {
+    /// Look up a locale by code, falling back to the first one.
+    pub(crate) fn find(code: &str) -> &'static Self {
+        LOCALES
+            .iter()
+            .find(|l| l.code == code)
+            .unwrap_or(&LOCALES[0])
+    }
+
+    /// Format a number with this locale's separators.
+    pub(crate) fn number(&self, value: f64, decimals: usize) -> String {
+        let fixed = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = match fixed.find('.') {
+            Some(dot) => (&fixed[..dot], Some(&fixed[dot + 1..])),
+            None => (fixed.as_str(), None),
+        };
+
+        let mut out = String::new();
+        if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            out.push('-');
+        }
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                out += self.group;
+            }
+            out.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            out.push(self.decimal);
+            out += fraction;
+        }
+
+        out
+    }
+
+    /// Format an amount of money, using the currency's symbol where it has one.
+    pub(crate) fn money(&self, value: f64, currency: &str) -> String {
+        let (symbol, decimals) = CURRENCIES
+            .iter()
+            .find(|(code, _, _)| *code == currency)
+            .map(|(_, symbol, decimals)| (*symbol, *decimals))
+            .unwrap_or((currency, 2));
+        let amount = self.number(value, decimals);
+
+        match (self.symbol_after, symbol.chars().count()) {
+            (true, _) => format!("{}\u{a0}{}", amount, symbol),
+            // prefix symbols are written tight, but codes and words need a space
+            (false, 1) => format!("{}{}", symbol, amount),
+            (false, _) if symbol.ends_with('$') => format!("{}{}", symbol, amount),
+            (false, _) => format!("{}\u{a0}{}", symbol, amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn separators() {
+        assert_eq!(Locale::find("en-US").number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(Locale::find("de-DE").number(1234.5, 2), "1.234,50");
+        assert_eq!(Locale::find("en-US").number(-0.001, 2), "0.00");
+        assert_eq!(Locale::find("en-US").number(-12.5, 0), "-12");
+        assert_eq!(Locale::find("xx-XX").code, "en-US");
+    }
+
+    #[test]
+    fn currencies() {
+        assert_eq!(Locale::find("en-US").money(1234.5, "USD"), "$1,234.50");
+        assert_eq!(Locale::find("de-DE").money(12.5, "EUR"), "12,50\u{a0}€");
+        assert_eq!(Locale::find("en-GB").money(3000.0, "JPY"), "¥3,000");
+        assert_eq!(Locale::find("en-US").money(5.0, "CHF"), "CHF\u{a0}5.00");
+        assert_eq!(Locale::find("en-US").money(5.0, "NZD"), "NZD\u{a0}5.00");
+    }
+}
@@ -102,7 +102,7 @@ mod test {
 
         assert_eq!(
             database_file(Some(&tmp)).await?,
-            (tmp.into(), DEFAULT_FILE_NAME.into())
+            (tmp, DEFAULT_FILE_NAME.into())
         );
         Ok(())
     }
@@ -134,7 +134,7 @@ mod test {
 
         assert_eq!(
             database_file(Some(&tmp)).await?,
-            (tmp.into(), DEFAULT_FILE_NAME.into())
+            (tmp, DEFAULT_FILE_NAME.into())
         );
         Ok(())
     }
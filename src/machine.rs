@@ -0,0 +1,92 @@
+//! A washing machine plugged into a smart plug. Most plugs, or the home automation hub they report
+//! to, can call a URL once the power draw drops off at the end of a cycle. Pointing that at
+//! `/laundry/machine/finished` either logs the wash for whatever was loaded, or sends a
+//! notification asking to unload it from the laundry page.
+
+use {
+    super::{db::Connection, notify::Notifier, wash_items},
+    serde::Serialize,
+};
+
+#[derive(Clone)]
+pub(crate) struct Machine {
+    notifier: Notifier,
+    public_url: String,
+    auto_wash: bool,
+}
+
+/// What happened when a cycle finished, for whatever reported it.
+#[derive(Debug, Serialize)]
+pub(crate) struct Report {
+    loaded: usize,
+    washed: usize,
+}
+
+impl Machine {
+    pub(crate) fn new(notifier: Notifier, public_url: String, auto_wash: bool) -> Self {
+        Self {
+            notifier,
+            public_url,
+            auto_wash,
+        }
+    }
+
+    /// Deal with the end of a cycle. An empty machine is ignored, since plugs can't tell a wash
+    /// from anything else that draws power for a while.
+    pub(crate) async fn finished(&self, conn: &Connection) -> sqlx::Result<Report> {
+        let (items, _) = conn.get_machine_load().await?;
+        if items.is_empty() {
+            return Ok(Report {
+                loaded: 0,
+                washed: 0,
+            });
+        }
+
+        let names = items
+            .iter()
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if self.auto_wash {
+            let washed = unload(conn).await?;
+            self.notifier
+                .send(
+                    &format!("Washing machine done: {} item(s) washed", washed),
+                    &format!("{}\n{}/laundry", names, self.public_url),
+                )
+                .await;
+
+            return Ok(Report {
+                loaded: items.len(),
+                washed,
+            });
+        }
+
+        conn.finish_machine_load().await?;
+        self.notifier
+            .send(
+                &format!("Washing machine done: {} item(s)", items.len()),
+                &format!(
+                    "{}\nUnload them to log the wash: {}/laundry",
+                    names, self.public_url
+                ),
+            )
+            .await;
+
+        Ok(Report {
+            loaded: items.len(),
+            washed: 0,
+        })
+    }
+}
+
+/// Log a wash for everything in the machine and empty it.
+pub(crate) async fn unload(conn: &Connection) -> sqlx::Result<usize> {
+    let (items, _) = conn.get_machine_load().await?;
+    let ids = items.iter().map(|(id, _)| *id as usize).collect::<Vec<_>>();
+
+    let washed = wash_items(conn, &ids).await;
+    conn.clear_machine_load().await?;
+    Ok(washed)
+}
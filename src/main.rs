@@ -1,24 +1,79 @@
 #![deny(clippy::all)]
+// the item page builds its template context in one large json! call
+#![recursion_limit = "256"]
 
 use {
     anyhow::Context,
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Utc, Weekday},
     chrono_humanize::Humanize,
     clap::Clap,
     handlebars::Handlebars,
+    schemars::JsonSchema,
     serde::{Deserialize, Serialize},
     serde_json::json,
-    std::{net::IpAddr, path::PathBuf, sync::Arc},
+    std::{collections::HashMap, net::IpAddr, path::PathBuf, sync::Arc, time::Duration},
     tokio::{signal, sync::oneshot},
     warp::{path, Filter},
 };
 
+mod api;
+mod attachments;
+mod balance;
+mod cache;
+mod calendar;
+mod care;
+mod changes;
+mod client;
+mod colors;
+mod condition;
+mod context;
+mod custom;
 mod db;
+mod deadlines;
+mod declutter;
+mod doctor;
+mod edits;
+mod export;
+mod fsck;
+mod graphql;
+mod history;
+mod http;
+mod idempotency;
+mod import;
+mod jobs;
+mod laundry;
+mod lifespan;
+mod locale;
 mod location;
+mod machine;
+mod measurements;
+mod media;
+mod notify;
+mod openapi;
+mod s3;
+mod schedule;
+mod search;
+mod settings;
 mod template;
+mod thumbnails;
+mod timing;
+mod trips;
 mod utils;
+mod weather;
 
-use {db::Connection, template::WithTemplate};
+use {
+    cache::Reports,
+    colors::ColorFilter,
+    condition::Condition,
+    db::{query::Query, Archived, Connection, ListFilter},
+    machine::Machine,
+    media::Media,
+    notify::Notifier,
+    settings::Settings,
+    template::WithTemplate,
+    timing::Timings,
+    weather::{Season, Warmth, Weather},
+};
 
 #[derive(Clap)]
 #[clap(rename_all = "kebab-case", setting(clap::AppSettings::ColoredHelp))]
@@ -35,6 +90,179 @@ struct Opts {
         long_about = "Path to store database file\nIf not specified, will pick a location appropriate for your platform"
     )]
     data_path: Option<PathBuf>,
+
+    #[clap(
+        long,
+        about = "Public URL of this server, used for links in notifications",
+        long_about = "Public URL of this server, used for links in notifications\nIf not specified, will be built from the host and port"
+    )]
+    public_url: Option<String>,
+
+    #[clap(
+        long,
+        about = "Command to run for notifications",
+        long_about = "Command to run for notifications\nIt is called with the subject and body as its two arguments. Notifications are always logged.\nDuring the quiet hours set on the settings page, notifications are held and sent when they end."
+    )]
+    notify_command: Option<PathBuf>,
+
+    #[clap(
+        long,
+        parse(try_from_str = utils::parse_time),
+        about = "Local time (HH:MM) to send a reminder if nothing has been worn that day"
+    )]
+    remind_at: Option<NaiveTime>,
+
+    #[clap(
+        long,
+        about = "Number of years to keep individual wear events for",
+        long_about = "Number of years to keep individual wear events for\nOlder events are rolled up into monthly totals. If not specified, events are kept forever."
+    )]
+    retention_years: Option<u32>,
+
+    #[clap(
+        long,
+        default_value = "500",
+        about = "Milliseconds a request may take before it's logged as slow",
+        long_about = "Milliseconds a request may take before it's logged as slow\nThe admin page flags routes whose 95th percentile is over this too."
+    )]
+    slow_request_ms: u64,
+
+    #[clap(
+        long,
+        about = "Number of days to keep deleted items in the trash for",
+        long_about = "Number of days to keep deleted items in the trash for\nOlder ones are deleted for good, along with their attachments, once a day. If not specified, the trash is only emptied by hand."
+    )]
+    trash_days: Option<u32>,
+
+    #[clap(
+        long,
+        about = "Send a notification this many days before a return window or warranty ends"
+    )]
+    deadline_notice_days: Option<u32>,
+
+    #[clap(
+        long,
+        parse(try_from_str = utils::parse_time),
+        about = "Local time (HH:MM) to send the suggested loads on a scheduled laundry day"
+    )]
+    laundry_remind_at: Option<NaiveTime>,
+
+    #[clap(
+        long,
+        parse(try_from_str = utils::parse_time),
+        about = "Local time (HH:MM) to check saved views with alerts for newly matching items",
+        long_about = "Local time (HH:MM) to check saved views with alerts for newly matching items\nAlerts are turned on for a saved view from the index. Each check reports only items that didn't match the one before."
+    )]
+    view_alerts_at: Option<NaiveTime>,
+
+    #[clap(
+        long,
+        about = "Log a wash for everything in the washing machine as soon as its cycle finishes",
+        long_about = "Log a wash for everything in the washing machine as soon as its cycle finishes\nWithout this, a notification asks to unload it from the laundry page instead. Finished cycles are reported by POSTing to /laundry/machine/finished."
+    )]
+    auto_wash_on_finish: bool,
+
+    #[clap(
+        long,
+        about = "Repair inconsistent records before starting, as `fsck` does",
+        long_about = "Repair inconsistent records before starting, as `fsck` does\nWhat was fixed is logged."
+    )]
+    repair_on_start: bool,
+
+    #[clap(
+        long,
+        parse(try_from_str = utils::parse_weekday),
+        about = "Day of the week to send a report of wears per category against the rotation goals",
+        long_about = "Day of the week to send a report of wears per category against the rotation goals\nThe report covers the previous seven days and is sent at 18:00. Goals are set on the settings page."
+    )]
+    balance_report_day: Option<Weekday>,
+
+    #[clap(
+        long,
+        use_delimiter = true,
+        about = "Addresses of reverse proxies whose X-Forwarded-For header should be believed",
+        long_about = "Addresses of reverse proxies whose X-Forwarded-For header should be believed\nSeparate several with commas. Requests from anywhere else are attributed to the address they arrive from."
+    )]
+    trusted_proxies: Vec<IpAddr>,
+
+    #[clap(
+        long,
+        about = "Keep photos and other attachments in this S3 bucket instead of the data directory",
+        long_about = "Keep photos and other attachments in this S3 bucket instead of the data directory\nCredentials are read from WEAR_S3_ACCESS_KEY and WEAR_S3_SECRET_KEY. Files already in the data directory are not moved."
+    )]
+    s3_bucket: Option<String>,
+
+    #[clap(
+        long,
+        about = "URL of an S3-compatible service to use instead of AWS",
+        long_about = "URL of an S3-compatible service to use instead of AWS\nObjects are addressed by path, as in https://example.com/bucket/name."
+    )]
+    s3_endpoint: Option<String>,
+
+    #[clap(long, default_value = "us-east-1", about = "Region of the S3 bucket")]
+    s3_region: String,
+
+    #[clap(
+        long,
+        env = "WEAR_S3_ACCESS_KEY",
+        hide_env_values = true,
+        about = "S3 access key ID"
+    )]
+    s3_access_key: Option<String>,
+
+    #[clap(
+        long,
+        env = "WEAR_S3_SECRET_KEY",
+        hide_env_values = true,
+        about = "S3 secret access key"
+    )]
+    s3_secret_key: Option<String>,
+
+    #[clap(
+        long,
+        env = "WEAR_WEATHER_API_KEY",
+        hide_env_values = true,
+        about = "OpenWeatherMap API key, for suggesting what to wear for the day's forecast",
+        long_about = "OpenWeatherMap API key, for suggesting what to wear for the day's forecast\nNeeds --weather-location. Without a key, the temperature can be entered on the suggestions page instead."
+    )]
+    weather_api_key: Option<String>,
+
+    #[clap(
+        long,
+        about = "Where to get forecasts for, as LATITUDE,LONGITUDE",
+        long_about = "Where to get forecasts for, as LATITUDE,LONGITUDE\nAlso decides which months are summer: a negative latitude is south of the equator."
+    )]
+    weather_location: Option<weather::Location>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clap)]
+enum Command {
+    #[clap(
+        about = "Check the configuration, database, media directory and templates, then exit",
+        long_about = "Check the configuration, database, media directory and templates, then exit\nGive the same options as for the server, before `doctor`. Nothing is changed; the exit status is nonzero if there are problems."
+    )]
+    Doctor,
+
+    #[clap(
+        about = "Bring the database up to date, then exit",
+        long_about = "Bring the database up to date, then exit\nThe server does this on every start, so this is only needed to upgrade ahead of time, or to see what an upgrade would do."
+    )]
+    Migrate {
+        #[clap(
+            long,
+            about = "Print the changes that would be made, without making them"
+        )]
+        dry_run: bool,
+    },
+
+    #[clap(
+        about = "Repair records that disagree with each other, then exit",
+        long_about = "Repair records that disagree with each other, then exit\nWear counts are checked against the recorded wears, and unreadable times, leftovers from deleted items and attachments without files are cleaned up. Give the same options as for the server, before `fsck`, and stop the server first."
+    )]
+    Fsck,
 }
 
 #[tokio::main]
@@ -44,19 +272,162 @@ async fn main() -> anyhow::Result<()> {
         Err(e) => e.exit(),
     };
 
+    if let Some(Command::Doctor) = options.command {
+        let healthy = doctor::run(
+            options.data_path,
+            options.public_url.as_deref(),
+            options.notify_command.as_deref(),
+        )
+        .await;
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+
+    if let Some(Command::Migrate { dry_run }) = options.command {
+        return migrate(options.data_path, dry_run).await;
+    }
+
     let hb = template::init().context("Failed to initialize templating engine")?;
-    let conn = Connection::new(options.data_path)
+    let (data_dir, db_file) = location::database_file(options.data_path)
+        .await
+        .context("Failed to determine where to store data")?;
+    let conn = Connection::new(&data_dir, &db_file)
         .await
         .context("Failed to connect to database")?;
+    let media = match options.s3_bucket {
+        Some(bucket) => Media::with_store(
+            s3::S3Store::new(
+                options.s3_endpoint.as_deref(),
+                bucket,
+                options.s3_region,
+                options
+                    .s3_access_key
+                    .context("WEAR_S3_ACCESS_KEY is needed to use an S3 bucket")?,
+                options
+                    .s3_secret_key
+                    .context("WEAR_S3_SECRET_KEY is needed to use an S3 bucket")?,
+            )
+            .context("Failed to set up S3 storage")?,
+        ),
+        None => Media::new(data_dir.join("media"))
+            .await
+            .context("Failed to set up media directory")?,
+    };
+    let weather = Weather::new(options.weather_api_key, options.weather_location)
+        .context("Failed to set up weather forecasts")?;
+    if let Some(Command::Fsck) = options.command {
+        let repaired = fsck::report(&conn, &media).await;
+        conn.close().await;
+        return repaired.context("Failed to repair database");
+    }
+    doctor::preflight(
+        options.public_url.as_deref(),
+        options.notify_command.as_deref(),
+        &media,
+    )
+    .await
+    .context("Startup checks failed; run `wear doctor` for details")?;
+    if options.repair_on_start {
+        let repaired = fsck::run(&conn, &media)
+            .await
+            .context("Failed to repair database")?;
+        for (problem, n) in repaired {
+            eprintln!("Repaired {}: {}", n, problem);
+        }
+    }
+    // before anything else can use the database: sqlx keeps finished statements around, and
+    // their locks, so reading items on one connection blocks writing them on another
+    search::backfill(conn.clone()).await;
+    tokio::spawn(attachments::backfill_digests(conn.clone(), media.clone()));
+    let notifier = Notifier::new(options.notify_command, conn.clone());
+    // anything held for quiet hours when the server last stopped
+    tokio::spawn({
+        let notifier = notifier.clone();
+        async move { notifier.send_held().await }
+    });
+    let (host, port) = (options.host, options.port);
+    let public_url = options
+        .public_url
+        .unwrap_or_else(|| format!("http://{}:{}", host, port));
+
+    if let Some(at) = options.remind_at {
+        let (conn, notifier, public_url) = (conn.clone(), notifier.clone(), public_url.clone());
+        schedule::daily(at, move || {
+            jobs::logging_reminder(conn.clone(), notifier.clone(), public_url.clone())
+        });
+    }
+
+    if let Some(days) = options.deadline_notice_days {
+        let (conn, notifier, public_url) = (conn.clone(), notifier.clone(), public_url.clone());
+        schedule::daily(NaiveTime::from_hms(9, 0, 0), move || {
+            jobs::deadline_notice(conn.clone(), notifier.clone(), public_url.clone(), days)
+        });
+    }
+
+    if let Some(at) = options.laundry_remind_at {
+        let (conn, notifier, public_url) = (conn.clone(), notifier.clone(), public_url.clone());
+        schedule::daily(at, move || {
+            jobs::laundry_reminder(conn.clone(), notifier.clone(), public_url.clone())
+        });
+    }
+
+    if let Some(day) = options.balance_report_day {
+        let (conn, notifier, public_url) = (conn.clone(), notifier.clone(), public_url.clone());
+        schedule::daily(NaiveTime::from_hms(18, 0, 0), move || {
+            jobs::balance_report(conn.clone(), notifier.clone(), public_url.clone(), day)
+        });
+    }
+
+    if let Some(at) = options.view_alerts_at {
+        let (conn, notifier, public_url) = (conn.clone(), notifier.clone(), public_url.clone());
+        schedule::daily(at, move || {
+            jobs::view_alerts(conn.clone(), notifier.clone(), public_url.clone())
+        });
+    }
+
+    // only trips set to log wears are touched, so this always runs
+    {
+        let conn = conn.clone();
+        schedule::daily(NaiveTime::from_hms(23, 0, 0), move || {
+            jobs::trip_wears(conn.clone())
+        });
+    }
+
+    if let Some(years) = options.retention_years {
+        // before the server starts, like the search backfill, for the same reason
+        jobs::prune_wear_events(conn.clone(), years).await;
+
+        let conn = conn.clone();
+        schedule::daily(NaiveTime::from_hms(3, 0, 0), move || {
+            jobs::prune_wear_events(conn.clone(), years)
+        });
+    }
+
+    if let Some(days) = options.trash_days {
+        // before the server starts, like the search backfill, for the same reason
+        jobs::purge_trash(conn.clone(), media.clone(), days).await;
+
+        let (conn, media) = (conn.clone(), media.clone());
+        schedule::daily(NaiveTime::from_hms(3, 30, 0), move || {
+            jobs::purge_trash(conn.clone(), media.clone(), days)
+        });
+    }
+
+    let machine = Machine::new(notifier, public_url, options.auto_wash_on_finish);
 
     // set up the server in a way that lets us shut it down from the outside
     let (tx, rx) = oneshot::channel();
-    let (_address, server) = warp::serve(new_router(hb, conn.clone())).bind_with_graceful_shutdown(
-        (options.host, options.port),
-        async {
-            rx.await.ok();
-        },
-    );
+    let (_address, server) = warp::serve(new_router(
+        hb,
+        conn.clone(),
+        media,
+        machine,
+        weather,
+        options.trusted_proxies,
+        Timings::new(Duration::from_millis(options.slow_request_ms)),
+    ))
+    .bind_with_graceful_shutdown((options.host, options.port), async {
+        rx.await.ok();
+    });
     let server_task = tokio::spawn(server);
 
     // on ctrl+c, tell the server to shut down
@@ -73,7 +444,41 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+async fn migrate(data_path: Option<PathBuf>, dry_run: bool) -> anyhow::Result<()> {
+    let (data_dir, db_file) = location::database_file(data_path)
+        .await
+        .context("Failed to determine where to store data")?;
+
+    if !dry_run {
+        Connection::new(&data_dir, &db_file)
+            .await
+            .context("Failed to migrate database")?
+            .close()
+            .await;
+        return Ok(());
+    }
+
+    let file = data_dir.join(db_file);
+    let pending = db::pending_migrations(&file)
+        .await
+        .context("Failed to read database")?;
+
+    if pending.is_empty() {
+        println!("{} is up to date; nothing to do.", file.display());
+    }
+    for migration in pending {
+        println!(
+            "-- {} (schema version {})\n{}",
+            migration.name,
+            migration.version,
+            migration.sql.trim_end()
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, Serialize)]
 struct Item {
     #[serde(default)]
     id: usize,
@@ -83,6 +488,9 @@ struct Item {
     count: usize,
     #[serde(default)]
     total_count: usize,
+    /// Hours of use logged with its wears
+    #[serde(default)]
+    total_hours: f64,
     #[serde(default)]
     last_wear: Option<DateTime<Utc>>,
     #[serde(default)]
@@ -93,13 +501,274 @@ struct Item {
         deserialize_with = "utils::split_comma",
         serialize_with = "utils::join_comma"
     )]
+    #[schemars(with = "String", description = "Comma-separated")]
     tags: Vec<String>,
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    retailer: String,
+    #[serde(default)]
+    product_url: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    return_by: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    warranty_until: Option<NaiveDate>,
+    #[serde(default)]
+    care_wash: String,
+    #[serde(default)]
+    care_bleach: String,
+    #[serde(default)]
+    care_dry: String,
+    #[serde(default)]
+    care_iron: String,
+    #[serde(default)]
+    care_dry_clean: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    chest: Option<f64>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    waist: Option<f64>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    inseam: Option<f64>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    sleeve: Option<f64>,
+    /// Wears between washes; new items get their category's limit unless they have their own
+    #[serde(default, deserialize_with = "utils::optional")]
+    wash_after: Option<u32>,
+    /// Identical pieces tracked together, like a pack of socks; they're worn in turn
+    #[serde(default = "utils::one", deserialize_with = "utils::at_least_one")]
+    quantity: u32,
+    #[serde(default, deserialize_with = "utils::optional")]
+    purchased_on: Option<NaiveDate>,
+    /// How long the item is expected to last, in months and in wears
+    #[serde(default, deserialize_with = "utils::optional")]
+    lifespan_months: Option<u32>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    lifespan_wears: Option<u32>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    price: Option<f64>,
+    /// ISO 4217 code of the price's currency, if not the one in the settings
+    #[serde(default)]
+    currency: String,
+    /// How worn out it is, as last rated
+    #[serde(default, deserialize_with = "utils::optional")]
+    #[schemars(
+        with = "Option<String>",
+        description = "new, good, worn or retire-soon"
+    )]
+    condition: Option<Condition>,
+    /// Whether its use is better measured in wears or in hours
+    #[serde(default)]
+    #[schemars(with = "String", description = "wears or hours")]
+    tracking: Tracking,
+    /// How warm it is to wear, for suggesting what suits the weather
+    #[serde(default, deserialize_with = "utils::optional")]
+    #[schemars(with = "Option<String>", description = "light, medium or warm")]
+    warmth: Option<Warmth>,
+    /// The seasons it's worn in; none means all year
+    #[serde(
+        default,
+        deserialize_with = "utils::comma_separated",
+        serialize_with = "utils::join_comma"
+    )]
+    #[schemars(
+        with = "String",
+        description = "Comma-separated: spring, summer, autumn or winter"
+    )]
+    seasons: Vec<Season>,
+    #[serde(skip)]
+    replaced_by: Option<usize>,
+    #[serde(skip)]
+    resoled_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    archived_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    wishlisted_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    deleted_at: Option<DateTime<Utc>>,
+    /// Spent on cleaning it so far, in the wardrobe's currency, when the query that loaded it adds
+    /// that up
+    #[serde(skip)]
+    cleaning_cost: f64,
+}
+
+impl Item {
+    /// Check user-provided fields for anything the form can't enforce by itself.
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.product_url.is_empty() && !utils::is_web_url(&self.product_url) {
+            anyhow::bail!("Product link is not a web address: {}", self.product_url);
+        }
+
+        if self.price.is_some_and(|p| p < 0.) {
+            anyhow::bail!("Price can't be negative");
+        }
+        let is_code =
+            self.currency.len() == 3 && self.currency.chars().all(|c| c.is_ascii_uppercase());
+        if !self.currency.is_empty() && !is_code {
+            anyhow::bail!("Currency is not a three-letter code: {}", self.currency);
+        }
+
+        care::validate(self)
+    }
+
+    /// Wears since the last wash of the most worn piece, since pieces are worn in turn.
+    fn wears_each(&self) -> usize {
+        self.count.div_ceil(self.quantity.max(1) as usize)
+    }
+
+    /// Whether it's been worn as often since its last wash as it should go between washes, the
+    /// same as the `needs:wash` filter.
+    fn needs_wash(&self) -> bool {
+        self.wash_after.is_some_and(|n| {
+            self.count >= (n as usize).saturating_mul(self.quantity.max(1) as usize)
+        })
+    }
+
+    /// What each wear has cost so far, once it's been worn at all, counting what it's cost to
+    /// clean. Cleaning is paid in the wardrobe's `currency`, so it's only added to a price in the
+    /// same one.
+    fn cost_per_wear(&self, currency: &str) -> Option<f64> {
+        let cleaning = match self.currency.as_str() {
+            "" => self.cleaning_cost,
+            c if c == currency => self.cleaning_cost,
+            _ => 0.,
+        };
+
+        self.price
+            .filter(|_| self.total_count > 0)
+            .map(|price| (price + cleaning) / self.total_count as f64)
+    }
+
+    /// What each hour of use has cost so far, for items tracked by the hour.
+    fn cost_per_hour(&self) -> Option<f64> {
+        self.price
+            .filter(|_| self.tracking == Tracking::Hours && self.total_hours > 0.)
+            .map(|price| price / self.total_hours)
+    }
+}
+
+/// A partial update to an item. Fields that are left out aren't touched, and dates and
+/// measurements (in centimetres) can be cleared with `null`.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ItemPatch {
+    name: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    tags: Option<Vec<String>>,
+    retailer: Option<String>,
+    product_url: Option<String>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    return_by: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    warranty_until: Option<Option<NaiveDate>>,
+    care_wash: Option<String>,
+    care_bleach: Option<String>,
+    care_dry: Option<String>,
+    care_iron: Option<String>,
+    care_dry_clean: Option<String>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    chest: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    waist: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    inseam: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    sleeve: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    wash_after: Option<Option<u32>>,
+    quantity: Option<u32>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    purchased_on: Option<Option<NaiveDate>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    lifespan_months: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    lifespan_wears: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    price: Option<Option<f64>>,
+    currency: Option<String>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    #[schemars(
+        with = "Option<String>",
+        description = "new, good, worn or retire-soon"
+    )]
+    condition: Option<Option<Condition>>,
+    #[schemars(with = "Option<String>", description = "wears or hours")]
+    tracking: Option<Tracking>,
+    #[serde(default, deserialize_with = "utils::nullable")]
+    #[schemars(with = "Option<String>", description = "light, medium or warm")]
+    warmth: Option<Option<Warmth>>,
+    #[schemars(
+        with = "Option<Vec<String>>",
+        description = "spring, summer, autumn or winter; empty for all year"
+    )]
+    seasons: Option<Vec<Season>>,
 }
 
-fn new_router(hb: Handlebars, db: Connection) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
+impl ItemPatch {
+    /// What the item would look like with this patch applied, for validation.
+    fn apply(&self, item: Item) -> Item {
+        fn pick<T: Clone>(new: &Option<T>, old: T) -> T {
+            new.clone().unwrap_or(old)
+        }
+
+        Item {
+            name: pick(&self.name, item.name),
+            description: pick(&self.description, item.description),
+            color: pick(&self.color, item.color),
+            tags: pick(&self.tags, item.tags),
+            retailer: pick(&self.retailer, item.retailer),
+            product_url: pick(&self.product_url, item.product_url),
+            return_by: pick(&self.return_by, item.return_by),
+            warranty_until: pick(&self.warranty_until, item.warranty_until),
+            care_wash: pick(&self.care_wash, item.care_wash),
+            care_bleach: pick(&self.care_bleach, item.care_bleach),
+            care_dry: pick(&self.care_dry, item.care_dry),
+            care_iron: pick(&self.care_iron, item.care_iron),
+            care_dry_clean: pick(&self.care_dry_clean, item.care_dry_clean),
+            chest: pick(&self.chest, item.chest),
+            waist: pick(&self.waist, item.waist),
+            inseam: pick(&self.inseam, item.inseam),
+            sleeve: pick(&self.sleeve, item.sleeve),
+            wash_after: pick(&self.wash_after, item.wash_after),
+            quantity: pick(&self.quantity, item.quantity),
+            purchased_on: pick(&self.purchased_on, item.purchased_on),
+            lifespan_months: pick(&self.lifespan_months, item.lifespan_months),
+            lifespan_wears: pick(&self.lifespan_wears, item.lifespan_wears),
+            price: pick(&self.price, item.price),
+            currency: pick(&self.currency, item.currency),
+            condition: pick(&self.condition, item.condition),
+            tracking: pick(&self.tracking, item.tracking),
+            warmth: pick(&self.warmth, item.warmth),
+            seasons: pick(&self.seasons, item.seasons),
+            ..item
+        }
+    }
+}
+
+fn new_router(
+    hb: Handlebars,
+    db: Connection,
+    media: Media,
+    machine: Machine,
+    weather: Weather,
+    trusted_proxies: Vec<IpAddr>,
+    timings: Timings,
+) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
     let hb = Arc::new(hb);
     let hbars = move |wt: WithTemplate<_>| wt.render(hb.clone());
+    let schema = graphql::schema(db.clone());
+    let reports = Reports::default();
+    let api = api::routes(db.clone(), schema.clone(), reports.clone());
     let with_state = warp::any().map(move || db.clone());
+    let with_media = warp::any().map(move || media.clone());
+    let with_machine = warp::any().map(move || machine.clone());
+    let with_weather = warp::any().map(move || weather.clone());
+    let with_schema = warp::any().map(move || schema.clone());
+    let with_reports = warp::any().map(move || reports.clone());
+    let with_timings = {
+        let timings = timings.clone();
+        warp::any().map(move || timings.clone())
+    };
 
     let index = warp::get()
         .and(path::end())
@@ -116,202 +785,3841 @@ fn new_router(hb: Handlebars, db: Connection) -> warp::filters::BoxedFilter<(imp
         )
     });
 
-    let new = warp::get()
-        .and(warp::path("new"))
+    let api_docs = warp::get()
+        .and(path("api"))
         .and(path::end())
-        .map(|| WithTemplate {
-            name: "new",
-            value: json!({}),
-        })
+        .map(|| warp::reply::html(include_str!("./static/api.html")));
+
+    let anonymized_export = warp::get()
+        .and(path("export"))
+        .and(path("anonymized.json"))
+        .and(path::end())
+        .and(warp::query::<ReportOpts>())
+        .and(with_state.clone())
+        .and(with_reports.clone())
+        .and_then(handle_anonymized_export);
+
+    let photo_export = warp::get()
+        .and(path("export"))
+        .and(path("photos.zip"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(export::photos);
+
+    let deadlines = warp::get()
+        .and(path("deadlines"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(deadlines_page)
         .map(hbars.clone());
 
-    let post_item = warp::post()
+    let lifespans = warp::get()
+        .and(path("lifespan"))
         .and(path::end())
-        .and(warp::body::content_length_limit(1024 * 32))
-        .and(warp::body::form())
         .and(with_state.clone())
-        .and_then(|item, conn: Connection| async move {
-            conn.new_item(item).await.map_err(|e| {
-                eprintln!("{}", e);
-                warp::reject::not_found()
-            })
+        .and_then(lifespan_page)
+        .map(hbars.clone());
+
+    let spending = warp::get()
+        .and(path("reports"))
+        .and(path("spending"))
+        .and(path::end())
+        .and(warp::query::<ReportOpts>())
+        .and(with_state.clone())
+        .and(with_reports.clone())
+        .and_then(spending_page)
+        .map(hbars.clone());
+
+    let changes = warp::get()
+        .and(path("reports"))
+        .and(path("changes"))
+        .and(path::end())
+        .and(warp::query::<ChangesOpts>())
+        .and(with_state.clone())
+        .and_then(changes_page)
+        .map(hbars.clone());
+
+    let declutter = warp::get()
+        .and(path("reports"))
+        .and(path("declutter"))
+        .and(path::end())
+        .and(warp::query::<Vec<(String, String)>>())
+        .and(with_state.clone())
+        .and_then(declutter_page)
+        .map(hbars.clone());
+
+    let admin = warp::get()
+        .and(path("admin"))
+        .and(path::end())
+        .and(with_timings)
+        .map(|timings: Timings| WithTemplate {
+            name: "admin",
+            value: json!({
+                "budgetMs": timings.budget().as_millis(),
+                "routes": timings.summary(),
+            }),
         })
-        .map(utils::go_home);
+        .map(hbars.clone());
 
-    let edit_item = warp::get()
-        .and(path::param())
+    let calendar = warp::get()
+        .and(path("calendar"))
         .and(path::end())
+        .and(warp::query::<CalendarOpts>())
         .and(with_state.clone())
-        .and_then(handle_edit_form)
-        .map(hbars);
+        .and_then(calendar_page)
+        .map(hbars.clone());
 
-    let update_item = warp::post()
-        .and(path::param())
+    let suggest = warp::get()
+        .and(path("suggest"))
+        .and(path::end())
+        .and(warp::query::<SuggestOpts>())
+        .and(with_state.clone())
+        .and(with_weather)
+        .and_then(suggest_page)
+        .map(hbars.clone());
+
+    let plan_wear = warp::post()
         .and(path::end())
         .and(warp::body::content_length_limit(1024 * 32))
         .and(warp::body::form())
         .and(with_state.clone())
-        .and_then(|id, item, conn: Connection| async move {
-            conn.update_item(Item { id, ..item }).await.map_err(|e| {
-                eprintln!("{}", e);
-                warp::reject::not_found()
-            })
-        })
-        .map(utils::go_home);
+        .and_then(plan_wear);
 
-    let increment_item = warp::post()
+    let wear_planned = warp::post()
         .and(path::param())
-        .and(warp::path("increment"))
+        .and(path("wear"))
         .and(path::end())
         .and(with_state.clone())
         .and_then(|id, conn: Connection| async move {
-            conn.log_wear(id).await.map_err(|e| {
-                eprintln!("{}", e);
-                warp::reject::not_found()
-            })
+            conn.wear_planned(id, Local::today().naive_local())
+                .await
+                .map_err(|e| {
+                    eprintln!("wearing planned wear {}: {}", id, e);
+                    warp::reject::not_found()
+                })
         })
-        .map(utils::go_home);
+        .map(|(day, _): (NaiveDate, u64)| {
+            utils::go_to(format!("/calendar?month={}", day.format("%Y-%m")))
+        });
 
-    let reset_item = warp::post()
+    let remove_planned = warp::post()
         .and(path::param())
-        .and(warp::path("reset"))
+        .and(path("remove"))
         .and(path::end())
         .and(with_state.clone())
         .and_then(|id, conn: Connection| async move {
-            conn.log_wash(id).await.map_err(|e| {
-                eprintln!("{}", e);
+            conn.delete_planned_wear(id).await.map_err(|e| {
+                eprintln!("removing planned wear {}: {}", id, e);
                 warp::reject::not_found()
             })
         })
-        .map(utils::go_home);
+        .map(|_| utils::go_to("/calendar".into()));
 
-    let delete_item = warp::post()
-        .and(path::param())
-        .and(path("remove"))
+    let plan_routes = path("calendar")
+        .and(path("plan"))
+        .and(plan_wear.or(wear_planned).or(remove_planned))
+        .boxed();
+
+    let laundry = warp::get()
+        .and(path("laundry"))
         .and(path::end())
-        .and(with_state)
-        .and_then(|id, conn: Connection| async move {
-            conn.delete_item(id).await.map_err(|e| {
-                eprintln!("{}", e);
-                warp::reject::not_found()
+        .and(with_state.clone())
+        .and_then(laundry_page)
+        .map(hbars.clone());
+
+    let laundry_plan = warp::get()
+        .and(path("laundry"))
+        .and(path("plan"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(laundry_plan_page)
+        .map(hbars.clone());
+
+    let laundry_calendar = warp::get()
+        .and(path("laundry"))
+        .and(path("calendar.ics"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|conn: Connection| async move {
+            let schedule = conn.get_laundry_schedule().await.map_err(|e| {
+                eprintln!("request for laundry calendar: {}", e);
+                warp::reject::not_found()
+            })?;
+
+            Ok::<_, warp::Rejection>(warp::reply::with_header(
+                laundry::calendar(&schedule, Local::today().naive_local()),
+                "Content-Type",
+                "text/calendar; charset=utf-8",
+            ))
+        });
+
+    let laundry_schedule = warp::post()
+        .and(path("laundry"))
+        .and(path("schedule"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(32 * 1024))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: Vec<(String, String)>, conn: Connection| async move {
+            let days = form
+                .iter()
+                .filter(|(k, _)| k == "day")
+                .filter_map(|(_, v)| v.parse().ok())
+                .collect::<Vec<_>>();
+
+            conn.set_laundry_schedule(&days).await.map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/laundry".into()));
+
+    let laundry_wash = warp::post()
+        .and(path("laundry"))
+        .and(path("wash"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(32 * 1024))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: Vec<(String, String)>, conn: Connection| async move {
+            let ids = form
+                .iter()
+                .filter(|(k, _)| k == "item")
+                .filter_map(|(_, v)| v.parse().ok())
+                .collect::<Vec<_>>();
+
+            Ok::<_, warp::Rejection>(wash_items(&conn, &ids).await)
+        })
+        .map(|_| utils::go_to("/laundry".into()));
+
+    let laundry_done = warp::post()
+        .and(path("laundry"))
+        .and(path("done"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(32 * 1024))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(finish_laundry_day)
+        .map(|_| utils::go_to("/laundry".into()));
+
+    let start_machine = warp::post()
+        .and(path::end())
+        .and(warp::body::content_length_limit(32 * 1024))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: Vec<(String, String)>, conn: Connection| async move {
+            let ids = form
+                .iter()
+                .filter(|(k, _)| k == "item")
+                .filter_map(|(_, v)| v.parse().ok())
+                .collect::<Vec<_>>();
+
+            conn.start_machine_load(&ids).await.map_err(|e| {
+                eprintln!("starting washing machine: {}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/laundry".into()));
+
+    // called by the smart plug (or whatever watches it), so it takes no particular body
+    let machine_finished = warp::post()
+        .and(path("finished"))
+        .and(path::end())
+        .and(with_machine.clone())
+        .and(with_state.clone())
+        .and_then(|machine: Machine, conn: Connection| async move {
+            machine
+                .finished(&conn)
+                .await
+                .map(|report| warp::reply::json(&report))
+                .map_err(|e| {
+                    eprintln!("washing machine finished: {}", e);
+                    warp::reject::not_found()
+                })
+        });
+
+    let unload_machine = warp::post()
+        .and(path("unload"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|conn: Connection| async move {
+            machine::unload(&conn).await.map_err(|e| {
+                eprintln!("unloading washing machine: {}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/laundry".into()));
+
+    let clear_machine = warp::post()
+        .and(path("clear"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|conn: Connection| async move {
+            conn.clear_machine_load().await.map_err(|e| {
+                eprintln!("clearing washing machine: {}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/laundry".into()));
+
+    let machine_routes = path("laundry")
+        .and(path("machine"))
+        .and(
+            start_machine
+                .or(machine_finished)
+                .or(unload_machine)
+                .or(clear_machine),
+        )
+        .boxed();
+
+    let outfits = warp::get()
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(outfits_page)
+        .map(hbars.clone());
+
+    let add_outfit = warp::post()
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form, conn| save_outfit(None, form, conn));
+
+    let view_outfit = warp::get()
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(outfit_page)
+        .map(hbars.clone());
+
+    let update_outfit = warp::post()
+        .and(path::param())
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, form, conn| save_outfit(Some(id), form, conn));
+
+    let remove_outfit = warp::post()
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.delete_outfit(id).await.map_err(|e| {
+                eprintln!("removing outfit {}: {}", id, e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/outfit".into()));
+
+    let wear_outfit = warp::post()
+        .and(path::param())
+        .and(path("wear"))
+        .and(path::end())
+        .and(utils::body_or_default())
+        .and(idempotency::key())
+        .and(with_state.clone())
+        .and_then(
+            |id, details: WearDetails, key, conn: Connection| async move {
+                let action = format!("wear-outfit/{}", id);
+                idempotency::once(&conn, key, &action, async {
+                    utils::check_not_future(details.date)?;
+                    let outfit = conn.get_outfit(id).await?;
+                    let wears = outfit
+                        .items
+                        .iter()
+                        .map(|i| (i.key, &details))
+                        .collect::<Vec<_>>();
+                    Ok(conn.log_wear_many(&wears).await?)
+                })
+                .await
+                .map_err(|e| {
+                    eprintln!("wearing outfit {}: {}", id, e);
+                    warp::reject::not_found()
+                })
+            },
+        )
+        .map(|_| utils::go_to("/outfit".into()));
+
+    let trips = warp::get()
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(trips_page)
+        .map(hbars.clone());
+
+    let add_trip = warp::post()
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form, conn| save_trip(None, form, conn));
+
+    let view_trip = warp::get()
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(trip_page)
+        .map(hbars.clone());
+
+    let update_trip = warp::post()
+        .and(path::param())
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, form, conn| save_trip(Some(id), form, conn));
+
+    let remove_trip = warp::post()
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.delete_trip(id).await.map_err(|e| {
+                eprintln!("removing trip {}: {}", id, e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/trip".into()));
+
+    let add_trip_items = warp::post()
+        .and(path::param())
+        .and(path("items"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(
+            |id, form: Vec<(String, String)>, conn: Connection| async move {
+                let ids = |key| {
+                    form.iter()
+                        .filter(|(k, _)| k == key)
+                        .filter_map(|(_, v)| v.parse::<usize>().ok())
+                        .collect::<Vec<_>>()
+                };
+                conn.add_trip_items(id, &ids("item"), &ids("outfit"))
+                    .await
+                    .map(|_| id)
+                    .map_err(|e| {
+                        eprintln!("packing for trip {}: {}", id, e);
+                        warp::reject::not_found()
+                    })
+            },
+        )
+        .map(|id: usize| utils::go_to(format!("/trip/{}", id)));
+
+    let pack_trip_item = warp::post()
+        .and(path::param())
+        .and(path("items"))
+        .and(path::param())
+        .and(path("pack"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, item_id, form: PackForm, conn: Connection| async move {
+            conn.set_packed(id, item_id, form.packed)
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("packing item {} for trip {}: {}", item_id, id, e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(|id: usize| utils::go_to(format!("/trip/{}", id)));
+
+    let remove_trip_item = warp::post()
+        .and(path::param())
+        .and(path("items"))
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, item_id, conn: Connection| async move {
+            conn.remove_trip_item(id, item_id)
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("unpacking item {} for trip {}: {}", item_id, id, e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(|id: usize| utils::go_to(format!("/trip/{}", id)));
+
+    let wear_trip = warp::post()
+        .and(path::param())
+        .and(path("wear"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.log_trip_wears(id, Local::today().naive_local())
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("logging wears for trip {}: {}", id, e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(|id: usize| utils::go_to(format!("/trip/{}", id)));
+
+    let open_view = warp::get()
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.get_saved_view(id).await.map_err(|e| {
+                eprintln!("request for saved view {}: {}", id, e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|query| utils::go_to(format!("/?{}", query)));
+
+    let add_view = warp::post()
+        .and(path::end())
+        .and(warp::query::query())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|opts, form, conn| save_view(None, opts, form, conn));
+
+    let update_view = warp::post()
+        .and(path::param())
+        .and(path::end())
+        .and(warp::query::query())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, opts, form, conn| save_view(Some(id), opts, form, conn));
+
+    let remove_view = warp::post()
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.delete_saved_view(id).await.map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_home);
+
+    let view_alert = warp::post()
+        .and(path::param())
+        .and(path("alert"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(set_view_alert);
+
+    let welcome = warp::get()
+        .and(path("welcome"))
+        .and(path::end())
+        .and(warp::query::query())
+        .map(welcome_page)
+        .map(hbars.clone());
+
+    let welcome_items = warp::post()
+        .and(path("welcome"))
+        .and(path("items"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(add_first_items)
+        .map(|_| utils::go_to("/welcome?step=3".into()));
+
+    let import_items = warp::post()
+        .and(path("import"))
+        .and(path("items"))
+        .and(path::end())
+        .and(warp::multipart::form().max_length(import::MAX_CSV_SIZE))
+        .and(with_state.clone())
+        .and_then(import::items)
+        .map(hbars.clone());
+
+    let import_page = warp::get()
+        .and(path("import"))
+        .and(path::end())
+        .map(|| WithTemplate {
+            name: "import",
+            value: json!({}),
+        })
+        .map(hbars.clone());
+
+    let import_photos = warp::post()
+        .and(path("import"))
+        .and(path("photos"))
+        .and(path::end())
+        .and(warp::multipart::form().max_length(import::MAX_ARCHIVE_SIZE))
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(import::photos)
+        .map(hbars.clone());
+
+    let measurements = warp::get()
+        .and(path("measurements"))
+        .and(path::end())
+        .and(warp::query::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .and_then(measurements_page)
+        .map(hbars.clone());
+
+    let settings_page = warp::get()
+        .and(path("settings"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|conn: Connection| async move {
+            let settings = conn.get_settings().await.map_err(|e| {
+                eprintln!("request for settings: {}", e);
+                warp::reject::not_found()
+            })?;
+            let goals = conn.get_rotation_goals().await.unwrap_or_else(|e| {
+                eprintln!(
+                    "request for settings: could not retrieve rotation goals: {}",
+                    e
+                );
+                Vec::new()
+            });
+            let limits = conn.get_wear_limits().await.unwrap_or_else(|e| {
+                eprintln!(
+                    "request for settings: could not retrieve wear limits: {}",
+                    e
+                );
+                Vec::new()
+            });
+            let categories = conn.get_categories().await.unwrap_or_else(|e| {
+                eprintln!("request for settings: could not retrieve categories: {}", e);
+                Vec::new()
+            });
+            let fields = conn.get_custom_fields().await.unwrap_or_else(|e| {
+                eprintln!(
+                    "request for settings: could not retrieve custom fields: {}",
+                    e
+                );
+                Vec::new()
+            });
+
+            Ok::<_, warp::Rejection>(WithTemplate {
+                name: "settings",
+                value: json!({
+                    "settings": settings,
+                    "currencies": locale::CURRENCIES
+                        .iter()
+                        .map(|(code, symbol, _)| json!({
+                            "code": code,
+                            "symbol": symbol,
+                            "selected": *code == settings.currency,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "locales": locale::LOCALES
+                        .iter()
+                        .map(|l| json!({
+                            "code": l.code,
+                            "name": l.name,
+                            "selected": l.code == settings.locale,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "goals": goals
+                        .iter()
+                        .map(|(category, wears)| json!({ "category": category, "wears": wears }))
+                        .collect::<Vec<_>>(),
+                    "limits": limits
+                        .iter()
+                        .map(|(category, wears)| json!({ "category": category, "wears": wears }))
+                        .collect::<Vec<_>>(),
+                    "categories": categories.into_iter().map(|(c,)| c).collect::<Vec<_>>(),
+                    "customFields": fields,
+                }),
+            })
+        })
+        .map(hbars.clone());
+
+    let save_settings = warp::post()
+        .and(path("settings"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and(with_reports)
+        .and_then(
+            |settings: Settings, conn: Connection, reports: Reports| async move {
+                reports.clear();
+                conn.save_settings(&settings).await.map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+            },
+        )
+        .map(|_| utils::go_to("/settings".into()));
+
+    let set_goal = warp::post()
+        .and(path("settings"))
+        .and(path("goals"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: GoalForm, conn: Connection| async move {
+            match form.weekly_wears {
+                Some(wears) if !form.category.trim().is_empty() => {
+                    conn.set_rotation_goal(&form.category, wears).await
+                }
+                _ => conn.delete_rotation_goal(&form.category).await,
+            }
+            .map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/settings".into()));
+
+    let set_field = warp::post()
+        .and(path("settings"))
+        .and(path("fields"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: FieldForm, conn: Connection| async move {
+            match (form.remove, form.kind) {
+                (Some(id), _) => conn.delete_custom_field(id).await,
+                (None, Some(kind)) if !form.name.trim().is_empty() => {
+                    conn.add_custom_field(&form.name, kind).await
+                }
+                _ => Ok(0),
+            }
+            .map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/settings".into()));
+
+    let set_limit = warp::post()
+        .and(path("settings"))
+        .and(path("limits"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|form: LimitForm, conn: Connection| async move {
+            match form.wears {
+                Some(wears) if !form.category.trim().is_empty() => {
+                    conn.set_wear_limit(&form.category, wears).await
+                }
+                _ => conn.delete_wear_limit(&form.category).await,
+            }
+            .map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(|_| utils::go_to("/settings".into()));
+
+    let graphql = warp::post()
+        .and(path("graphql"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::json())
+        .and(with_schema)
+        .and_then(graphql::execute)
+        .map(|reply| api::deprecated(reply, "/api/v1/graphql".into()));
+
+    let todo = warp::get()
+        .and(path("todo"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(todo_page)
+        .map(hbars.clone());
+
+    let quick = warp::get()
+        .and(path("quick"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(quick_page)
+        .map(hbars.clone());
+
+    let new = warp::get()
+        .and(warp::path("new"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|conn: Connection| async move {
+            let settings = conn.get_settings().await.map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })?;
+            let item = Item::default();
+            let fields = conn.get_custom_fields().await.unwrap_or_else(|e| {
+                eprintln!(
+                    "request for new item form: could not retrieve custom fields: {}",
+                    e
+                );
+                Vec::new()
+            });
+
+            Ok::<_, warp::Rejection>(WithTemplate {
+                name: "new",
+                value: json!({
+                    "careOptions": care::form_options(&item),
+                    "conditionOptions": condition::form_options(&item),
+                    "trackingOptions": Tracking::form_options(item.tracking),
+                    "warmthOptions": Warmth::form_options(item.warmth),
+                    "seasonOptions": Season::form_options(&item.seasons),
+                    "customFields": custom::form_fields(&fields, &[]),
+                    "units": settings.units.label(),
+                    "measurementFields": measurements::form_fields(&item, settings.units),
+                    "defaultCurrency": settings.currency,
+                    "formKey": idempotency::new_key(),
+                }),
+            })
+        })
+        .map(hbars.clone());
+
+    let post_item = warp::post()
+        .and(path::end())
+        .and(item_form())
+        .and(idempotency::key())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(
+            |(mut item, photos, custom): ItemForm, key, conn: Connection, media: Media| async move {
+                let fail = |e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                };
+
+                item.validate().map_err(fail)?;
+                let fields = conn.get_custom_fields().await.map_err(|e| fail(e.into()))?;
+                let custom = custom::validate(&fields, custom).map_err(fail)?;
+                let settings = conn.get_settings().await.map_err(|e| fail(e.into()))?;
+                measurements::to_cm(&mut item, settings.units);
+                idempotency::once(&conn, key, "new", async {
+                    let id = conn.new_item(item).await?;
+                    conn.set_custom_values(id, &custom).await?;
+                    for photo in &photos {
+                        attachments::store(id, photo, &conn, &media).await?;
+                    }
+                    Ok(id)
+                })
+                .await
+                .map_err(fail)
+            },
+        )
+        .map(utils::go_home);
+
+    let view_item = warp::get()
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(handle_item_page)
+        .map(hbars.clone());
+
+    let item_history = warp::get()
+        .and(path::param())
+        .and(path("history"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(item_history_page)
+        .map(hbars.clone());
+
+    let item_edits = warp::get()
+        .and(path::param())
+        .and(path("changes"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(item_edits_page)
+        .map(hbars.clone());
+
+    let edit_item = warp::get()
+        .and(path::param())
+        .and(path("edit"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(handle_edit_form)
+        .map(hbars.clone());
+
+    let update_item = warp::post()
+        .and(path::param())
+        .and(path::end())
+        .and(item_form())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(
+            |id, (mut item, photos, custom): ItemForm, conn: Connection, media: Media| async move {
+                let fail = |e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                };
+
+                item.validate().map_err(fail)?;
+                let fields = conn.get_custom_fields().await.map_err(|e| fail(e.into()))?;
+                let custom = custom::validate(&fields, custom).map_err(fail)?;
+                let settings = conn.get_settings().await.map_err(|e| fail(e.into()))?;
+                measurements::to_cm(&mut item, settings.units);
+                conn.update_item(Item { id, ..item })
+                    .await
+                    .map_err(|e| fail(e.into()))?;
+                conn.set_custom_values(id, &custom)
+                    .await
+                    .map_err(|e| fail(e.into()))?;
+                for photo in &photos {
+                    attachments::store(id, photo, &conn, &media)
+                        .await
+                        .map_err(fail)?;
+                }
+                Ok::<_, warp::Rejection>(())
+            },
+        )
+        .map(utils::go_home);
+
+    let item_photo = warp::get()
+        .and(path::param())
+        .and(path("photo"))
+        .and(path::end())
+        .and(warp::query())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(attachments::photo);
+
+    let patch_item = warp::patch()
+        .and(path::param())
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::json())
+        .and(with_state.clone())
+        .and_then(handle_patch);
+
+    let quick_edit = warp::post()
+        .and(path::param())
+        .and(path("quick"))
+        .and(path::end())
+        .and(warp::query::query())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(handle_quick_edit);
+
+    let increment_item = warp::post()
+        .and(path::param())
+        .and(warp::path("increment"))
+        .and(path::end())
+        .and(utils::body_or_default())
+        .and(idempotency::key())
+        .and(with_state.clone())
+        .and_then(
+            |id, details: WearDetails, key, conn: Connection| async move {
+                let action = format!("increment/{}", id);
+                idempotency::once(&conn, key, &action, async {
+                    utils::check_not_future(details.date)?;
+                    Ok(conn.log_wear(id, &details).await?)
+                })
+                .await
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+            },
+        )
+        .map(utils::go_home);
+
+    let undo_wear = warp::post()
+        .and(path::param())
+        .and(warp::path("undo-wear"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.undo_wear(id).await.map(|_| id).map_err(|e| {
+                eprintln!("undoing wear of item {}: {}", id, e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let resole_item = warp::post()
+        .and(path::param())
+        .and(warp::path("resole"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.log_resole(id).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let duplicate_item = warp::post()
+        .and(path::param())
+        .and(warp::path("duplicate"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.clone_item(id).await.map_err(|e| {
+                eprintln!("duplicating item {}: {}", id, e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let archive_item = warp::post()
+        .and(path::param())
+        .and(warp::path("archive"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.archive_item(id, true).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let purchase_item = warp::post()
+        .and(path::param())
+        .and(warp::path("purchase"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.purchase_item(id, Local::today().naive_local())
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(utils::go_to_item);
+
+    let unarchive_item = warp::post()
+        .and(path::param())
+        .and(warp::path("unarchive"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.archive_item(id, false).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let flag_task = warp::post()
+        .and(path::param())
+        .and(warp::path("tasks"))
+        .and(path::param())
+        .and(path::end())
+        .and(utils::body_or_default::<TaskForm>())
+        .and(with_state.clone())
+        .and_then(
+            |id, task: Task, form: TaskForm, conn: Connection| async move {
+                match conn.flag_task(id, task, &form.note).await {
+                    Ok(0) => Err(warp::reject::not_found()),
+                    Ok(_) => Ok(form.redirect(id)),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        Err(warp::reject::not_found())
+                    }
+                }
+            },
+        )
+        .map(utils::go_to);
+
+    let clear_task = warp::post()
+        .and(path::param())
+        .and(warp::path("tasks"))
+        .and(path::param())
+        .and(warp::path("done"))
+        .and(path::end())
+        .and(utils::body_or_default::<TaskForm>())
+        .and(with_state.clone())
+        .and_then(
+            |id, task: Task, form: TaskForm, conn: Connection| async move {
+                conn.clear_task(id, task)
+                    .await
+                    .map(|_| form.redirect(id))
+                    .map_err(|e| {
+                        eprintln!("{}", e);
+                        warp::reject::not_found()
+                    })
+            },
+        )
+        .map(utils::go_to);
+
+    let reset_item = warp::post()
+        .and(path::param())
+        .and(warp::path("reset"))
+        .and(path::end())
+        .and(utils::body_or_default())
+        .and(idempotency::key())
+        .and(with_state.clone())
+        .and_then(
+            |id, details: WashDetails, key, conn: Connection| async move {
+                let action = format!("reset/{}", id);
+                idempotency::once(&conn, key, &action, async {
+                    utils::check_not_future(details.date)?;
+                    Ok(conn.log_wash(id, &details).await?)
+                })
+                .await
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+            },
+        )
+        .map(utils::go_home);
+
+    let tag_action = warp::get()
+        .and(path("t"))
+        .and(path::param())
+        .and(path::end())
+        .and(warp::query::query())
+        .and(with_state.clone())
+        .and_then(handle_tag)
+        .map(hbars.clone());
+
+    let rotate_token = warp::post()
+        .and(path::param())
+        .and(path("token"))
+        .and(path("rotate"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.retire_token(id, false).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let revoke_token = warp::post()
+        .and(path::param())
+        .and(path("token"))
+        .and(path("revoke"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.retire_token(id, true).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let upload_attachment = warp::post()
+        .and(path::param())
+        .and(path("attachments"))
+        .and(path::end())
+        .and(warp::multipart::form().max_length(media::MAX_FILE_SIZE + 1024 * 32))
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(attachments::upload)
+        .map(utils::go_to_item);
+
+    let download_attachment = warp::get()
+        .and(path::param())
+        .and(path("attachments"))
+        .and(path::param())
+        .and(path::end())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(attachments::download);
+
+    let remove_attachment = warp::post()
+        .and(path::param())
+        .and(path("attachments"))
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and(with_media.clone())
+        .and_then(attachments::remove)
+        .map(utils::go_to_item);
+
+    let set_replacement = warp::post()
+        .and(path::param())
+        .and(path("replacement"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, form: ReplacementForm, conn: Connection| async move {
+            conn.set_replacement(id, form.replaced_by)
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(utils::go_to_item);
+
+    let rate_condition = warp::post()
+        .and(path::param())
+        .and(path("condition"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, form: ConditionForm, conn: Connection| async move {
+            let patch = ItemPatch {
+                condition: Some(form.condition),
+                ..ItemPatch::default()
+            };
+            conn.patch_item(id, &patch).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let add_repair = warp::post()
+        .and(path::param())
+        .and(path("repairs"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(|id, form: RepairForm, conn: Connection| async move {
+            let on = form.date.unwrap_or_else(|| Local::today().naive_local());
+            conn.add_repair(id, on, &form.description, form.cost)
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(utils::go_to_item);
+
+    let remove_repair = warp::post()
+        .and(path::param())
+        .and(path("repairs"))
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, repair_id, conn: Connection| async move {
+            conn.delete_repair(id, repair_id)
+                .await
+                .map(|_| id)
+                .map_err(|e| {
+                    eprintln!("{}", e);
+                    warp::reject::not_found()
+                })
+        })
+        .map(utils::go_to_item);
+
+    let trash_item = warp::post()
+        .and(path::param())
+        .and(path("remove"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.trash_item(id, true).await.map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_home);
+
+    let restore_item = warp::post()
+        .and(path::param())
+        .and(path("restore"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(|id, conn: Connection| async move {
+            conn.trash_item(id, false).await.map(|_| id).map_err(|e| {
+                eprintln!("{}", e);
+                warp::reject::not_found()
+            })
+        })
+        .map(utils::go_to_item);
+
+    let delete_item = warp::post()
+        .and(path::param())
+        .and(path("delete"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and(with_media)
+        .and_then(delete_item)
+        .map(|()| utils::go_to("/trash".to_string()));
+
+    let wishlist = warp::get()
+        .and(path("wishlist"))
+        .and(path::end())
+        .and(with_state.clone())
+        .and_then(wishlist_page)
+        .map(hbars.clone());
+
+    let add_to_wishlist = warp::post()
+        .and(path("wishlist"))
+        .and(path::end())
+        .and(warp::body::content_length_limit(1024 * 32))
+        .and(warp::body::form())
+        .and(with_state.clone())
+        .and_then(add_to_wishlist)
+        .map(|_| utils::go_to("/wishlist".into()));
+
+    let trash = warp::get()
+        .and(path("trash"))
+        .and(path::end())
+        .and(with_state)
+        .and_then(trash_page)
+        .map(hbars.clone());
+
+    // boxed, in groups, so that matching a request doesn't nest every route's future on the stack
+    let item_actions = increment_item
+        .or(undo_wear)
+        .or(resole_item)
+        .or(duplicate_item)
+        .or(archive_item)
+        .or(unarchive_item)
+        .or(purchase_item)
+        .or(restore_item)
+        .or(reset_item)
+        .or(flag_task)
+        .or(clear_task)
+        .or(set_replacement)
+        .or(rate_condition)
+        .or(add_repair)
+        .or(remove_repair)
+        .or(rotate_token)
+        .or(revoke_token)
+        .boxed();
+
+    let item_routes = warp::path("item")
+        .and(
+            post_item
+                .or(new)
+                .or(view_item)
+                .or(item_history)
+                .or(item_edits)
+                .or(edit_item)
+                .or(item_photo)
+                .or(update_item)
+                .or(patch_item)
+                .or(quick_edit)
+                .or(item_actions)
+                .or(upload_attachment)
+                .or(download_attachment)
+                .or(remove_attachment)
+                .or(trash_item)
+                .or(delete_item),
+        )
+        .boxed();
+
+    let routes = index
+        .or(css)
+        .or(tag_action)
+        .or(quick)
+        .or(deadlines)
+        .or(lifespans)
+        .or(spending)
+        .or(changes)
+        .or(declutter)
+        .or(todo)
+        .or(laundry)
+        .or(calendar)
+        .or(plan_routes)
+        .or(suggest)
+        .or(laundry_plan)
+        .or(laundry_calendar)
+        .or(laundry_schedule)
+        .or(laundry_wash)
+        .or(laundry_done)
+        .or(machine_routes)
+        .or(import_page)
+        .or(api)
+        .or(graphql)
+        .or(api_docs)
+        .or(measurements)
+        .or(trash)
+        .or(wishlist)
+        .or(add_to_wishlist)
+        .or(admin)
+        .or(settings_page)
+        .or(save_settings)
+        .or(set_goal)
+        .or(set_limit)
+        .or(set_field)
+        .or(import_photos)
+        .or(import_items)
+        .or(welcome)
+        .or(welcome_items)
+        .or(anonymized_export)
+        .or(photo_export)
+        .or(warp::path("outfit").and(
+            outfits
+                .or(add_outfit)
+                .or(view_outfit)
+                .or(update_outfit)
+                .or(remove_outfit)
+                .or(wear_outfit),
+        ))
+        .or(warp::path("trip").and(
+            trips
+                .or(add_trip)
+                .or(view_trip)
+                .or(update_trip)
+                .or(remove_trip)
+                .or(add_trip_items)
+                .or(pack_trip_item)
+                .or(remove_trip_item)
+                .or(wear_trip),
+        ))
+        .or(warp::path("view").and(
+            open_view
+                .or(add_view)
+                .or(update_view)
+                .or(remove_view)
+                .or(view_alert),
+        ))
+        .or(item_routes);
+
+    client::log("wear", Arc::new(trusted_proxies), timings, routes).boxed()
+}
+
+/// Something measured over the course of a single wear, mostly for shoes and other gear where the
+/// number of wears doesn't say much.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Metric {
+    Distance,
+    Steps,
+    Hours,
+}
+
+impl Metric {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Distance => "distance",
+            Self::Steps => "steps",
+            Self::Hours => "hours",
+        }
+    }
+
+    /// Format a stored total of the named metric for display.
+    fn display(kind: &str, total: f64) -> String {
+        if kind == Self::Distance.as_str() {
+            format!("{:.1} km", total)
+        } else if kind == Self::Hours.as_str() {
+            format!("{:.1} h", total)
+        } else {
+            format!("{:.0} {}", total, kind)
+        }
+    }
+}
+
+/// How an item's use is counted: by wears, or for gear like running shoes, by hours of use.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Tracking {
+    #[default]
+    Wears,
+    Hours,
+}
+
+impl Tracking {
+    const ALL: [Self; 2] = [Self::Wears, Self::Hours];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Wears => "wears",
+            Self::Hours => "hours",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Wears => "Wears",
+            Self::Hours => "Hours of use",
+        }
+    }
+
+    /// Choices for the item form, with `current` selected.
+    fn form_options(current: Self) -> serde_json::Value {
+        Self::ALL
+            .iter()
+            .map(|t| json!({ "value": t.as_str(), "label": t.label(), "selected": *t == current }))
+            .collect()
+    }
+}
+
+impl std::str::FromStr for Tracking {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown tracking mode: {}", s))
+    }
+}
+
+/// Optional extra information submitted along with a wear.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct WearDetails {
+    #[serde(default, deserialize_with = "utils::optional")]
+    distance: Option<f64>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    steps: Option<f64>,
+    /// How long it was worn, for items tracked by the hour
+    #[serde(default, deserialize_with = "utils::optional")]
+    hours: Option<f64>,
+    /// Where it was worn, or anything else worth remembering
+    #[serde(default)]
+    note: String,
+    /// The day it was worn, if not today
+    #[serde(default, deserialize_with = "utils::optional")]
+    date: Option<NaiveDate>,
+}
+
+impl WearDetails {
+    fn metrics(&self) -> impl Iterator<Item = (Metric, f64)> {
+        vec![
+            (Metric::Distance, self.distance),
+            (Metric::Steps, self.steps),
+            (Metric::Hours, self.hours),
+        ]
+        .into_iter()
+        .filter_map(|(metric, value)| {
+            value
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .map(|v| (metric, v))
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WashMethod {
+    Machine,
+    Hand,
+    DryClean,
+}
+
+impl WashMethod {
+    const ALL: [Self; 3] = [Self::Machine, Self::Hand, Self::DryClean];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Machine => "machine",
+            Self::Hand => "hand",
+            Self::DryClean => "dry-clean",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Machine => "Machine washed",
+            Self::Hand => "Hand washed",
+            Self::DryClean => "Dry cleaned",
+        }
+    }
+}
+
+impl std::str::FromStr for WashMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown wash method: {}", s))
+    }
+}
+
+/// Optional extra information submitted along with a wash.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct WashDetails {
+    #[serde(default, deserialize_with = "utils::optional")]
+    #[schemars(with = "Option<String>", description = "machine, hand or dry-clean")]
+    method: Option<WashMethod>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    temperature: Option<u32>,
+    #[serde(default)]
+    detergent: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    cost: Option<f64>,
+    /// The day it was washed, if not today
+    #[serde(default, deserialize_with = "utils::optional")]
+    date: Option<NaiveDate>,
+}
+
+/// Work an item is waiting on, tracked separately from whether it is clean.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Task {
+    Iron,
+    Mend,
+}
+
+impl Task {
+    const ALL: [Self; 2] = [Self::Iron, Self::Mend];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Iron => "iron",
+            Self::Mend => "mend",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Iron => "Needs ironing",
+            Self::Mend => "Needs mending",
+        }
+    }
+}
+
+impl std::str::FromStr for Task {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown task: {}", s))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TaskForm {
+    #[serde(default)]
+    note: String,
+    /// Send the browser back to the to-do list instead of the item page.
+    #[serde(default)]
+    next: Option<String>,
+}
+
+impl TaskForm {
+    fn redirect(&self, id: usize) -> String {
+        match self.next.as_deref() {
+            Some("todo") => "/todo".into(),
+            _ => format!("/item/{}", id),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum SortItems {
+    Name,
+    Count,
+    Wear,
+    Wash,
+    /// Wears ever, not just since the last wash
+    Total,
+    /// The first tag
+    Tag,
+    Description,
+    /// When the item itself was last edited
+    Modified,
+    /// From new to worn out
+    Condition,
+}
+
+/// Where items without a value go when sorting by something they may not have, like a date.
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Nulls {
+    First,
+    Last,
+}
+
+/// Predefined filters for the index.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum View {
+    /// Not worn in the last 90 days, or ever
+    Unworn,
+    /// Added in the last 30 days
+    New,
+    /// Washed in the last week
+    RecentlyWashed,
+}
+
+/// Items shown on one page of the index, unless asked for otherwise.
+const PER_PAGE: usize = 50;
+
+#[derive(Clone, Deserialize)]
+struct IndexOpts {
+    view: Option<View>,
+    q: Option<String>,
+    tag: Option<String>,
+    color: Option<String>,
+    unworn_days: Option<u32>,
+    filter: Option<String>,
+    archived: Option<bool>,
+    /// Keys to sort by, separated by commas; later ones break ties in earlier ones
+    #[serde(default, deserialize_with = "utils::comma_separated")]
+    sort: Vec<SortItems>,
+    descending: Option<bool>,
+    /// Where never worn or washed items go; last unless asked otherwise
+    nulls: Option<Nulls>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+}
+
+impl IndexOpts {
+    /// The query string for this listing, starting from its first page.
+    fn query(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(view) = self.view {
+            params.push(format!("view={}", json!(view).as_str().unwrap_or_default()));
+        }
+        if let Some(q) = &self.q {
+            params.push(format!("q={}", utils::encode_query(q)));
+        }
+        if let Some(tag) = &self.tag {
+            params.push(format!("tag={}", utils::encode_query(tag)));
+        }
+        if let Some(color) = &self.color {
+            params.push(format!("color={}", utils::encode_query(color)));
+        }
+        if let Some(days) = self.unworn_days {
+            params.push(format!("unworn_days={}", days));
+        }
+        if let Some(filter) = &self.filter {
+            params.push(format!("filter={}", utils::encode_query(filter)));
+        }
+        if self.archived == Some(true) {
+            params.push("archived=true".into());
+        }
+        if !self.sort.is_empty() {
+            params.push(format!("sort={}", sort_param(&self.sort)));
+        }
+        if self.descending == Some(true) {
+            params.push("descending=true".into());
+        }
+        if let Some(nulls) = self.nulls {
+            params.push(format!(
+                "nulls={}",
+                json!(nulls).as_str().unwrap_or_default()
+            ));
+        }
+        if let Some(per_page) = self.per_page {
+            params.push(format!("per_page={}", per_page));
+        }
+
+        params.join("&")
+    }
+
+    /// What the listing is narrowed down to, and why the filter language couldn't be
+    /// understood if it couldn't, in which case that part is left out.
+    fn filter(&self) -> (ListFilter, Option<String>) {
+        let (query, error) = match self.filter.as_deref().unwrap_or_default().parse::<Query>() {
+            Ok(query) => (query, None),
+            Err(e) => (Query::default(), Some(e.to_string())),
+        };
+
+        let filter = ListFilter {
+            view: self.view,
+            search: self.q.clone(),
+            tag: self.tag.clone(),
+            color: self.color.as_deref().and_then(ColorFilter::new),
+            unworn_days: self.unworn_days,
+            query,
+            archived: match self.archived {
+                Some(true) => Archived::Only,
+                _ => Archived::Hidden,
+            },
+            deleted: false,
+            wishlist: false,
+        };
+        (filter, error)
+    }
+
+    /// Link to another page of the same listing.
+    fn page_link(&self, page: usize) -> String {
+        match self.query() {
+            q if q.is_empty() => format!("/?page={}", page),
+            q => format!("/?{}&page={}", q, page),
+        }
+    }
+}
+
+/// An item submitted from the item form, along with any photos and custom field values.
+type ItemForm = (Item, Vec<attachments::Upload>, Vec<(usize, String)>);
+
+/// The new and edit forms, sent plain or as multipart when a photo is attached.
+fn item_form() -> impl Filter<Extract = (ItemForm,), Error = warp::Rejection> + Clone {
+    let plain = warp::body::content_length_limit(1024 * 32)
+        .and(warp::body::form())
+        .and_then(|mut fields: Vec<(String, String)>| async move {
+            weather::join_seasons(&mut fields);
+            // the fields are read as pairs first so custom fields, which aren't part of an
+            // item, can be picked out
+            let item = serde_urlencoded::to_string(&fields)
+                .ok()
+                .and_then(|body| serde_urlencoded::from_str(&body).ok())
+                .ok_or_else(warp::reject::not_found)?;
+            Ok::<_, warp::Rejection>((item, Vec::new(), custom::from_form(&fields)))
+        });
+
+    let multipart = warp::multipart::form()
+        .max_length(media::MAX_FILE_SIZE + 1024 * 32)
+        .and_then(|form| async move {
+            let fail = |e: anyhow::Error| {
+                eprintln!("reading item form: {}", e);
+                warp::reject::not_found()
+            };
+
+            let (mut fields, uploads) = attachments::read_form(form)
+                .await
+                .map_err(|e| fail(e.into()))?;
+            weather::join_seasons(&mut fields);
+            let custom = custom::from_form(&fields);
+            let fields = fields
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect();
+            let item = serde_json::from_value::<Item>(serde_json::Value::Object(fields))
+                .map_err(|e| fail(e.into()))?;
+
+            let photos = uploads
+                .into_iter()
+                .filter(|u| u.field == "photo")
+                .collect::<Vec<_>>();
+            if let Some(other) = photos
+                .iter()
+                .find(|u| !u.content_type.starts_with("image/"))
+            {
+                return Err(fail(anyhow::anyhow!("{} is not an image", other.file_name)));
+            }
+
+            Ok((item, photos, custom))
+        });
+
+    plain.or(multipart).unify()
+}
+
+/// Sort keys as they're written in a query string.
+fn sort_param(sort: &[SortItems]) -> String {
+    sort.iter()
+        .map(|s| json!(s).as_str().unwrap_or_default().to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Default, Deserialize)]
+struct WelcomeOpts {
+    step: Option<u8>,
+    /// Categories named in the first step, separated by commas
+    categories: Option<String>,
+}
+
+/// Rows offered for adding the first few items.
+const WELCOME_ROWS: usize = 5;
+
+/// The first-run wizard: name some categories, add a few items, then maybe import the rest.
+fn welcome_page(opts: WelcomeOpts) -> WithTemplate<serde_json::Value> {
+    let categories = opts
+        .categories
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+
+    WithTemplate {
+        name: "welcome",
+        value: json!({
+            "step": opts.step.unwrap_or(1).clamp(1, 3),
+            "categories": categories,
+            "categoriesParam": categories.join(", "),
+            "rows": vec![(); WELCOME_ROWS],
+        }),
+    }
+}
+
+/// Add the items from the wizard's second step. Each row's fields arrive in order, so a name
+/// starts a new row; rows without one are skipped.
+async fn add_first_items(
+    form: Vec<(String, String)>,
+    conn: Connection,
+) -> Result<(), warp::Rejection> {
+    let mut rows: Vec<Item> = Vec::new();
+    for (key, value) in form {
+        let value = value.trim().to_string();
+        match (key.as_str(), rows.last_mut()) {
+            ("name", _) => rows.push(Item {
+                name: value,
+                color: utils::default_color(),
+                ..Item::default()
+            }),
+            ("category", Some(item)) if !value.is_empty() => item.tags = vec![value.to_lowercase()],
+            ("color", Some(item)) if !value.is_empty() => item.color = value,
+            _ => (),
+        }
+    }
+
+    for item in rows.into_iter().filter(|i| !i.name.is_empty()) {
+        conn.new_item(item).await.map_err(|e| {
+            eprintln!("adding first items: {}", e);
+            warp::reject::not_found()
+        })?;
+    }
+
+    Ok(())
+}
+
+async fn home_page(
+    params: IndexOpts,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let per_page = params.per_page.unwrap_or(PER_PAGE).clamp(1, 500);
+    let page = params.page.unwrap_or(1).max(1);
+
+    // show everything rather than nothing if the filter doesn't make sense, but say why
+    let (filter, filter_error) = params.filter();
+    let (items, total) = match conn
+        .get_view_page(
+            &filter,
+            &params.sort,
+            params.descending != Some(true),
+            params.nulls.unwrap_or(Nulls::Last),
+            per_page,
+            (page - 1) * per_page,
+        )
+        .await
+    {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("request for index: could not retrieve collection: {}", e);
+            (Vec::new(), 0)
+        }
+    };
+    let weekly_wears = conn
+        .get_weekly_wears(None, Utc::now(), history::SPARKLINE_WEEKS)
+        .await
+        .map(|counts| history::weekly(&counts, history::SPARKLINE_WEEKS))
+        .unwrap_or_else(|e| {
+            eprintln!("request for index: could not retrieve weekly wears: {}", e);
+            HashMap::new()
+        });
+    let today = Local::today().naive_local();
+    let worn_today = conn
+        .get_daily_wears(None, today, today)
+        .await
+        .map(|days| {
+            days.into_iter()
+                .map(|(id, _, wears)| (id as usize, wears.max(0) as u32))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("request for index: could not retrieve today's wears: {}", e);
+            HashMap::new()
+        });
+    let settings = conn.get_settings().await.unwrap_or_else(|e| {
+        eprintln!("request for index: could not retrieve settings: {}", e);
+        Settings::default()
+    });
+    let with_photos = conn.get_items_with_photos().await.unwrap_or_else(|e| {
+        eprintln!("request for index: could not retrieve photos: {}", e);
+        Vec::new()
+    });
+    let items = items
+        .iter()
+        .map(
+            |item @ Item {
+                 id,
+                 name,
+                 description,
+                 count,
+                 total_count,
+                 last_wear,
+                 last_wash,
+                 color,
+                 tags,
+                 ..
+             }| context::IndexItem {
+                key: *id,
+                name: name.clone(),
+                description: description.clone(),
+                count: *count,
+                total_count: *total_count,
+                has_wear: last_wear.is_some(),
+                wear: *last_wear,
+                wear_fmt: last_wear.map(|t| settings.time(t)),
+                has_wash: last_wash.is_some(),
+                wash: *last_wash,
+                wash_fmt: last_wash.map(|t| settings.time(t)),
+                color: color.clone(),
+                color_param: utils::encode_query(color),
+                tags: tags.join(", "),
+                tag_links: tags
+                    .iter()
+                    .map(|t| t.trim())
+                    .filter(|t| !t.is_empty())
+                    .map(|t| context::TagLink {
+                        name: t.to_string(),
+                        param: utils::encode_query(t),
+                    })
+                    .collect(),
+                weekly_wears: weekly_wears.get(id).cloned(),
+                worn_today: worn_today.get(id).copied().filter(|n| *n > 1),
+                has_photo: with_photos.contains(&(*id as i32,)),
+                cost_per_wear: item
+                    .cost_per_wear(&settings.currency)
+                    .filter(|_| item.tracking == Tracking::Wears)
+                    .map(|c| settings.money_in(c, &item.currency)),
+                hours: Some(item.total_hours)
+                    .filter(|_| item.tracking == Tracking::Hours)
+                    .map(|h| Metric::display(Metric::Hours.as_str(), h)),
+                cost_per_hour: item
+                    .cost_per_hour()
+                    .map(|c| settings.money_in(c, &item.currency)),
+                quantity: Some(item.quantity).filter(|n| *n > 1),
+                wears_each: item.wears_each(),
+                needs_wash: item.needs_wash(),
+                condition: item.condition,
+                condition_label: item.condition.map(Condition::label),
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let saved_views = conn.get_saved_views().await.unwrap_or_else(|e| {
+        eprintln!("request for index: could not retrieve saved views: {}", e);
+        Vec::new()
+    });
+    let updated_at = conn.last_change().await.unwrap_or_else(|e| {
+        eprintln!("request for index: could not find the last change: {}", e);
+        None
+    });
+    let current_query = params.query();
+    let current_view = saved_views
+        .iter()
+        .find(|(_, _, query, _)| *query == current_query)
+        .map(|(id, name, _, alert)| context::CurrentView {
+            id: *id,
+            name: name.clone(),
+            alert: *alert,
+        });
+
+    let filtered = params.view.is_some()
+        || [&params.q, &params.tag, &params.color, &params.filter]
+            .iter()
+            .any(|p| p.is_some())
+        || params.unworn_days.is_some()
+        || filter.archived == Archived::Only;
+    // nothing to list yet, so help get started instead
+    if total == 0 && !filtered && page == 1 && filter_error.is_none() {
+        return Ok(welcome_page(WelcomeOpts::default()));
+    }
+
+    let pages = total.div_ceil(per_page);
+    let nulls_links = [Nulls::First, Nulls::Last]
+        .iter()
+        .map(|&nulls| {
+            let other = IndexOpts {
+                nulls: Some(nulls),
+                ..params.clone()
+            };
+            other.page_link(1)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(WithTemplate::with_context(
+        "index",
+        context::Index {
+            items,
+            num_items: total,
+            view: params.view,
+            q: params.q.clone(),
+            q_param: params.q.as_deref().map(utils::encode_query),
+            tag: params.tag.clone(),
+            tag_param: params.tag.as_deref().map(utils::encode_query),
+            color: params.color.clone(),
+            color_param: params.color.as_deref().map(utils::encode_query),
+            color_families: colors::FAMILIES,
+            unworn_days: params.unworn_days,
+            archived: filter.archived == Archived::Only,
+            filter: params.filter.clone(),
+            filter_param: params.filter.as_deref().map(utils::encode_query),
+            filter_error,
+            filtered,
+            exact_color: match filter.color {
+                Some(ColorFilter::Exact(hex)) => Some(hex),
+                _ => None,
+            },
+            sort: params.sort.first().cloned(),
+            then_by: params
+                .sort
+                .get(1..)
+                .filter(|rest| !rest.is_empty())
+                .map(|rest| format!(",{}", sort_param(rest))),
+            descending: params.descending,
+            nulls: params.nulls,
+            nulls_first: params.nulls == Some(Nulls::First),
+            nulls_links,
+            page,
+            pages,
+            prev: if page > 1 {
+                Some(params.page_link(page - 1))
+            } else {
+                None
+            },
+            next: if page < pages {
+                Some(params.page_link(page + 1))
+            } else {
+                None
+            },
+            form_key: idempotency::new_key(),
+            saved_views: saved_views
+                .iter()
+                .map(|(id, name, query, _)| context::ViewLink {
+                    id: *id,
+                    name: name.clone(),
+                    link: format!("/?{}", query),
+                    current: *query == current_query,
+                })
+                .collect(),
+            current_view,
+            current_query,
+            updated_at,
+            updated_fmt: updated_at.map(|t| settings.time(t)),
+        },
+    ))
+}
+
+async fn quick_page(conn: Connection) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let items = conn.get_all(&[SortItems::Wear], true).await.map_err(|e| {
+        eprintln!(
+            "request for quick log: could not retrieve collection: {}",
+            e
+        );
+        warp::reject::not_found()
+    })?;
+
+    Ok(WithTemplate {
+        name: "quick",
+        value: json!({
+            "items": items
+                .iter()
+                .filter(|i| !i.token.is_empty())
+                .map(|Item { name, color, token, .. }| json!({
+                    "name": name,
+                    "color": color,
+                    "token": token,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// Options shared by reports over the whole collection.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub(crate) struct ReportOpts {
+    /// Count archived items too, rather than only current ones
+    #[serde(default)]
+    include_archived: bool,
+}
+
+impl ReportOpts {
+    fn archived(self) -> Archived {
+        Archived::included(self.include_archived)
+    }
+
+    /// Reports are cached separately with and without archived items.
+    fn cache_key(self, name: &'static str, with_archived: &'static str) -> &'static str {
+        if self.include_archived {
+            with_archived
+        } else {
+            name
+        }
+    }
+}
+
+/// Wear history with anything identifying stripped out, cached since it touches every wear.
+async fn anonymized_export(
+    conn: &Connection,
+    reports: &Reports,
+    opts: ReportOpts,
+) -> anyhow::Result<serde_json::Value> {
+    let filter = ListFilter {
+        archived: opts.archived(),
+        ..ListFilter::default()
+    };
+
+    reports
+        .get(
+            opts.cache_key("anonymized export", "anonymized export with archived"),
+            async {
+                let items = conn.get_view(&filter, &[], true, Nulls::Last).await?;
+                let wears = conn.get_wear_events().await?;
+                let summaries = conn.get_wear_summaries().await?;
+                Ok(export::anonymized(&items, &wears, &summaries))
+            },
+        )
+        .await
+}
+
+async fn handle_anonymized_export(
+    opts: ReportOpts,
+    conn: Connection,
+    reports: Reports,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let export = anonymized_export(&conn, &reports, opts)
+        .await
+        .map_err(|e| {
+            eprintln!("request for anonymized export: {}", e);
+            warp::reject::not_found()
+        })?;
+
+    Ok(warp::reply::with_header(
+        warp::reply::json(&export),
+        "Content-Disposition",
+        "attachment; filename=\"wear-anonymized.json\"",
+    ))
+}
+
+async fn spending_page(
+    opts: ReportOpts,
+    conn: Connection,
+    reports: Reports,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for spending report: {}", e);
+        warp::reject::not_found()
+    };
+
+    let value = reports
+        .get::<anyhow::Error>(
+            opts.cache_key("spending", "spending with archived"),
+            async {
+                let settings = conn.get_settings().await?;
+                let years = conn.get_annual_cleaning_spend(opts.archived()).await?;
+                let top_items = match years.first() {
+                    Some((year, _, _)) => {
+                        conn.get_cleaning_spend_by_item(year, opts.archived())
+                            .await?
+                    }
+                    None => Vec::new(),
+                };
+
+                // cheapest to wear first; prices in different currencies aren't compared
+                let filter = ListFilter {
+                    archived: opts.archived(),
+                    ..ListFilter::default()
+                };
+                let mut worn = conn
+                    .get_view(&filter, &[SortItems::Name], true, Nulls::Last)
+                    .await?
+                    .into_iter()
+                    .filter_map(|item| Some((item.cost_per_wear(&settings.currency)?, item)))
+                    .collect::<Vec<_>>();
+                worn.sort_by(|(a, x), (b, y)| x.currency.cmp(&y.currency).then(a.total_cmp(b)));
+
+                Ok(json!({
+                    "includeArchived": opts.include_archived,
+                    "latestYear": years.first().map(|(year, _, _)| year),
+                    "years": years
+                        .iter()
+                        .map(|(year, total, cleanings)| json!({
+                            "year": year,
+                            "cleaning": settings.money(*total),
+                            "cleanings": cleanings,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "topItems": top_items
+                        .iter()
+                        .map(|(id, name, total)| json!({
+                            "key": id,
+                            "name": name,
+                            "cleaning": settings.money(*total),
+                        }))
+                        .collect::<Vec<_>>(),
+                    "costPerWear": worn
+                        .iter()
+                        .map(|(cost, item)| json!({
+                            "key": item.id,
+                            "name": item.name,
+                            "archived": item.archived_at.is_some(),
+                            "price": item.price.map(|p| settings.money_in(p, &item.currency)),
+                            "wears": item.total_count,
+                            "costPerWear": settings.money_in(*cost, &item.currency),
+                        }))
+                        .collect::<Vec<_>>(),
+                }))
+            },
+        )
+        .await
+        .map_err(fail)?;
+
+    Ok(WithTemplate {
+        name: "spending",
+        value,
+    })
+}
+
+/// The days to compare the wardrobe between.
+#[derive(Deserialize)]
+struct ChangesOpts {
+    #[serde(default, deserialize_with = "utils::optional")]
+    from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    to: Option<NaiveDate>,
+}
+
+async fn changes_page(
+    opts: ChangesOpts,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for changes report: {}", e);
+        warp::reject::not_found()
+    };
+
+    let period = changes::Period::new(opts.from, opts.to, Local::today().naive_local());
+    let (start, end) = period.bounds();
+
+    let counts = (
+        conn.count_items_at(start).await.map_err(fail)?,
+        conn.count_items_at(end).await.map_err(fail)?,
+    );
+    let added = conn
+        .get_items_changed_between(start, end, false)
+        .await
+        .map_err(fail)?;
+    let retired = conn
+        .get_items_changed_between(start, end, true)
+        .await
+        .map_err(fail)?;
+    let worn = conn.get_wears_between(start, end).await.map_err(fail)?;
+
+    Ok(WithTemplate {
+        name: "changes",
+        value: json!({
+            "from": period.from,
+            "to": period.to,
+            "changes": changes::compare(counts, added, retired, worn),
+        }),
+    })
+}
+
+/// What the wardrobe would look like without the items checked on the page.
+async fn declutter_page(
+    query: Vec<(String, String)>,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for declutter report: {}", e);
+        warp::reject::not_found()
+    };
+
+    let removed = query
+        .iter()
+        .filter(|(k, _)| k == "item")
+        .filter_map(|(_, v)| v.parse().ok())
+        .collect::<Vec<usize>>();
+
+    let settings = conn.get_settings().await.map_err(fail)?;
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let now = Utc::now();
+    let recent = conn
+        .get_wears_between(now - chrono::Duration::days(declutter::RECENT_DAYS), now)
+        .await
+        .map_err(fail)?
+        .into_iter()
+        .map(|(id, _, wears)| (id as usize, wears.max(0) as u32))
+        .collect::<HashMap<_, _>>();
+
+    let comparison = declutter::compare(&items, &recent, &removed, &settings.currency);
+    let row = |label: &str, now: String, after: String, change: String| json!({ "label": label, "now": now, "after": after, "change": change });
+    let count = |label, now: usize, after: usize| {
+        row(
+            label,
+            now.to_string(),
+            after.to_string(),
+            format!("{:+}", after as i64 - now as i64),
+        )
+    };
+    let (before, after) = (&comparison.now, &comparison.after);
+
+    Ok(WithTemplate {
+        name: "declutter",
+        value: json!({
+            "recentDays": declutter::RECENT_DAYS,
+            "items": items.iter().map(|item| json!({
+                "key": item.id,
+                "name": item.name,
+                "recentWears": recent.get(&item.id).copied().unwrap_or_default(),
+                "checked": removed.contains(&item.id),
+            })).collect::<Vec<_>>(),
+            "chosen": !removed.is_empty(),
+            "rows": [
+                count("Items", before.items, after.items),
+                count("Categories", before.categories, after.categories),
+                count("Color families", before.color_families, after.color_families),
+                count("Recent wears", before.recent_wears as usize, after.recent_wears as usize),
+                count("Not worn recently", before.unworn, after.unworn),
+                row(
+                    "Value",
+                    settings.money(before.value),
+                    settings.money(after.value),
+                    settings.money(after.value - before.value),
+                ),
+            ],
+            "emptied": comparison.emptied,
+            "wearShare": comparison.wear_share,
+        }),
+    })
+}
+
+/// Which month of the calendar to show.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct CalendarOpts {
+    /// Given as `YYYY-MM`; this month if left out
+    #[serde(default, deserialize_with = "calendar::deserialize_month")]
+    month: Option<NaiveDate>,
+}
+
+/// A month of the calendar, with what was worn and what's planned each day.
+pub(crate) async fn calendar_month(
+    conn: &Connection,
+    opts: &CalendarOpts,
+) -> anyhow::Result<calendar::Month> {
+    let today = Local::today().naive_local();
+    let first = opts
+        .month
+        .unwrap_or_else(|| today.with_day(1).unwrap_or(today));
+    let (from, to) = calendar::bounds(first).context("Month out of range")?;
+    let planned = conn
+        .get_planned_wears(from, to)
+        .await?
+        .into_iter()
+        .filter_map(|(id, day, item_id, outfit_id, name, note)| {
+            let day = day.parse::<NaiveDate>().ok()?;
+            Some((
+                day,
+                calendar::Planned {
+                    id: id as usize,
+                    item_id: item_id.map(|id| id as usize),
+                    outfit_id: outfit_id.map(|id| id as usize),
+                    name,
+                    note,
+                },
+            ))
+        })
+        .collect();
+    let worn = conn
+        .get_worn_by_day(from, to)
+        .await?
+        .into_iter()
+        .filter_map(|(day, id, name, color, wears)| {
+            let day = day.parse::<NaiveDate>().ok()?;
+            Some((
+                day,
+                calendar::Worn {
+                    id: id as usize,
+                    name,
+                    color,
+                    wears: wears as u32,
+                },
+            ))
+        })
+        .collect();
+
+    calendar::month(first, today, worn, planned).context("Month out of range")
+}
+
+/// The calendar, with a form to plan what to wear on a day to come.
+async fn calendar_page(
+    opts: CalendarOpts,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e: anyhow::Error| {
+        eprintln!("request for calendar: {}", e);
+        warp::reject::not_found()
+    };
+
+    let month = calendar_month(&conn, &opts).await.map_err(fail)?;
+    let items = conn
+        .get_all(&[SortItems::Name], true)
+        .await
+        .map_err(|e| fail(e.into()))?;
+    let outfits = conn.get_outfits().await.map_err(|e| fail(e.into()))?;
+
+    let mut value = json!(month);
+    value["today"] = json!(Local::today().naive_local());
+    value["items"] = items
+        .iter()
+        .map(|i| json!({ "key": i.id, "name": i.name }))
+        .collect();
+    value["outfits"] = outfits
+        .iter()
+        .map(|o| json!({ "id": o.id, "name": o.name }))
+        .collect();
+
+    Ok(WithTemplate {
+        name: "calendar",
+        value,
+    })
+}
+
+#[derive(Deserialize)]
+struct SuggestOpts {
+    /// Today's temperature in °C, instead of the forecast
+    #[serde(default, deserialize_with = "utils::optional")]
+    temp: Option<f64>,
+}
+
+/// What to wear today: items that suit the forecast, or a temperature given by hand, and the
+/// season, least recently worn first.
+async fn suggest_page(
+    opts: SuggestOpts,
+    conn: Connection,
+    weather: Weather,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e: sqlx::Error| {
+        eprintln!("request for suggestions: {}", e);
+        warp::reject::not_found()
+    };
+
+    let today = Local::today().naive_local();
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let settings = conn.get_settings().await.map_err(fail)?;
+
+    // a failed forecast still leaves suggestions for the season
+    let (forecast, error) = match opts.temp {
+        Some(temp) => (Some(weather::Forecast::constant(temp)), None),
+        None => match weather.today(today).await {
+            Ok(forecast) => (forecast, None),
+            Err(e) => {
+                eprintln!("getting the forecast: {}", e);
+                (None, Some(e.to_string()))
+            }
+        },
+    };
+    let season = weather.season(today);
+    let mut groups = weather::suggest(items.iter(), forecast.as_ref(), season);
+    for pick in groups.iter_mut().flat_map(|g| g.items.iter_mut()) {
+        pick.last_wear_fmt = pick.last_wear.map(|t| settings.time(t));
+    }
+
+    Ok(WithTemplate {
+        name: "suggest",
+        value: json!({
+            "season": season.as_str(),
+            "forecast": forecast,
+            "byHand": opts.temp.is_some(),
+            "warmths": forecast
+                .as_ref()
+                .map(|f| f.warmths().into_iter().map(Warmth::as_str).collect::<Vec<_>>()),
+            "groups": groups,
+            "configured": weather.configured(),
+            "error": error,
+            "temp": opts.temp,
+        }),
+    })
+}
+
+/// An item or outfit to wear on a day, chosen as `item:<id>` or `outfit:<id>`.
+#[derive(Deserialize)]
+struct PlanForm {
+    date: NaiveDate,
+    what: String,
+    #[serde(default)]
+    note: String,
+}
+
+/// Put an item or outfit on the calendar for today or a day to come.
+async fn plan_wear(form: PlanForm, conn: Connection) -> Result<impl warp::Reply, warp::Rejection> {
+    let fail = |e: anyhow::Error| {
+        eprintln!("planning a wear: {}", e);
+        warp::reject::not_found()
+    };
+
+    if form.date < Local::today().naive_local() {
+        return Err(fail(anyhow::anyhow!("{} has already passed", form.date)));
+    }
+    let (item_id, outfit_id) = match form
+        .what
+        .split_once(':')
+        .and_then(|(kind, id)| Some((kind, id.parse::<usize>().ok()?)))
+    {
+        Some(("item", id)) => (Some(id), None),
+        Some(("outfit", id)) => (None, Some(id)),
+        _ => return Err(fail(anyhow::anyhow!("Nothing to wear in {:?}", form.what))),
+    };
+
+    conn.add_planned_wear(form.date, item_id, outfit_id, &form.note)
+        .await
+        .map_err(|e| fail(e.into()))?;
+
+    Ok(utils::go_to(format!(
+        "/calendar?month={}",
+        form.date.format("%Y-%m")
+    )))
+}
+
+async fn laundry_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for laundry page: {}", e);
+        warp::reject::not_found()
+    };
+
+    let today = Local::today().naive_local();
+    let schedule = conn.get_laundry_schedule().await.map_err(fail)?;
+    let done = conn
+        .get_laundry_days(today - chrono::Duration::days(28))
+        .await
+        .map_err(fail)?;
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let (in_machine, machine_finished) = conn.get_machine_load().await.map_err(fail)?;
+
+    let done_days = done.iter().map(|(d, _)| *d).collect::<Vec<_>>();
+    let upcoming = laundry::upcoming(&schedule, today, &done_days);
+
+    Ok(WithTemplate {
+        name: "laundry",
+        value: json!({
+            "week": laundry::WEEK
+                .iter()
+                .map(|d| json!({
+                    "value": d.to_string().to_lowercase(),
+                    "label": d.to_string(),
+                    "selected": schedule.contains(d),
+                }))
+                .collect::<Vec<_>>(),
+            "upcoming": upcoming,
+            "upcomingFmt": upcoming.map(|d| match (d - today).num_days() {
+                0 => "Today".to_string(),
+                1 => "Tomorrow".to_string(),
+                _ => d.format("%A, %B %e").to_string(),
+            }),
+            "today": today,
+            "loads": laundry::suggest_loads(&items),
+            "machine": {
+                "items": in_machine
+                    .iter()
+                    .map(|(id, name)| json!({ "key": id, "name": name }))
+                    .collect::<Vec<_>>(),
+                "finished": machine_finished.map(|f| f.with_timezone(&Local).format("%H:%M").to_string()),
+            },
+            "dirty": items
+                .iter()
+                .filter(|i| i.needs_wash())
+                .map(|i| json!({ "key": i.id, "name": i.name, "count": i.count, "care": care::symbols(i) }))
+                .collect::<Vec<_>>(),
+            "professional": items
+                .iter()
+                .filter(|i| i.count > 0 && laundry::wash_details(i).is_none())
+                .map(|i| json!({ "key": i.id, "name": i.name, "care": care::symbols(i) }))
+                .collect::<Vec<_>>(),
+            "done": done
+                .iter()
+                .map(|(day, washed)| json!({ "day": day, "washed": washed }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+async fn laundry_plan_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for laundry plan: {}", e);
+        warp::reject::not_found()
+    };
+
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let (in_machine, _) = conn.get_machine_load().await.map_err(fail)?;
+    let dirty = items.iter().filter(|i| i.needs_wash());
+
+    Ok(WithTemplate {
+        name: "laundry-plan",
+        value: json!({
+            "loads": laundry::plan(dirty.clone()),
+            "professional": dirty
+                .filter(|i| laundry::wash_details(i).is_none())
+                .map(|i| json!({ "key": i.id, "name": i.name, "care": care::symbols(i) }))
+                .collect::<Vec<_>>(),
+            "machineBusy": !in_machine.is_empty(),
+        }),
+    })
+}
+
+async fn finish_laundry_day(
+    form: Vec<(String, String)>,
+    conn: Connection,
+) -> Result<usize, warp::Rejection> {
+    let day = form
+        .iter()
+        .find(|(k, _)| k == "day")
+        .and_then(|(_, v)| v.parse::<NaiveDate>().ok())
+        .unwrap_or_else(|| Local::today().naive_local());
+
+    let ids = form
+        .iter()
+        .filter(|(k, _)| k == "item")
+        .filter_map(|(_, v)| v.parse::<usize>().ok())
+        .collect::<Vec<_>>();
+    let washed = wash_items(&conn, &ids).await;
+
+    conn.complete_laundry_day(day, washed).await.map_err(|e| {
+        eprintln!("{}", e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(washed)
+}
+
+/// Log a wash for each item, going by its care label, and return how many were logged. Items that
+/// can't be found are skipped; the rest are logged together or not at all.
+async fn wash_items(conn: &Connection, ids: &[usize]) -> usize {
+    let filter = ListFilter {
+        archived: Archived::Included,
+        ..ListFilter::default()
+    };
+    let items = match conn.get_view(&filter, &[], true, Nulls::Last).await {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("laundry: could not retrieve items: {}", e);
+            return 0;
+        }
+    };
+
+    let washes = items
+        .iter()
+        .filter(|item| ids.contains(&item.id))
+        .map(|item| (item.id, laundry::wash_details(item).unwrap_or_default()))
+        .collect::<Vec<_>>();
+    let washes = washes.iter().map(|(id, d)| (*id, d)).collect::<Vec<_>>();
+    match conn.log_wash_many(&washes).await {
+        Ok(washed) => washed as usize,
+        Err(e) => {
+            eprintln!("laundry: could not log washes: {}", e);
+            0
+        }
+    }
+}
+
+/// Items with measurements, optionally narrowed down to those close to the given sizes.
+async fn measurements_page(
+    params: HashMap<String, String>,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for measurements: {}", e);
+        warp::reject::not_found()
+    };
+
+    let settings = conn.get_settings().await.map_err(fail)?;
+    let units = settings.units;
+    let number = |key: &str| {
+        params
+            .get(key)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v >= 0.0)
+    };
+
+    let tolerance = number("tolerance").unwrap_or(2.0);
+    let targets = measurements::Measurement::ALL
+        .iter()
+        .filter_map(|m| number(m.key()).map(|v| (*m, v)))
+        .collect::<Vec<_>>();
+    let ranges = targets
+        .iter()
+        .map(|(m, v)| (*m, units.to_cm(v - tolerance), units.to_cm(v + tolerance)))
+        .collect::<Vec<_>>();
+
+    let items = conn.get_by_measurements(&ranges).await.map_err(fail)?;
+
+    Ok(WithTemplate {
+        name: "measurements",
+        value: json!({
+            "units": units.label(),
+            "tolerance": tolerance,
+            "filtered": !targets.is_empty(),
+            "fields": measurements::Measurement::ALL
+                .iter()
+                .map(|m| json!({
+                    "field": m.key(),
+                    "label": m.label(),
+                    "value": targets.iter().find(|(t, _)| t == m).map(|(_, v)| v),
+                }))
+                .collect::<Vec<_>>(),
+            "items": items
+                .iter()
+                .map(|i| json!({
+                    "key": i.id,
+                    "name": i.name,
+                    "values": measurements::Measurement::ALL
+                        .iter()
+                        .map(|m| m.get(i).map(|cm| measurements::display(cm, units)))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+async fn todo_page(conn: Connection) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let tasks = conn.get_all_tasks().await.map_err(|e| {
+        eprintln!("request for to-do list: could not retrieve tasks: {}", e);
+        warp::reject::not_found()
+    })?;
+
+    let groups = Task::ALL
+        .iter()
+        .map(|task| {
+            json!({
+                "label": task.label(),
+                "items": tasks
+                    .iter()
+                    .filter(|(_, _, t, _, _)| t == task.as_str())
+                    .map(|(id, name, t, note, flagged_at)| json!({
+                        "key": id,
+                        "name": name,
+                        "task": t,
+                        "note": note,
+                        "flagged": flagged_at
+                            .parse::<DateTime<Utc>>()
+                            .map(|t| t.date().naive_utc())
+                            .ok(),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(WithTemplate {
+        name: "todo",
+        value: json!({ "empty": tasks.is_empty(), "groups": groups }),
+    })
+}
+
+/// Items that have been moved to the trash, which can be restored or deleted for good.
+async fn trash_page(conn: Connection) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let filter = ListFilter {
+        archived: Archived::Included,
+        deleted: true,
+        ..ListFilter::default()
+    };
+    let items = conn
+        .get_view(&filter, &[SortItems::Name], true, Nulls::Last)
+        .await
+        .map_err(|e| {
+            eprintln!("request for trash: could not retrieve collection: {}", e);
+            warp::reject::not_found()
+        })?;
+
+    Ok(WithTemplate {
+        name: "trash",
+        value: json!({
+            "items": items
+                .iter()
+                .map(|item| json!({
+                    "key": item.id,
+                    "name": item.name,
+                    "deleted": item.deleted_at.map(|t| t.date().naive_utc()),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// Items wanted but not bought yet, with a form to add another.
+async fn wishlist_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let filter = ListFilter {
+        wishlist: true,
+        ..ListFilter::default()
+    };
+    let items = conn
+        .get_view(&filter, &[SortItems::Name], true, Nulls::Last)
+        .await
+        .map_err(|e| {
+            eprintln!("request for wishlist: could not retrieve collection: {}", e);
+            warp::reject::not_found()
+        })?;
+    let settings = conn.get_settings().await.unwrap_or_else(|e| {
+        eprintln!("request for wishlist: could not retrieve settings: {}", e);
+        Settings::default()
+    });
+
+    Ok(WithTemplate {
+        name: "wishlist",
+        value: json!({
+            "items": items
+                .iter()
+                .map(|item| json!({
+                    "key": item.id,
+                    "name": item.name,
+                    "color": item.color,
+                    "retailer": item.retailer,
+                    "productUrl": item.product_url,
+                    "price": item.price.map(|p| settings.money_in(p, &item.currency)),
+                    "since": item.wishlisted_at.map(|t| t.date().naive_utc()),
+                }))
+                .collect::<Vec<_>>(),
+            "defaultCurrency": settings.currency,
+        }),
+    })
+}
+
+async fn add_to_wishlist(item: Item, conn: Connection) -> Result<(), warp::Rejection> {
+    let fail = |e: anyhow::Error| {
+        eprintln!("adding to wishlist: {}", e);
+        warp::reject::not_found()
+    };
+
+    item.validate().map_err(fail)?;
+    let id = conn.new_item(item).await.map_err(|e| fail(e.into()))?;
+    conn.wishlist_item(id).await.map_err(|e| fail(e.into()))?;
+    Ok(())
+}
+
+/// Delete an item in the trash for good, along with its attachments.
+async fn delete_item(id: usize, conn: Connection, media: Media) -> Result<(), warp::Rejection> {
+    let fail = |e| {
+        eprintln!("deleting item {}: {}", id, e);
+        warp::reject::not_found()
+    };
+
+    // one click shouldn't lose anything, so only what's already in the trash goes
+    let item = conn.get_item(id).await.map_err(fail)?;
+    if item.deleted_at.is_none() {
+        eprintln!("deleting item {}: not in the trash", id);
+        return Err(warp::reject::not_found());
+    }
+
+    attachments::remove_all(id, &conn, &media).await;
+    conn.delete_item(id).await.map_err(fail)?;
+    Ok(())
+}
+
+async fn deadlines_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let today = Local::today().naive_local();
+    let items = conn.get_with_deadlines(today).await.map_err(|e| {
+        eprintln!(
+            "request for deadlines: could not retrieve collection: {}",
+            e
+        );
+        warp::reject::not_found()
+    })?;
+
+    Ok(WithTemplate {
+        name: "deadlines",
+        value: json!({ "deadlines": deadlines::upcoming(&items, today) }),
+    })
+}
+
+async fn lifespan_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let today = Local::today().naive_local();
+    let items = conn.get_with_lifespans().await.map_err(|e| {
+        eprintln!(
+            "request for lifespans: could not retrieve collection: {}",
+            e
+        );
+        warp::reject::not_found()
+    })?;
+
+    Ok(WithTemplate {
+        name: "lifespan",
+        value: json!({
+            "threshold": lifespan::NEARING_END,
+            "items": lifespan::nearing_end(&items, today),
+        }),
+    })
+}
+
+/// A rotation goal for one category; leaving out the number of wears removes it.
+#[derive(Deserialize)]
+struct GoalForm {
+    category: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    weekly_wears: Option<u32>,
+}
+
+/// A category's default wears between washes; leaving out the number removes it.
+#[derive(Deserialize)]
+struct LimitForm {
+    category: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    wears: Option<u32>,
+}
+
+/// A new custom field, or the id of one to remove.
+#[derive(Deserialize)]
+struct FieldForm {
+    #[serde(default)]
+    name: String,
+    kind: Option<custom::Kind>,
+    #[serde(default, deserialize_with = "utils::optional")]
+    remove: Option<usize>,
+}
+
+/// A name for the listing given in the query string.
+#[derive(Deserialize)]
+struct SavedViewForm {
+    name: String,
+}
+
+/// Store the listing as a saved view, or replace an existing one, and go to it.
+async fn save_view(
+    id: Option<usize>,
+    opts: IndexOpts,
+    form: SavedViewForm,
+    conn: Connection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let name = form.name.trim();
+    if name.is_empty() {
+        eprintln!("saving a view: no name given");
+        return Err(warp::reject::not_found());
+    }
+
+    let query = opts.query();
+    match id {
+        Some(id) => conn.update_saved_view(id, name, &query).await,
+        None => conn.add_saved_view(name, &query).await,
+    }
+    .map_err(|e| {
+        eprintln!("{}", e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(utils::go_to(format!("/?{}", query)))
+}
+
+/// Every outfit, with a button to wear each and a form for a new one.
+async fn outfits_page(
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for outfits: {}", e);
+        warp::reject::not_found()
+    };
+
+    let outfits = conn.get_outfits().await.map_err(fail)?;
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+
+    Ok(WithTemplate {
+        name: "outfits",
+        value: json!({
+            "outfits": outfits,
+            "items": outfit_choices(&items, &[]),
+            "formKey": idempotency::new_key(),
+        }),
+    })
+}
+
+/// One outfit, to wear, change or remove.
+async fn outfit_page(
+    id: usize,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for outfit {}: {}", id, e);
+        warp::reject::not_found()
+    };
+
+    let outfit = conn.get_outfit(id).await.map_err(fail)?;
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let members = outfit.items.iter().map(|i| i.key).collect::<Vec<_>>();
+
+    Ok(WithTemplate {
+        name: "outfit",
+        value: json!({
+            "items": outfit_choices(&items, &members),
+            "outfit": outfit,
+            "today": Local::today().naive_local(),
+            "formKey": idempotency::new_key(),
+        }),
+    })
+}
+
+/// Items that can go in an outfit, with the ones already in it checked.
+fn outfit_choices(items: &[Item], members: &[usize]) -> Vec<serde_json::Value> {
+    items
+        .iter()
+        .map(|i| {
+            json!({
+                "key": i.id,
+                "name": i.name,
+                "color": i.color,
+                "selected": members.contains(&i.id),
             })
         })
-        .map(utils::go_home);
+        .collect()
+}
 
-    index
-        .or(css)
-        .or(warp::path("item").and(
-            post_item
-                .or(new)
-                .or(edit_item)
-                .or(update_item)
-                .or(increment_item)
-                .or(reset_item)
-                .or(delete_item),
-        ))
-        .with(warp::log("wear"))
-        .boxed()
+/// Save a new outfit, or change an existing one, from a name and the items checked for it.
+async fn save_outfit(
+    id: Option<usize>,
+    form: Vec<(String, String)>,
+    conn: Connection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let name = form
+        .iter()
+        .find(|(k, _)| k == "name")
+        .map(|(_, v)| v.trim())
+        .unwrap_or_default();
+    if name.is_empty() {
+        eprintln!("saving an outfit: no name given");
+        return Err(warp::reject::not_found());
+    }
+
+    let ids = form
+        .iter()
+        .filter(|(k, _)| k == "item")
+        .filter_map(|(_, v)| v.parse::<usize>().ok())
+        .collect::<Vec<_>>();
+
+    let id = match id {
+        Some(id) => conn.update_outfit(id, name, &ids).await.map(|_| id),
+        None => conn.add_outfit(name, &ids).await,
+    }
+    .map_err(|e| {
+        eprintln!("{}", e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(utils::go_to(format!("/outfit/{}", id)))
 }
 
-#[derive(Deserialize, Serialize)]
-#[serde(rename_all = "kebab-case")]
-enum SortItems {
-    Name,
-    Count,
-    Wear,
-    Wash,
+/// Every trip, with a form to start a new one.
+async fn trips_page(conn: Connection) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let trips = conn.get_trips().await.map_err(|e| {
+        eprintln!("request for trips: {}", e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(WithTemplate {
+        name: "trips",
+        value: json!({
+            "trips": trips
+                .iter()
+                .map(|t| json!({
+                    "id": t.id,
+                    "name": t.name,
+                    "startsOn": t.starts_on,
+                    "endsOn": t.ends_on,
+                    "items": t.items.len(),
+                    "packed": t.packed(),
+                }))
+                .collect::<Vec<_>>(),
+            "today": Local::today().naive_local(),
+        }),
+    })
+}
+
+/// A trip's packing list, to check off, add to, or log wears from.
+async fn trip_page(
+    id: usize,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for trip {}: {}", id, e);
+        warp::reject::not_found()
+    };
+
+    let trip = conn.get_trip(id).await.map_err(fail)?;
+    let items = conn.get_all(&[SortItems::Name], true).await.map_err(fail)?;
+    let outfits = conn.get_outfits().await.map_err(fail)?;
+    let today = Local::today().naive_local();
+    let to_log = trips::days_to_log(trip.starts_on, trip.ends_on, trip.logged_through, today);
+
+    Ok(WithTemplate {
+        name: "trip",
+        value: json!({
+            "packed": trip.packed(),
+            "daysToLog": to_log.len(),
+            "choices": items
+                .iter()
+                .filter(|i| !trip.items.iter().any(|t| t.key == i.id))
+                .map(|i| json!({ "key": i.id, "name": i.name, "color": i.color }))
+                .collect::<Vec<_>>(),
+            "outfits": outfits
+                .iter()
+                .map(|o| json!({ "id": o.id, "name": o.name }))
+                .collect::<Vec<_>>(),
+            "trip": trip,
+        }),
+    })
 }
 
+/// A trip's name and dates, and whether to log wears for it.
 #[derive(Deserialize)]
-struct IndexOpts {
-    sort: Option<SortItems>,
-    descending: Option<bool>,
+struct TripForm {
+    name: String,
+    starts_on: NaiveDate,
+    ends_on: NaiveDate,
+    #[serde(default)]
+    log_wears: bool,
 }
 
-async fn home_page(
-    params: IndexOpts,
+/// Start a trip, or change an existing one, and go to it.
+async fn save_trip(
+    id: Option<usize>,
+    form: TripForm,
     conn: Connection,
-) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
-    let items = match conn
-        .get_all(&params.sort, params.descending != Some(true))
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let fail = |e: anyhow::Error| {
+        eprintln!("saving a trip: {}", e);
+        warp::reject::not_found()
+    };
+
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Err(fail(anyhow::anyhow!("no name given")));
+    }
+    if form.ends_on < form.starts_on {
+        return Err(fail(anyhow::anyhow!(
+            "ends on {}, before it starts on {}",
+            form.ends_on,
+            form.starts_on
+        )));
+    }
+
+    let id = match id {
+        Some(id) => conn
+            .update_trip(id, name, form.starts_on, form.ends_on, form.log_wears)
+            .await
+            .map(|_| id),
+        None => {
+            conn.add_trip(name, form.starts_on, form.ends_on, form.log_wears)
+                .await
+        }
+    }
+    .map_err(|e| fail(e.into()))?;
+
+    Ok(utils::go_to(format!("/trip/{}", id)))
+}
+
+/// Whether an item on a packing list has been packed.
+#[derive(Deserialize)]
+struct PackForm {
+    #[serde(default)]
+    packed: bool,
+}
+
+/// Everything a saved view's query string lists, for checking it without showing it. A filter
+/// that can't be understood is an error here, since leaving it out would match far too much.
+async fn view_matches(conn: &Connection, query: &str) -> anyhow::Result<Vec<Item>> {
+    let opts = serde_urlencoded::from_str::<IndexOpts>(query)?;
+    let (filter, error) = opts.filter();
+    if let Some(e) = error {
+        anyhow::bail!(e);
+    }
+
+    Ok(conn
+        .get_view(&filter, &opts.sort, true, Nulls::Last)
+        .await?)
+}
+
+/// Whether a saved view should send alerts.
+#[derive(Deserialize)]
+struct ViewAlertForm {
+    alert: bool,
+}
+
+/// Turn alerts for a saved view on or off, and go back to it.
+async fn set_view_alert(
+    id: usize,
+    form: ViewAlertForm,
+    conn: Connection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let fail = |e: anyhow::Error| {
+        eprintln!("setting alerts for saved view {}: {}", id, e);
+        warp::reject::not_found()
+    };
+
+    let query = conn.get_saved_view(id).await.map_err(|e| fail(e.into()))?;
+    let matching = view_matches(&conn, &query)
         .await
-    {
-        Ok(i) => i
-            .iter()
-            .map(
-                |Item {
-                     id,
-                     name,
-                     description,
-                     count,
-                     total_count,
-                     last_wear,
-                     last_wash,
-                     color,
-                     tags,
-                 }| {
-                    json!({
-                        "key": id,
-                        "name": name,
-                        "description": description,
-                        "count": count,
-                        "totalCount": total_count,
-                        "hasWear": last_wear.is_some(),
-                        "wear": last_wear,
-                        "wearFmt": last_wear.map(|t| (t - Utc::now()).humanize()),
-                        "hasWash": last_wash.is_some(),
-                        "wash": last_wash,
-                        "washFmt": last_wash.map(|t| (t - Utc::now()).humanize()),
-                        "color": color,
-                        "tags": tags.join(", "),
-                    })
-                },
-            )
-            .collect::<Vec<_>>(),
+        .map_err(fail)?
+        .iter()
+        .map(|i| i.id)
+        .collect::<Vec<_>>();
+    conn.set_view_alert(id, form.alert, &matching)
+        .await
+        .map_err(|e| fail(e.into()))?;
 
-        Err(e) => {
-            eprintln!("request for index: could not retrieve collection: {}", e);
+    Ok(utils::go_to(format!("/?{}", query)))
+}
+
+/// Wears and washes of one item in the order they happened.
+async fn item_history_page(
+    id: usize,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for history of item {}: {}", id, e);
+        warp::reject::not_found()
+    };
+    let item = conn.get_item(id).await.map_err(fail)?;
+    let events = conn.get_item_history(id).await.map_err(fail)?;
+
+    Ok(WithTemplate {
+        name: "history",
+        value: json!({
+            "key": id,
+            "name": item.name,
+            "typical": history::typical(&events).map(|t| json!({
+                "cycles": t.cycles,
+                "wears": t.wears.to_string(),
+                "days": t.days.to_string(),
+                "daysWorn": Some(t.days_worn).filter(|d| *d != t.wears).map(|d| d.to_string()),
+            })),
+            "events": events
+                .iter()
+                .map(|e| json!({
+                    "wash": e.kind == history::Kind::Wash,
+                    "at": e.at,
+                    "fmt": (e.at - Utc::now()).humanize(),
+                    "detail": e.detail,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// What edits have changed on an item, newest first, with the fields changed together kept
+/// together.
+async fn item_edits_page(
+    id: usize,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let fail = |e| {
+        eprintln!("request for changes to item {}: {}", id, e);
+        warp::reject::not_found()
+    };
+    let item = conn.get_item(id).await.map_err(fail)?;
+
+    let mut made: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+    for (at, edit) in conn.get_item_edits(id).await.map_err(fail)? {
+        let change = json!({
+            "field": edits::label(&edit.field),
+            "before": edit.before,
+            "after": edit.after,
+        });
+        match made.last_mut() {
+            Some((last, changes)) if *last == at => changes.push(change),
+            _ => made.push((at, vec![change])),
+        }
+    }
+
+    Ok(WithTemplate {
+        name: "edits",
+        value: json!({
+            "key": id,
+            "name": item.name,
+            "edits": made
+                .iter()
+                .map(|(at, changes)| json!({
+                    "at": at,
+                    "fmt": DateTime::parse_from_rfc3339(at)
+                        .map(|t| (t.with_timezone(&Utc) - Utc::now()).humanize())
+                        .unwrap_or_else(|_| at.clone()),
+                    "changes": changes,
+                    "rows": changes.len(),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+#[derive(Deserialize)]
+struct ReplacementForm {
+    #[serde(default, deserialize_with = "utils::optional")]
+    replaced_by: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ConditionForm {
+    /// Empty to clear the rating
+    #[serde(default, deserialize_with = "utils::optional")]
+    condition: Option<Condition>,
+}
+
+#[derive(Deserialize)]
+struct RepairForm {
+    /// Today, if not given
+    #[serde(default, deserialize_with = "utils::optional")]
+    date: Option<NaiveDate>,
+    description: String,
+    #[serde(default, deserialize_with = "utils::optional")]
+    cost: Option<f64>,
+}
+
+async fn handle_item_page(
+    id: usize,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let lineage = conn.get_lineage(id).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve lineage: {}", id, e);
+        Vec::new()
+    });
+    let oldest = lineage.first().map(|g| g.generation).unwrap_or_default();
+    let others = conn
+        .get_all(&[SortItems::Name], true)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "request for item {}: could not retrieve collection: {}",
+                id, e
+            );
+            Vec::new()
+        });
+    let metrics = conn.get_metric_totals(id, None).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve metrics: {}", id, e);
+        Vec::new()
+    });
+    let wears = conn.get_item_wears(id, 10).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve wears: {}", id, e);
+        Vec::new()
+    });
+    let washes = conn.get_wash_counts(id).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve washes: {}", id, e);
+        Vec::new()
+    });
+    let cleaning_cost = conn.get_cleaning_cost(id).await.unwrap_or_else(|e| {
+        eprintln!(
+            "request for item {}: could not retrieve cleaning cost: {}",
+            id, e
+        );
+        0.0
+    });
+    let repairs = conn.get_repairs(id).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve repairs: {}", id, e);
+        Vec::new()
+    });
+    let repair_cost: f64 = repairs.iter().filter_map(|(_, _, _, cost)| *cost).sum();
+    let settings = conn.get_settings().await.unwrap_or_else(|e| {
+        eprintln!(
+            "request for item {}: could not retrieve settings: {}",
+            id, e
+        );
+        Default::default()
+    });
+    let tasks = conn.get_tasks(id).await.unwrap_or_else(|e| {
+        eprintln!("request for item {}: could not retrieve tasks: {}", id, e);
+        Vec::new()
+    });
+    let custom_fields = match (
+        conn.get_custom_fields().await,
+        conn.get_custom_values(id).await,
+    ) {
+        (Ok(fields), Ok(values)) => custom::shown(&fields, &values),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!(
+                "request for item {}: could not retrieve custom fields: {}",
+                id, e
+            );
             Vec::new()
         }
     };
+    let attachments = conn.get_attachments(id).await.unwrap_or_else(|e| {
+        eprintln!(
+            "request for item {}: could not retrieve attachments: {}",
+            id, e
+        );
+        Vec::new()
+    });
+    let weekly_wears = conn
+        .get_weekly_wears(Some(id), Utc::now(), history::SPARKLINE_WEEKS)
+        .await
+        .map(|counts| history::weekly(&counts, history::SPARKLINE_WEEKS))
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "request for item {}: could not retrieve weekly wears: {}",
+                id, e
+            );
+            HashMap::new()
+        });
+    let has_photo = attachments
+        .iter()
+        .any(|a| a.content_type.starts_with("image/"));
+    let mut attachment_list = Vec::new();
+    for attachment in &attachments {
+        let duplicates = match (
+            &attachment.digest,
+            attachment.content_type.starts_with("image/"),
+        ) {
+            (Some(digest), true) => conn
+                .get_photo_duplicates(digest, id)
+                .await
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        let mut value = json!(attachment);
+        value["duplicates"] = duplicates
+            .iter()
+            .map(|(key, name)| json!({ "key": key, "name": name }))
+            .collect();
+        attachment_list.push(value);
+    }
+
+    let item = conn.get_item(id).await.map_err(|e| {
+        eprintln!("{}", e);
+        warp::reject::not_found()
+    })?;
+
+    // cleaning and repairs are counted in the wardrobe's currency, so they only add up with a
+    // price in the same one
+    let cost_of_ownership = Some(item.price.unwrap_or_default() + cleaning_cost + repair_cost)
+        .filter(|_| item.currency.is_empty() || item.currency == settings.currency)
+        .filter(|total| *total > 0.0);
+
+    let since_resole = match item.resoled_at {
+        Some(t) => Some((
+            conn.count_wears_since(id, t).await.unwrap_or_default(),
+            conn.get_metric_totals(id, Some(t))
+                .await
+                .unwrap_or_default(),
+        )),
+        None => None,
+    };
 
     Ok(WithTemplate {
-        name: "index",
+        name: "item",
         value: json!({
-            "items": items,
-            "numItems": items.len(),
-            "sort": params.sort,
-            "descending": params.descending,
+            "formKey": idempotency::new_key(),
+            "key": item.id,
+            "name": item.name,
+            "description": item.description,
+            "count": item.count,
+            "totalCount": item.total_count,
+            "quantity": Some(item.quantity).filter(|n| *n > 1),
+            "wearsEach": item.wears_each(),
+            "needsWash": item.needs_wash(),
+            "condition": item.condition.map(Condition::label),
+            "conditionOptions": condition::form_options(&item),
+            "hours": Some(item.total_hours)
+                .filter(|_| item.tracking == Tracking::Hours)
+                .map(|h| Metric::display(Metric::Hours.as_str(), h)),
+            "costPerHour": item.cost_per_hour().map(|c| settings.money_in(c, &item.currency)),
+            "suggestedCondition": Some(condition::suggest(&item))
+                .filter(|c| item.condition != Some(*c))
+                .map(|c| json!({ "value": c.as_str(), "label": c.label() })),
+            "wear": item.last_wear,
+            "wearFmt": item.last_wear.map(|t| settings.time(t)),
+            "wash": item.last_wash,
+            "washFmt": item.last_wash.map(|t| settings.time(t)),
+            "color": item.color,
+            "tags": item.tags.iter().filter(|t| !t.is_empty()).collect::<Vec<_>>(),
+            "token": item.token,
+            "retailer": item.retailer,
+            "productUrl": item.product_url,
+            "returnBy": item.return_by,
+            "warrantyUntil": item.warranty_until,
+            "purchasedOn": item.purchased_on,
+            "life": lifespan::of(&item, Local::today().naive_local()),
+            "price": item.price.map(|p| settings.money_in(p, &item.currency)),
+            "costPerWear": item
+                .cost_per_wear(&settings.currency)
+                .map(|c| settings.money_in(c, &item.currency)),
+            "customFields": custom_fields,
+            "weeklyWears": weekly_wears.get(&id),
+            "care": care::symbols(&item),
+            "measurements": measurements::summary(&item, settings.units),
+            "attachments": attachment_list,
+            "tasks": Task::ALL
+                .iter()
+                .map(|task| {
+                    let flag = tasks.iter().find(|(t, _)| t == task.as_str());
+                    json!({
+                        "task": task.as_str(),
+                        "label": task.label(),
+                        "flagged": flag.is_some(),
+                        "note": flag.map(|(_, note)| note),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "replacedBy": item.replaced_by,
+            "metrics": metrics
+                .iter()
+                .map(|(kind, total, wears)| json!({
+                    "total": Metric::display(kind, *total),
+                    "wears": wears,
+                }))
+                .collect::<Vec<_>>(),
+            "wears": wears
+                .iter()
+                .map(|(worn_at, note)| json!({
+                    "at": worn_at,
+                    "fmt": DateTime::parse_from_rfc3339(worn_at)
+                        .map(|t| (t.with_timezone(&Utc) - Utc::now()).humanize())
+                        .unwrap_or_else(|_| worn_at.clone()),
+                    "note": note,
+                }))
+                .collect::<Vec<_>>(),
+            "washes": washes
+                .iter()
+                .map(|(method, count)| json!({
+                    "label": method.parse().map(WashMethod::label).unwrap_or("Washed"),
+                    "count": count,
+                }))
+                .collect::<Vec<_>>(),
+            "currency": settings.currency,
+            "cleaningCost": settings.money(cleaning_cost),
+            "cleaningCostPerWear": match item.total_count {
+                _ if cleaning_cost == 0.0 => None,
+                0 => None,
+                n => Some(settings.money(cleaning_cost / n as f64)),
+            },
+            "repairs": repairs
+                .iter()
+                .map(|(repair_id, on, description, cost)| json!({
+                    "id": repair_id,
+                    "on": on,
+                    "description": description,
+                    "cost": cost.map(|c| settings.money(c)),
+                }))
+                .collect::<Vec<_>>(),
+            "repairCost": Some(repair_cost).filter(|c| *c > 0.0).map(|c| settings.money(c)),
+            "costOfOwnership": cost_of_ownership.map(|c| settings.money(c)),
+            "costOfOwnershipPerWear": match item.total_count {
+                0 => None,
+                n => cost_of_ownership.map(|c| settings.money(c / n as f64)),
+            },
+            "washMethods": WashMethod::ALL
+                .iter()
+                .map(|m| json!({ "value": m.as_str(), "label": m.label() }))
+                .collect::<Vec<_>>(),
+            "resoled": item.resoled_at.map(|t| t.date().naive_utc()),
+            "archived": item.archived_at.map(|t| t.date().naive_utc()),
+            "deleted": item.deleted_at.map(|t| t.date().naive_utc()),
+            "wishlisted": item.wishlisted_at.map(|t| t.date().naive_utc()),
+            "sinceResole": since_resole.map(|(wears, totals)| json!({
+                "wears": wears,
+                "metrics": totals
+                    .iter()
+                    .map(|(kind, total, _)| Metric::display(kind, *total))
+                    .collect::<Vec<_>>(),
+            })),
+            "lineage": lineage
+                .iter()
+                .filter(|_| lineage.len() > 1)
+                .map(|db::Generation { item: i, generation, first_wear }| json!({
+                    "key": i.id,
+                    "name": i.name,
+                    "current": i.id == id,
+                    "generation": generation - oldest + 1,
+                    "totalCount": i.total_count,
+                    "firstWear": first_wear.map(|t| t.date().naive_utc()),
+                    "lastWear": i.last_wear.map(|t| t.date().naive_utc()),
+                    "daysInUse": first_wear
+                        .zip(i.last_wear)
+                        .map(|(first, last)| (last - first).num_days()),
+                }))
+                .collect::<Vec<_>>(),
+            "others": others
+                .iter()
+                .filter(|o| o.id != id)
+                .map(|o| json!({
+                    "key": o.id,
+                    "name": o.name,
+                    "selected": Some(o.id) == item.replaced_by,
+                }))
+                .collect::<Vec<_>>(),
+            "hasPhoto": has_photo,
         }),
     })
 }
 
+/// Change only the fields given in the request body, responding with the updated item.
+/// The fields that can be edited in place on the index. Blank ones are left alone, except tags,
+/// which can be cleared.
+#[derive(Deserialize)]
+struct QuickEdit {
+    name: Option<String>,
+    color: Option<String>,
+    tags: Option<String>,
+}
+
+/// Apply an edit made on the index, with the same checks as the API, and go back to the listing
+/// given in the query string.
+async fn handle_quick_edit(
+    id: usize,
+    back: IndexOpts,
+    edit: QuickEdit,
+    conn: Connection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+    let patch = ItemPatch {
+        name: non_empty(edit.name),
+        color: non_empty(edit.color),
+        tags: edit
+            .tags
+            .map(|t| t.split(',').map(ToString::to_string).collect()),
+        ..ItemPatch::default()
+    };
+
+    api::patch(id, patch, &conn).await.map_err(|e| {
+        eprintln!("quick edit of item {}: {}", id, e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(utils::go_to(format!("/?{}", back.query())))
+}
+
+async fn handle_patch(
+    id: usize,
+    patch: ItemPatch,
+    conn: Connection,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let item = api::patch(id, patch, &conn).await.map_err(|e| {
+        eprintln!("patch for item {}: {}", id, e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(api::deprecated(
+        warp::reply::json(&item),
+        format!("/api/v1/items/{}", id),
+    ))
+}
+
 async fn handle_edit_form(
     id: usize,
     conn: Connection,
 ) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let settings = conn.get_settings().await.unwrap_or_else(|e| {
+        eprintln!(
+            "request for item {}: could not retrieve settings: {}",
+            id, e
+        );
+        Default::default()
+    });
+    let (fields, values) = match (
+        conn.get_custom_fields().await,
+        conn.get_custom_values(id).await,
+    ) {
+        (Ok(f), Ok(v)) => (f, v),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!(
+                "request for item {}: could not retrieve custom fields: {}",
+                id, e
+            );
+            Default::default()
+        }
+    };
+
     match conn.get_item(id).await {
-        Ok(Item {
-            id,
-            name,
-            description,
-            color,
-            tags,
-            ..
-        }) => Ok(WithTemplate {
-            name: "edit",
-            value: json!({
-                "edit": true,
-                "key": id,
-                "name": name,
-                "description": description,
-                "color": color,
-                "tags": tags.join(", "),
-            }),
-        }),
+        Ok(item) => Ok(WithTemplate::with_context(
+            "edit",
+            context::Edit {
+                edit: true,
+                key: item.id,
+                care_options: care::form_options(&item),
+                condition_options: condition::form_options(&item),
+                tracking_options: Tracking::form_options(item.tracking),
+                warmth_options: Warmth::form_options(item.warmth),
+                season_options: Season::form_options(&item.seasons),
+                suggested_condition: Some(condition::suggest(&item))
+                    .filter(|c| item.condition != Some(*c))
+                    .map(Condition::label),
+                units: settings.units.label(),
+                measurement_fields: measurements::form_fields(&item, settings.units),
+                default_currency: settings.currency,
+                custom_fields: custom::form_fields(&fields, &values),
+                tags: item.tags.join(", "),
+                name: item.name,
+                description: item.description,
+                color: item.color,
+                retailer: item.retailer,
+                product_url: item.product_url,
+                return_by: item.return_by,
+                warranty_until: item.warranty_until,
+                wash_after: item.wash_after,
+                quantity: item.quantity,
+                purchased_on: item.purchased_on,
+                lifespan_months: item.lifespan_months,
+                lifespan_wears: item.lifespan_wears,
+                price: item.price,
+                currency: item.currency,
+            },
+        )),
         Err(e) => {
             eprintln!("{}", e);
             Err(warp::reject::not_found())
         }
     }
 }
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum TagAction {
+    Wear,
+    Wash,
+}
+
+#[derive(Deserialize)]
+struct TagOpts {
+    a: Option<TagAction>,
+}
+
+/// Handle a visit to the short URL written to a garment's physical tag, performing the requested
+/// action (if any) and then showing the garment's current state.
+async fn handle_tag(
+    token: String,
+    params: TagOpts,
+    conn: Connection,
+) -> Result<WithTemplate<serde_json::Value>, warp::Rejection> {
+    let item = match conn.get_item_by_token(&token).await {
+        Ok(item) => item,
+        Err(sqlx::Error::RowNotFound) => {
+            // an old label: say so instead of a bare 404, and don't perform the action
+            let retired = conn.get_retired_token(&token).await.map_err(|e| {
+                eprintln!("request for tag {}: {}", token, e);
+                warp::reject::not_found()
+            })?;
+
+            return match retired {
+                Some((key, name, revoked)) => Ok(WithTemplate {
+                    name: "outdated",
+                    value: json!({ "key": key, "name": name, "revoked": revoked }),
+                }),
+                None => Err(warp::reject::not_found()),
+            };
+        }
+        Err(e) => {
+            eprintln!("request for tag {}: {}", token, e);
+            return Err(warp::reject::not_found());
+        }
+    };
+
+    let logged = match params.a {
+        Some(TagAction::Wear) => conn.log_wear(item.id, &WearDetails::default()).await,
+        Some(TagAction::Wash) => conn.log_wash(item.id, &WashDetails::default()).await,
+        None => Ok(0),
+    };
+
+    if let Err(e) = logged {
+        eprintln!("request for tag {}: {}", token, e);
+        return Err(warp::reject::not_found());
+    }
+
+    let Item {
+        id, name, count, ..
+    } = conn.get_item(item.id).await.map_err(|e| {
+        eprintln!("{}", e);
+        warp::reject::not_found()
+    })?;
+
+    Ok(WithTemplate {
+        name: "tag",
+        value: json!({
+            "key": id,
+            "name": name,
+            "count": count,
+            "action": params.a,
+        }),
+    })
+}
@@ -0,0 +1,117 @@
+//! Garment measurements, for comparing against things that fit when shopping secondhand.
+
+use {
+    super::{settings::Units, Item},
+    serde_json::json,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Measurement {
+    Chest,
+    Waist,
+    Inseam,
+    Sleeve,
+}
+
+impl Measurement {
+    pub(crate) const ALL: [Self; 4] = [Self::Chest, Self::Waist, Self::Inseam, Self::Sleeve];
+
+    /// The form field and database column for this measurement.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Self::Chest => "chest",
+            Self::Waist => "waist",
+            Self::Inseam => "inseam",
+            Self::Sleeve => "sleeve",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Chest => "Chest",
+            Self::Waist => "Waist",
+            Self::Inseam => "Inseam",
+            Self::Sleeve => "Sleeve",
+        }
+    }
+
+    pub(crate) fn get(self, item: &Item) -> Option<f64> {
+        match self {
+            Self::Chest => item.chest,
+            Self::Waist => item.waist,
+            Self::Inseam => item.inseam,
+            Self::Sleeve => item.sleeve,
+        }
+    }
+
+    fn get_mut(self, item: &mut Item) -> &mut Option<f64> {
+        match self {
+            Self::Chest => &mut item.chest,
+            Self::Waist => &mut item.waist,
+            Self::Inseam => &mut item.inseam,
+            Self::Sleeve => &mut item.sleeve,
+        }
+    }
+}
+
+/// Round a length for display in the given units.
+pub(crate) fn display(cm: f64, units: Units) -> String {
+    format!("{:.1} {}", units.of_cm(cm), units.label())
+}
+
+/// Convert measurements entered in the user's units to centimetres, dropping nonsense values.
+pub(crate) fn to_cm(item: &mut Item, units: Units) {
+    for m in Measurement::ALL.iter() {
+        let value = m.get_mut(item);
+        *value = value
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .map(|v| units.to_cm(v));
+    }
+}
+
+/// Every measurement an item has, formatted for display.
+pub(crate) fn summary(item: &Item, units: Units) -> Vec<serde_json::Value> {
+    Measurement::ALL
+        .iter()
+        .filter_map(|m| {
+            m.get(item)
+                .map(|cm| json!({ "label": m.label(), "value": display(cm, units) }))
+        })
+        .collect()
+}
+
+/// Inputs for each measurement, prefilled in the user's units.
+pub(crate) fn form_fields(item: &Item, units: Units) -> Vec<serde_json::Value> {
+    Measurement::ALL
+        .iter()
+        .map(|m| {
+            json!({
+                "field": m.key(),
+                "label": m.label(),
+                "value": m.get(item).map(|cm| format!("{:.1}", units.of_cm(cm))),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn entered_in_inches() {
+        let mut item = Item {
+            chest: Some(40.0),
+            waist: Some(-3.0),
+            inseam: Some(f64::NAN),
+            ..Item::default()
+        };
+
+        to_cm(&mut item, Units::In);
+
+        assert_eq!(item.chest, Some(101.6));
+        assert_eq!((item.waist, item.inseam, item.sleeve), (None, None, None));
+        assert_eq!(display(101.6, Units::In), "40.0 in");
+        assert_eq!(display(101.6, Units::Cm), "101.6 cm");
+    }
+}
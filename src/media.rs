@@ -0,0 +1,129 @@
+use {
+    async_trait::async_trait,
+    chrono::Utc,
+    sha2::{Digest, Sha256},
+    std::{
+        io,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    tokio::fs,
+};
+
+/// File types that may be attached to an item, and the extension they're stored with.
+const ALLOWED_TYPES: &[(&str, &str)] = &[
+    ("application/pdf", "pdf"),
+    ("image/gif", "gif"),
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/webp", "webp"),
+    ("text/plain", "txt"),
+];
+
+/// Largest file that will be accepted for storage.
+pub(crate) const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Somewhere to keep uploaded files, by the name they were stored under.
+#[async_trait]
+pub(crate) trait ImageStore: Send + Sync {
+    async fn put(&self, name: &str, data: &[u8]) -> io::Result<()>;
+    async fn get(&self, name: &str) -> io::Result<Vec<u8>>;
+    async fn delete(&self, name: &str) -> io::Result<()>;
+}
+
+/// Files in a directory on this machine.
+struct LocalStore(PathBuf);
+
+impl LocalStore {
+    fn path(&self, stored_as: &str) -> PathBuf {
+        // stored names are generated by `Media`, but never let one escape the media directory
+        let file_name = Path::new(stored_as).file_name().unwrap_or_default();
+        self.0.join(file_name)
+    }
+}
+
+#[async_trait]
+impl ImageStore for LocalStore {
+    async fn put(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        fs::write(self.path(name), data).await
+    }
+
+    async fn get(&self, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path(name)).await
+    }
+
+    async fn delete(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(name)).await
+    }
+}
+
+/// Where uploaded files live.
+#[derive(Clone)]
+pub(crate) struct Media(Arc<dyn ImageStore>);
+
+impl Media {
+    /// Keep files in a directory on this machine, creating it if needed.
+    pub(crate) async fn new(root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&root).await?;
+        Ok(Self::with_store(LocalStore(root)))
+    }
+
+    pub(crate) fn with_store(store: impl ImageStore + 'static) -> Self {
+        Self(Arc::new(store))
+    }
+
+    pub(crate) fn extension_for(content_type: &str) -> Option<&'static str> {
+        ALLOWED_TYPES
+            .iter()
+            .find(|(t, _)| *t == content_type)
+            .map(|(_, ext)| *ext)
+    }
+
+    /// A content hash, for spotting the same file uploaded twice.
+    pub(crate) fn digest(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// Store a file for the given item, returning the name it was stored under.
+    pub(crate) async fn save(
+        &self,
+        item_id: usize,
+        extension: &str,
+        data: &[u8],
+    ) -> io::Result<String> {
+        let name = format!("{}-{}.{}", item_id, Utc::now().timestamp_nanos(), extension);
+        self.0.put(&name, data).await?;
+        Ok(name)
+    }
+
+    /// Store a file under a name of the caller's choosing, replacing any file already there.
+    pub(crate) async fn write(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        self.0.put(name, data).await
+    }
+
+    pub(crate) async fn read(&self, stored_as: &str) -> io::Result<Vec<u8>> {
+        self.0.get(stored_as).await
+    }
+
+    /// Make sure files can be stored, by writing and removing one.
+    pub(crate) async fn check_writable(&self) -> io::Result<()> {
+        self.0.put(".write-check", b"").await?;
+        self.0.delete(".write-check").await
+    }
+
+    pub(crate) async fn remove(&self, stored_as: &str) {
+        if let Err(e) = self.0.delete(stored_as).await {
+            eprintln!("Could not remove stored file {}: {}", stored_as, e);
+        }
+    }
+
+    /// Like `remove`, for files that might not exist.
+    pub(crate) async fn remove_if_present(&self, stored_as: &str) {
+        match self.0.delete(stored_as).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                eprintln!("Could not remove stored file {}: {}", stored_as, e)
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    super::{db::Connection, schedule},
+    chrono::{Local, NaiveTime},
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    },
+    tokio::process::Command,
+};
+
+/// Delivers messages to the user outside of the web interface.
+///
+/// Every notification is written to the log. If a command is configured, it is also run with the
+/// subject and body as its two arguments, which is enough to hook up `notify-send`, `mail`, a
+/// push service's CLI, etc.
+///
+/// During the quiet hours in the settings, notifications are held in the database instead, and
+/// all sent together when the quiet hours end.
+#[derive(Clone, Default)]
+pub(crate) struct Notifier {
+    command: Option<Arc<PathBuf>>,
+    conn: Option<Connection>,
+    /// Whether sending the held notifications is already scheduled
+    scheduled: Arc<AtomicBool>,
+}
+
+impl Notifier {
+    pub(crate) fn new(command: Option<PathBuf>, conn: Connection) -> Self {
+        Self {
+            command: command.map(Arc::new),
+            conn: Some(conn),
+            scheduled: Arc::default(),
+        }
+    }
+
+    /// When the current quiet hours end, if it's during them.
+    async fn quiet_until(&self, conn: &Connection) -> Option<NaiveTime> {
+        match conn.get_settings().await {
+            Ok(settings) => settings.quiet_until(Local::now().time()),
+            Err(e) => {
+                eprintln!("Could not check for quiet hours: {}", e);
+                None
+            }
+        }
+    }
+
+    pub(crate) async fn send(&self, subject: &str, body: &str) {
+        if let Some(conn) = &self.conn {
+            if let Some(until) = self.quiet_until(conn).await {
+                match conn.hold_notification(subject, body).await {
+                    Ok(_) => {
+                        eprintln!(
+                            "Notification held until {}: {}",
+                            until.format("%H:%M"),
+                            subject
+                        );
+                        return self.send_held_at(until);
+                    }
+                    Err(e) => eprintln!("Could not hold notification, sending it now: {}", e),
+                }
+            }
+        }
+
+        self.deliver(subject, body).await
+    }
+
+    /// Send the held notifications, unless it's still quiet; then they wait until it isn't.
+    pub(crate) async fn send_held(&self) {
+        let conn = match &self.conn {
+            Some(conn) => conn,
+            None => return,
+        };
+        // the quiet hours may have changed since the notifications were held
+        if let Some(until) = self.quiet_until(conn).await {
+            return self.send_held_at(until);
+        }
+
+        match conn.take_held_notifications().await {
+            Ok(held) => {
+                for (subject, body) in held {
+                    self.deliver(&subject, &body).await;
+                }
+            }
+            Err(e) => eprintln!("Could not retrieve held notifications: {}", e),
+        }
+    }
+
+    fn send_held_at(&self, at: NaiveTime) {
+        if self.scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let notifier = self.clone();
+        schedule::once(at, async move {
+            notifier.scheduled.store(false, Ordering::SeqCst);
+            notifier.send_held().await;
+        });
+    }
+
+    async fn deliver(&self, subject: &str, body: &str) {
+        eprintln!("Notification: {}\n{}", subject, body);
+
+        if let Some(cmd) = &self.command {
+            match Command::new(cmd.as_ref())
+                .arg(subject)
+                .arg(body)
+                .status()
+                .await
+            {
+                Ok(status) if status.success() => (),
+                Ok(status) => eprintln!("Notification command exited with {}", status),
+                Err(e) => eprintln!("Could not run notification command: {}", e),
+            }
+        }
+    }
+}
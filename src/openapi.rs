@@ -0,0 +1,574 @@
+//! An OpenAPI 3 description of the routes that other programs are likely to call. Request and
+//! response bodies are generated from the same types the handlers deserialize and serialize, so
+//! they can't drift from what the server actually accepts.
+
+use {
+    super::{
+        api::{ErrorBody, History, ItemPage, Status},
+        calendar::Month,
+        Item, ItemPatch, WashDetails, WearDetails,
+    },
+    schemars::{
+        gen::{SchemaGenerator, SchemaSettings},
+        schema::Schema,
+        JsonSchema,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::{json, Map, Value},
+};
+
+// Mirrors async-graphql's request and response shapes, for documentation only
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GraphQLRequest {
+    query: String,
+    operation_name: Option<String>,
+    variables: Option<Map<String, Value>>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, Serialize)]
+struct GraphQLResponse {
+    data: Option<Value>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, Serialize)]
+struct GraphQLError {
+    message: String,
+    locations: Option<Vec<Value>>,
+    path: Option<Vec<Value>>,
+}
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+enum Content {
+    Json(SchemaFn),
+    /// Accepted as either JSON or a urlencoded form
+    JsonOrForm(SchemaFn),
+    Other(&'static str),
+    Empty,
+}
+
+struct Param {
+    name: &'static str,
+    location: &'static str,
+    required: bool,
+    kind: &'static str,
+    choices: &'static [&'static str],
+    description: &'static str,
+}
+
+const fn path_id(description: &'static str) -> Param {
+    Param {
+        name: "id",
+        location: "path",
+        required: true,
+        kind: "integer",
+        choices: &[],
+        description,
+    }
+}
+
+const IDEMPOTENCY_KEY: Param = Param {
+    name: "Idempotency-Key",
+    location: "header",
+    required: false,
+    kind: "string",
+    choices: &[],
+    description: "Repeats of a request with the same key are acknowledged but not acted on",
+};
+
+struct Route {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    params: Vec<Param>,
+    body: Option<Content>,
+    responses: Vec<(u16, &'static str, Content)>,
+}
+
+/// Outside the API, every rejection is reported with a status code and an empty body.
+const NOT_FOUND: (u16, &str, Content) = (
+    404,
+    "No such item, or the request could not be completed",
+    Content::Empty,
+);
+
+const API_NOT_FOUND: (u16, &str, Content) =
+    (404, "No such item", Content::Json(schema::<ErrorBody>));
+const API_BAD_REQUEST: (u16, &str, Content) = (
+    400,
+    "The body could not be parsed, or the change is invalid",
+    Content::Json(schema::<ErrorBody>),
+);
+
+fn list_params() -> Vec<Param> {
+    vec![
+        Param {
+            name: "view",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &["unworn", "new", "recently-washed"],
+            description: "Only show items in one of the predefined views",
+        },
+        Param {
+            name: "q",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &[],
+            description:
+                "Only show items with names, descriptions or tags starting with these words",
+        },
+        Param {
+            name: "tag",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &[],
+            description: "Only show items with this tag, ignoring case",
+        },
+        Param {
+            name: "color",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &[],
+            description:
+                "Only show items of this #rrggbb color, or in a color family such as \"black\"",
+        },
+        Param {
+            name: "unworn_days",
+            location: "query",
+            required: false,
+            kind: "integer",
+            choices: &[],
+            description: "Only show items not worn in at least this many days, or never",
+        },
+        Param {
+            name: "archived",
+            location: "query",
+            required: false,
+            kind: "boolean",
+            choices: &[],
+            description: "Show archived items instead of current ones",
+        },
+        Param {
+            name: "filter",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &[],
+            description:
+                "Only show items matching a filter like `tag:wool color:black count>3 unworn>30d needs:wash condition:worn care:dry-clean`",
+        },
+        Param {
+            name: "sort",
+            location: "query",
+            required: false,
+            kind: "string",
+            choices: &[
+                "name",
+                "count",
+                "wear",
+                "wash",
+                "total",
+                "tag",
+                "description",
+                "modified",
+                "condition",
+            ],
+            description: "Column to sort by",
+        },
+        Param {
+            name: "descending",
+            location: "query",
+            required: false,
+            kind: "boolean",
+            choices: &[],
+            description: "Reverse the sort order",
+        },
+    ]
+}
+
+fn routes() -> Vec<Route> {
+    vec![
+        Route {
+            method: "get",
+            path: "/",
+            summary: "List items",
+            params: list_params()
+                .into_iter()
+                .chain(vec![
+                    Param {
+                        name: "page",
+                        location: "query",
+                        required: false,
+                        kind: "integer",
+                        choices: &[],
+                        description: "Page to show, starting from 1",
+                    },
+                    Param {
+                        name: "per_page",
+                        location: "query",
+                        required: false,
+                        kind: "integer",
+                        choices: &[],
+                        description: "Items per page, up to 500 (default 50)",
+                    },
+                ])
+                .collect(),
+            body: None,
+            responses: vec![(200, "The item list", Content::Other("text/html"))],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/items",
+            summary: "List items, a page at a time",
+            params: list_params()
+                .into_iter()
+                .chain(vec![
+                    Param {
+                        name: "limit",
+                        location: "query",
+                        required: false,
+                        kind: "integer",
+                        choices: &[],
+                        description: "Items per page, up to 500 (default 100)",
+                    },
+                    Param {
+                        name: "cursor",
+                        location: "query",
+                        required: false,
+                        kind: "string",
+                        choices: &[],
+                        description: "The `prev_cursor` or `next_cursor` of another page",
+                    },
+                ])
+                .collect(),
+            body: None,
+            responses: vec![
+                (200, "Matching items", Content::Json(schema::<ItemPage>)),
+                API_BAD_REQUEST,
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/items/search",
+            summary: "Search items, a page at a time",
+            params: list_params()
+                .into_iter()
+                .map(|p| match p.name {
+                    "q" => Param {
+                        required: true,
+                        description: "Words that names, descriptions or tags must start with",
+                        ..p
+                    },
+                    _ => p,
+                })
+                .chain(vec![
+                    Param {
+                        name: "limit",
+                        location: "query",
+                        required: false,
+                        kind: "integer",
+                        choices: &[],
+                        description: "Items per page, up to 500 (default 100)",
+                    },
+                    Param {
+                        name: "cursor",
+                        location: "query",
+                        required: false,
+                        kind: "string",
+                        choices: &[],
+                        description: "The `prev_cursor` or `next_cursor` of another page",
+                    },
+                ])
+                .collect(),
+            body: None,
+            responses: vec![
+                (200, "Matching items", Content::Json(schema::<ItemPage>)),
+                (
+                    400,
+                    "Nothing to search for, or the filters are invalid",
+                    Content::Json(schema::<ErrorBody>),
+                ),
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/items/{id}",
+            summary: "Get one item",
+            params: vec![path_id("Item to get")],
+            body: None,
+            responses: vec![
+                (200, "The item", Content::Json(schema::<Item>)),
+                API_NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "patch",
+            path: "/api/v1/items/{id}",
+            summary: "Change some of an item's fields",
+            params: vec![path_id("Item to change")],
+            body: Some(Content::Json(schema::<ItemPatch>)),
+            responses: vec![
+                (200, "The updated item", Content::Json(schema::<Item>)),
+                API_BAD_REQUEST,
+                API_NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "post",
+            path: "/item/{id}/increment",
+            summary: "Log a wear",
+            params: vec![path_id("Item that was worn"), IDEMPOTENCY_KEY],
+            body: Some(Content::JsonOrForm(schema::<WearDetails>)),
+            responses: vec![
+                (303, "Logged; redirects to the item list", Content::Empty),
+                NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "post",
+            path: "/item/{id}/reset",
+            summary: "Log a wash",
+            params: vec![path_id("Item that was washed"), IDEMPOTENCY_KEY],
+            body: Some(Content::JsonOrForm(schema::<WashDetails>)),
+            responses: vec![
+                (303, "Logged; redirects to the item list", Content::Empty),
+                NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/t/{token}",
+            summary: "Visit an item's tag link, optionally logging a wear or wash",
+            params: vec![
+                Param {
+                    name: "token",
+                    location: "path",
+                    required: true,
+                    kind: "string",
+                    choices: &[],
+                    description: "The token printed on the item's label",
+                },
+                Param {
+                    name: "a",
+                    location: "query",
+                    required: false,
+                    kind: "string",
+                    choices: &["wear", "wash"],
+                    description: "Action to log",
+                },
+            ],
+            body: None,
+            responses: vec![
+                (200, "The item's current state", Content::Other("text/html")),
+                NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/items/{id}/history",
+            summary: "List an item's wears and washes in order, with how long it usually goes between washes",
+            params: vec![path_id("Item to get the history of")],
+            body: None,
+            responses: vec![
+                (200, "The history", Content::Json(schema::<History>)),
+                API_NOT_FOUND,
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/status",
+            summary: "Find out when anything last changed, to tell whether to refresh",
+            params: vec![],
+            body: None,
+            responses: vec![(200, "The latest change", Content::Json(schema::<Status>))],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/calendar",
+            summary: "Lay out a month of wear history by day, weeks starting on Monday",
+            params: vec![Param {
+                name: "month",
+                location: "query",
+                required: false,
+                kind: "string",
+                choices: &[],
+                description: "Month to show, as YYYY-MM (default this month)",
+            }],
+            body: None,
+            responses: vec![
+                (200, "The month", Content::Json(schema::<Month>)),
+                API_BAD_REQUEST,
+            ],
+        },
+        Route {
+            method: "post",
+            path: "/api/v1/graphql",
+            summary: "Query items, tags and history with GraphQL",
+            params: vec![],
+            body: Some(Content::Json(schema::<GraphQLRequest>)),
+            responses: vec![
+                (
+                    200,
+                    "Query results and any errors",
+                    Content::Json(schema::<GraphQLResponse>),
+                ),
+                API_BAD_REQUEST,
+            ],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/export/anonymized.json",
+            summary: "Download wear history with names and descriptions removed",
+            params: vec![Param {
+                name: "include_archived",
+                location: "query",
+                required: false,
+                kind: "boolean",
+                choices: &[],
+                description: "Include archived items as well as current ones",
+            }],
+            body: None,
+            responses: vec![(200, "The export", Content::Other("application/json"))],
+        },
+        Route {
+            method: "get",
+            path: "/laundry/calendar.ics",
+            summary: "Subscribe to the laundry schedule",
+            params: vec![],
+            body: None,
+            responses: vec![(200, "An iCalendar feed", Content::Other("text/calendar"))],
+        },
+        Route {
+            method: "get",
+            path: "/api/v1/openapi.json",
+            summary: "This document",
+            params: vec![],
+            body: None,
+            responses: vec![(
+                200,
+                "OpenAPI 3 document",
+                Content::Other("application/json"),
+            )],
+        },
+    ]
+}
+
+fn content(kind: &Content, gen: &mut SchemaGenerator) -> Value {
+    match kind {
+        Content::Json(f) => json!({ "application/json": { "schema": f(gen) } }),
+        Content::JsonOrForm(f) => {
+            let schema = f(gen);
+            json!({
+                "application/json": { "schema": schema },
+                "application/x-www-form-urlencoded": { "schema": schema },
+            })
+        }
+        Content::Other(mime) => json!({ mime.to_string(): {} }),
+        Content::Empty => json!({}),
+    }
+}
+
+/// Build the whole document.
+pub(crate) fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+
+    for route in routes() {
+        let parameters = route
+            .params
+            .iter()
+            .map(|p| {
+                let mut schema = json!({ "type": p.kind });
+                if !p.choices.is_empty() {
+                    schema["enum"] = json!(p.choices);
+                }
+                json!({
+                    "name": p.name,
+                    "in": p.location,
+                    "required": p.required,
+                    "description": p.description,
+                    "schema": schema,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let responses = route
+            .responses
+            .iter()
+            .map(|(status, description, body)| {
+                let mut response = json!({ "description": description });
+                if !matches!(body, Content::Empty) {
+                    response["content"] = content(body, &mut gen);
+                }
+                (status.to_string(), response)
+            })
+            .collect::<Map<_, _>>();
+
+        let mut operation = json!({
+            "summary": route.summary,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if let Some(body) = &route.body {
+            operation["requestBody"] = json!({
+                "required": !matches!(body, Content::JsonOrForm(_)),
+                "content": content(body, &mut gen),
+            });
+        }
+
+        paths
+            .entry(route.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .map(|p| p.insert(route.method.into(), operation));
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "wear",
+            "version": super::api::VERSION,
+        },
+        "paths": paths,
+        "components": { "schemas": gen.take_definitions() },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn schemas_resolve() {
+        let doc = document();
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+
+        // every reference points at a schema that was generated
+        let text = doc.to_string();
+        for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas.contains_key(name), "missing schema {}", name);
+        }
+
+        assert!(schemas.contains_key("Item"));
+        assert!(doc["paths"]["/api/v1/items/{id}"]["patch"]["requestBody"].is_object());
+        assert!(doc["paths"]["/api/v1/items/search"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|p| p["name"] == "q" && p["required"] == true));
+    }
+}
@@ -0,0 +1,78 @@
+use {
+    chrono::{DateTime, Duration, Local, NaiveTime, TimeZone},
+    std::future::Future,
+    tokio::time,
+};
+
+/// How long it is from `now` until the local clock next reads `at`.
+fn until_next<Tz: TimeZone>(at: NaiveTime, now: &DateTime<Tz>) -> Duration {
+    let today = now.date().and_time(at);
+
+    match today {
+        Some(t) if t > *now => t - now.clone(),
+        _ => (now.date() + Duration::days(1))
+            .and_time(at)
+            .map(|t| t - now.clone())
+            // the requested time doesn't exist tomorrow (DST), so just wait a day
+            .unwrap_or_else(|| Duration::days(1)),
+    }
+}
+
+/// Run `job` every day at the given local time, forever.
+pub(crate) fn daily<F, Fut>(at: NaiveTime, job: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    tokio::spawn(async move {
+        loop {
+            let wait = until_next(at, &Local::now()).to_std().unwrap_or_default();
+            time::delay_for(wait).await;
+            job().await;
+        }
+    });
+}
+
+/// Run `job` once, the next time the local clock reads `at`.
+pub(crate) fn once<Fut>(at: NaiveTime, job: Fut)
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let wait = until_next(at, &Local::now()).to_std().unwrap_or_default();
+        time::delay_for(wait).await;
+        job.await;
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, chrono::Utc};
+
+    #[test]
+    fn later_today() {
+        let now = Utc.ymd(2020, 5, 1).and_hms(8, 0, 0);
+        assert_eq!(
+            until_next(NaiveTime::from_hms(20, 30, 0), &now),
+            Duration::minutes(12 * 60 + 30)
+        );
+    }
+
+    #[test]
+    fn tomorrow() {
+        let now = Utc.ymd(2020, 5, 1).and_hms(21, 0, 0);
+        assert_eq!(
+            until_next(NaiveTime::from_hms(20, 30, 0), &now),
+            Duration::minutes(23 * 60 + 30)
+        );
+    }
+
+    #[test]
+    fn right_now() {
+        let now = Utc.ymd(2020, 5, 1).and_hms(20, 30, 0);
+        assert_eq!(
+            until_next(NaiveTime::from_hms(20, 30, 0), &now),
+            Duration::days(1)
+        );
+    }
+}
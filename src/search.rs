@@ -0,0 +1,72 @@
+//! Word search over item names, descriptions and tags.
+//!
+//! SQLite's FTS5 would be the obvious tool, but sqlx prepares every statement with
+//! `SQLITE_PREPARE_NO_VTAB`, which rules out virtual tables. Instead each item's words are kept in
+//! an ordinary indexed table, and a search matches items that have every word it asks for.
+
+use {super::db::Connection, std::collections::BTreeSet};
+
+/// Split text into lowercase words.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// The words an item can be found by.
+pub(crate) fn terms(name: &str, description: &str, tags: &str) -> BTreeSet<String> {
+    words(name)
+        .chain(words(description))
+        .chain(words(tags))
+        .collect()
+}
+
+/// Turn what someone typed into GLOB patterns that must each match one of an item's words. Each
+/// word counts as a prefix, so "oxf" finds "oxford". Returns nothing if there is nothing to search
+/// for.
+pub(crate) fn patterns(input: &str) -> Vec<String> {
+    // words only contain alphanumerics, so none of them can be GLOB syntax
+    words(input)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|w| format!("{}*", w))
+        .collect()
+}
+
+/// Index any items that aren't yet, e.g. those created before search existed.
+pub(crate) async fn backfill(conn: Connection) {
+    let ids = match conn.get_unindexed_items().await {
+        Ok(ids) => ids,
+        Err(e) => return eprintln!("Could not look up items to index for search: {}", e),
+    };
+
+    for id in ids {
+        if let Err(e) = conn.index_item(id).await {
+            eprintln!("Could not index item {} for search: {}", id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn item_words() {
+        let found = terms("Blue Oxford", "A well-worn shirt", "work,summer");
+        let expected = [
+            "a", "blue", "oxford", "shirt", "summer", "well", "work", "worn",
+        ];
+        assert_eq!(
+            found.iter().map(String::as_str).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn search_words_become_prefixes() {
+        assert_eq!(patterns("Blue oxf"), vec!["blue*", "oxf*"]);
+        assert_eq!(patterns("wool* [a-z] wool"), vec!["a*", "wool*", "z*"]);
+        assert!(patterns(" -- ").is_empty());
+    }
+}
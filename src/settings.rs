@@ -0,0 +1,297 @@
+//! User preferences, stored as key/value pairs and edited from the settings page.
+
+use {
+    super::{locale::Locale, utils},
+    chrono::{DateTime, Local, NaiveTime, Utc},
+    chrono_humanize::Humanize,
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+};
+
+/// Units that garment measurements are entered and shown in. They are always stored in
+/// centimetres.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Units {
+    #[default]
+    Cm,
+    In,
+}
+
+impl Units {
+    const CM_PER_INCH: f64 = 2.54;
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Cm => "cm",
+            Self::In => "in",
+        }
+    }
+
+    pub(crate) fn to_cm(self, value: f64) -> f64 {
+        match self {
+            Self::Cm => value,
+            Self::In => value * Self::CM_PER_INCH,
+        }
+    }
+
+    pub(crate) fn of_cm(self, cm: f64) -> f64 {
+        match self {
+            Self::Cm => cm,
+            Self::In => cm / Self::CM_PER_INCH,
+        }
+    }
+}
+
+/// How names and descriptions are compared when sorting.
+///
+/// SQLite's own NOCASE only folds ASCII letters; the bundled SQLite has no ICU, so there is no
+/// fully locale-aware option.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Collation {
+    /// "apple shirt" and "Apple shirt" sort together
+    #[default]
+    NoCase,
+    /// Capitals before lowercase, as the bytes fall
+    Binary,
+}
+
+impl Collation {
+    pub(crate) fn sql(self) -> &'static str {
+        match self {
+            Self::NoCase => "NOCASE",
+            Self::Binary => "BINARY",
+        }
+    }
+}
+
+/// How the times of last wears and washes are shown.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TimeFormat {
+    /// "an hour ago", "3 months ago"
+    #[default]
+    Relative,
+    /// "today", "yesterday", "4 days ago", ignoring the time of day
+    Days,
+    /// Relative for the last week, then the date itself
+    Week,
+}
+
+impl TimeFormat {
+    pub(crate) fn format(self, t: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let days = (now.with_timezone(&Local).date() - t.with_timezone(&Local).date()).num_days();
+
+        match (self, days) {
+            (Self::Relative, _) => (t - now).humanize(),
+            (Self::Week, d) if d.abs() > 7 => {
+                t.with_timezone(&Local).format("%Y-%m-%d").to_string()
+            }
+            (Self::Week, _) => (t - now).humanize(),
+            (Self::Days, 0) => "today".into(),
+            (Self::Days, 1) => "yesterday".into(),
+            (Self::Days, -1) => "tomorrow".into(),
+            (Self::Days, d) if d < 0 => format!("in {} days", -d),
+            (Self::Days, d) => format!("{} days ago", d),
+        }
+    }
+}
+
+/// Times of day are stored as "HH:MM", and left empty when not set.
+fn serialize_time<S: Serializer>(t: &Option<NaiveTime>, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&t.map(|t| t.format("%H:%M").to_string()).unwrap_or_default())
+}
+
+fn deserialize_time<'a, D: Deserializer<'a>>(d: D) -> Result<Option<NaiveTime>, D::Error> {
+    match String::deserialize(d)?.trim() {
+        "" => Ok(None),
+        // some browsers send seconds too
+        s => utils::parse_time(s)
+            .or_else(|_| s.parse())
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn default_currency() -> String {
+    "USD".into()
+}
+
+fn default_locale() -> String {
+    "en-US".into()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Settings {
+    #[serde(default)]
+    pub(crate) units: Units,
+    /// ISO 4217 code that prices are entered and shown in
+    #[serde(default = "default_currency")]
+    pub(crate) currency: String,
+    #[serde(default = "default_locale")]
+    pub(crate) locale: String,
+    #[serde(default)]
+    pub(crate) collation: Collation,
+    #[serde(default)]
+    pub(crate) time_format: TimeFormat,
+    /// Local times between which notifications wait instead of being sent
+    #[serde(
+        default,
+        serialize_with = "serialize_time",
+        deserialize_with = "deserialize_time"
+    )]
+    pub(crate) quiet_from: Option<NaiveTime>,
+    #[serde(
+        default,
+        serialize_with = "serialize_time",
+        deserialize_with = "deserialize_time"
+    )]
+    pub(crate) quiet_until: Option<NaiveTime>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            units: Units::default(),
+            currency: default_currency(),
+            locale: default_locale(),
+            collation: Collation::default(),
+            time_format: TimeFormat::default(),
+            quiet_from: None,
+            quiet_until: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Format an amount of money in the chosen currency and locale.
+    pub(crate) fn money(&self, value: f64) -> String {
+        Locale::find(&self.locale).money(value, &self.currency)
+    }
+
+    /// Format an amount in a particular currency, or the chosen one if none is given.
+    pub(crate) fn money_in(&self, value: f64, currency: &str) -> String {
+        match currency {
+            "" => self.money(value),
+            currency => Locale::find(&self.locale).money(value, currency),
+        }
+    }
+
+    /// Show a past (or future) time the chosen way.
+    pub(crate) fn time(&self, t: DateTime<Utc>) -> String {
+        self.time_format.format(t, Utc::now())
+    }
+
+    /// When the quiet hours that `now` falls in end, if it's during them. They can run past
+    /// midnight, like 22:00 to 07:00.
+    pub(crate) fn quiet_until(&self, now: NaiveTime) -> Option<NaiveTime> {
+        let (from, until) = (self.quiet_from?, self.quiet_until?);
+        let quiet = match from <= until {
+            true => from <= now && now < until,
+            false => now >= from || now < until,
+        };
+
+        Some(until).filter(|_| quiet)
+    }
+
+    /// Build settings from stored pairs, falling back to the defaults for anything unreadable.
+    pub(crate) fn from_pairs(pairs: Vec<(String, String)>) -> Self {
+        let map = pairs
+            .into_iter()
+            .map(|(k, v)| (k, serde_json::Value::String(v)))
+            .collect();
+
+        serde_json::from_value(serde_json::Value::Object(map)).unwrap_or_else(|e| {
+            eprintln!("Could not read stored settings, using defaults: {}", e);
+            Self::default()
+        })
+    }
+
+    /// Flatten settings into pairs for storage.
+    pub(crate) fn to_pairs(&self) -> Vec<(String, String)> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map
+                .into_iter()
+                .map(|(k, v)| match v {
+                    serde_json::Value::String(s) => (k, s),
+                    other => (k, other.to_string()),
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let settings = Settings {
+            units: Units::In,
+            currency: "EUR".into(),
+            quiet_from: Some(NaiveTime::from_hms(22, 0, 0)),
+            ..Settings::default()
+        };
+        let restored = Settings::from_pairs(settings.to_pairs());
+
+        assert_eq!(restored.units, Units::In);
+        assert_eq!(restored.currency, "EUR");
+        assert_eq!(restored.locale, "en-US");
+        assert_eq!(restored.quiet_from, Some(NaiveTime::from_hms(22, 0, 0)));
+        assert_eq!(restored.quiet_until, None);
+        assert_eq!(
+            Settings::from_pairs(vec![("units".into(), "furlongs".into())]).units,
+            Units::Cm
+        );
+    }
+
+    #[test]
+    fn quiet_hours() {
+        let at = |h, m| NaiveTime::from_hms(h, m, 0);
+        let overnight = Settings {
+            quiet_from: Some(at(22, 0)),
+            quiet_until: Some(at(7, 30)),
+            ..Settings::default()
+        };
+
+        assert_eq!(overnight.quiet_until(at(2, 0)), Some(at(7, 30)));
+        assert_eq!(overnight.quiet_until(at(22, 0)), Some(at(7, 30)));
+        assert_eq!(overnight.quiet_until(at(7, 30)), None);
+        assert_eq!(overnight.quiet_until(at(12, 0)), None);
+
+        let afternoon = Settings {
+            quiet_from: Some(at(13, 0)),
+            quiet_until: Some(at(15, 0)),
+            ..Settings::default()
+        };
+        assert_eq!(afternoon.quiet_until(at(14, 0)), Some(at(15, 0)));
+        assert_eq!(afternoon.quiet_until(at(2, 0)), None);
+
+        let unfinished = Settings {
+            quiet_until: None,
+            ..overnight
+        };
+        assert_eq!(unfinished.quiet_until(at(2, 0)), None);
+    }
+
+    #[test]
+    fn time_formats() {
+        let now = Utc::now();
+        let ago = |days| now - chrono::Duration::days(days);
+
+        assert_eq!(TimeFormat::Days.format(now, now), "today");
+        assert_eq!(TimeFormat::Days.format(ago(1), now), "yesterday");
+        assert_eq!(TimeFormat::Days.format(ago(4), now), "4 days ago");
+        assert_eq!(TimeFormat::Week.format(ago(3), now), "3 days ago");
+        assert_eq!(
+            TimeFormat::Week.format(ago(30), now),
+            ago(30).with_timezone(&Local).format("%Y-%m-%d").to_string()
+        );
+        assert_eq!(
+            TimeFormat::Relative.format(ago(30), now),
+            (ago(30) - now).humanize()
+        );
+    }
+}
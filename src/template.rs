@@ -14,14 +14,308 @@ impl<T: Serialize> WithTemplate<T> {
     }
 }
 
+impl WithTemplate<serde_json::Value> {
+    /// A page with one of the typed contexts, turned into the same value as the `json!` ones.
+    pub fn with_context(name: &'static str, context: impl Serialize) -> Self {
+        Self {
+            name,
+            value: serde_json::to_value(context).unwrap_or_default(),
+        }
+    }
+}
+
 pub fn init() -> anyhow::Result<Handlebars> {
     let mut hb = Handlebars::new();
 
     hb.register_template_string("index", include_str!("./static/index.hbs"))?;
     hb.register_partial("nav", include_str!("./static/nav.hbs"))?;
+    hb.register_partial("views", include_str!("./static/views.hbs"))?;
     hb.register_partial("form", include_str!("./static/form.hbs"))?;
+    hb.register_partial("care", include_str!("./static/care.hbs"))?;
+    hb.register_partial("sparkline", include_str!("./static/sparkline.hbs"))?;
     hb.register_template_string("new", include_str!("./static/new.hbs"))?;
     hb.register_template_string("edit", include_str!("./static/edit.hbs"))?;
+    hb.register_template_string("item", include_str!("./static/item.hbs"))?;
+    hb.register_template_string("history", include_str!("./static/history.hbs"))?;
+    hb.register_template_string("edits", include_str!("./static/edits.hbs"))?;
+    hb.register_template_string("tag", include_str!("./static/tag.hbs"))?;
+    hb.register_template_string("outdated", include_str!("./static/outdated.hbs"))?;
+    hb.register_template_string("deadlines", include_str!("./static/deadlines.hbs"))?;
+    hb.register_template_string("lifespan", include_str!("./static/lifespan.hbs"))?;
+    hb.register_template_string("measurements", include_str!("./static/measurements.hbs"))?;
+    hb.register_template_string("settings", include_str!("./static/settings.hbs"))?;
+    hb.register_template_string("import", include_str!("./static/import.hbs"))?;
+    hb.register_template_string("laundry", include_str!("./static/laundry.hbs"))?;
+    hb.register_template_string("laundry-plan", include_str!("./static/laundry-plan.hbs"))?;
+    hb.register_template_string("calendar", include_str!("./static/calendar.hbs"))?;
+    hb.register_template_string("outfits", include_str!("./static/outfits.hbs"))?;
+    hb.register_template_string("outfit", include_str!("./static/outfit.hbs"))?;
+    hb.register_template_string("trips", include_str!("./static/trips.hbs"))?;
+    hb.register_template_string("trip", include_str!("./static/trip.hbs"))?;
+    hb.register_template_string("suggest", include_str!("./static/suggest.hbs"))?;
+    hb.register_template_string("todo", include_str!("./static/todo.hbs"))?;
+    hb.register_template_string("spending", include_str!("./static/spending.hbs"))?;
+    hb.register_template_string("changes", include_str!("./static/changes.hbs"))?;
+    hb.register_template_string("declutter", include_str!("./static/declutter.hbs"))?;
+    hb.register_template_string("admin", include_str!("./static/admin.hbs"))?;
+    hb.register_template_string("trash", include_str!("./static/trash.hbs"))?;
+    hb.register_template_string("wishlist", include_str!("./static/wishlist.hbs"))?;
+    hb.register_template_string("quick", include_str!("./static/quick.hbs"))?;
+    hb.register_template_string("welcome", include_str!("./static/welcome.hbs"))?;
 
     Ok(hb)
 }
+
+/// Every page rendered from a known context and compared with the HTML it gave before, so that a
+/// template and what its handler passes it can't drift apart unnoticed. The item list and form
+/// are built from their typed contexts; the other pages from the JSON kept next to their HTML in
+/// `src/golden`. After a deliberate change, run the tests with `UPDATE_GOLDEN=1` to rewrite the
+/// HTML, and check the diff.
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            care, colors, condition, context, custom, measurements,
+            settings::Units,
+            weather::{Season, Warmth},
+            Item, Nulls, SortItems, Tracking,
+        },
+        chrono::{NaiveDate, TimeZone, Utc},
+        std::{env, fs, path::PathBuf},
+    };
+
+    const PAGES: &[&str] = &[
+        "new",
+        "item",
+        "history",
+        "edits",
+        "tag",
+        "outdated",
+        "deadlines",
+        "lifespan",
+        "measurements",
+        "settings",
+        "import",
+        "laundry",
+        "laundry-plan",
+        "outfits",
+        "outfit",
+        "calendar",
+        "trips",
+        "trip",
+        "suggest",
+        "todo",
+        "spending",
+        "changes",
+        "declutter",
+        "admin",
+        "trash",
+        "wishlist",
+        "quick",
+        "welcome",
+    ];
+
+    fn golden_path(file: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/golden")
+            .join(file)
+    }
+
+    /// Render a template and compare it with the HTML in its golden file.
+    fn check(hb: &Handlebars, name: &str, golden: &str, context: &impl Serialize) {
+        let rendered = hb
+            .render(name, context)
+            .unwrap_or_else(|e| panic!("{} did not render: {}", golden, e));
+        let path = golden_path(&format!("{}.html", golden));
+
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &rendered).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("could not read {}: {}", path.display(), e));
+        assert!(
+            rendered == expected,
+            "{} no longer renders as {}; if that's intended, run with UPDATE_GOLDEN=1",
+            golden,
+            path.display()
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let hb = init().unwrap();
+        for name in PAGES {
+            let path = golden_path(&format!("{}.json", name));
+            let context: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            check(&hb, name, name, &context);
+        }
+    }
+
+    #[test]
+    fn index() {
+        let hb = init().unwrap();
+        let worn = Utc.ymd(2026, 10, 16).and_hms(8, 30, 0);
+        let items = vec![
+            context::IndexItem {
+                key: 1,
+                name: "Blue shirt".into(),
+                description: "Oxford cloth".into(),
+                count: 2,
+                total_count: 14,
+                has_wear: true,
+                wear: Some(worn),
+                wear_fmt: Some("Oct 16, 2026 8:30 AM".into()),
+                has_wash: true,
+                wash: Some(worn - chrono::Duration::days(3)),
+                wash_fmt: Some("Oct 13, 2026 8:30 AM".into()),
+                color: "#2244aa".into(),
+                color_param: "%232244aa".into(),
+                tags: "top, work".into(),
+                tag_links: vec![
+                    context::TagLink {
+                        name: "top".into(),
+                        param: "top".into(),
+                    },
+                    context::TagLink {
+                        name: "work".into(),
+                        param: "work".into(),
+                    },
+                ],
+                weekly_wears: Some(vec![0, 1, 3, 2, 0, 0, 1, 2]),
+                worn_today: Some(2),
+                has_photo: true,
+                cost_per_wear: Some("$2.86".into()),
+                condition: Some(condition::Condition::Good),
+                condition_label: Some("Good"),
+                ..Default::default()
+            },
+            context::IndexItem {
+                key: 3,
+                name: "White crew socks".into(),
+                count: 11,
+                total_count: 40,
+                color: "#ffffff".into(),
+                color_param: "%23ffffff".into(),
+                quantity: Some(8),
+                wears_each: 2,
+                needs_wash: true,
+                ..Default::default()
+            },
+            context::IndexItem {
+                key: 2,
+                name: "Jeans".into(),
+                color: "#000044".into(),
+                color_param: "%23000044".into(),
+                tags: "bottom".into(),
+                tag_links: vec![context::TagLink {
+                    name: "bottom".into(),
+                    param: "bottom".into(),
+                }],
+                ..Default::default()
+            },
+        ];
+
+        let everything = context::Index {
+            items,
+            num_items: 3,
+            color_families: colors::FAMILIES,
+            nulls_links: vec!["/?nulls=first".into(), "/?nulls=last".into()],
+            page: 1,
+            pages: 1,
+            form_key: "5f1c0de7a2b94e6d8c3a1f00".into(),
+            saved_views: vec![context::ViewLink {
+                id: 1,
+                name: "Work".into(),
+                link: "/?tag=work".into(),
+                current: false,
+            }],
+            updated_at: Some(worn),
+            updated_fmt: Some("Oct 16, 2026 8:30 AM".into()),
+            ..Default::default()
+        };
+        check(&hb, "index", "index", &everything);
+
+        // a filtered, sorted listing partway through, as a saved view
+        let filtered = context::Index {
+            num_items: 120,
+            tag: Some("work".into()),
+            tag_param: Some("work".into()),
+            filter: Some("needs:wash".into()),
+            filter_param: Some("needs%3Awash".into()),
+            filtered: true,
+            sort: Some(SortItems::Wear),
+            then_by: Some(",name".into()),
+            descending: Some(true),
+            nulls: Some(Nulls::First),
+            nulls_first: true,
+            page: 2,
+            pages: 3,
+            prev: Some("/?tag=work&page=1".into()),
+            next: Some("/?tag=work&page=3".into()),
+            current_view: Some(context::CurrentView {
+                id: 1,
+                name: "Work".into(),
+                alert: true,
+            }),
+            current_query: "tag=work".into(),
+            ..everything
+        };
+        check(&hb, "index", "index-filtered", &filtered);
+    }
+
+    #[test]
+    fn edit() {
+        let hb = init().unwrap();
+        let item = Item {
+            id: 1,
+            name: "Blue shirt".into(),
+            description: "Oxford cloth".into(),
+            color: "#2244aa".into(),
+            tags: vec!["top".into(), "work".into()],
+            retailer: "Uniqlo".into(),
+            care_wash: "30".into(),
+            care_iron: "medium".into(),
+            chest: Some(104.),
+            wash_after: Some(3),
+            quantity: 1,
+            purchased_on: Some(NaiveDate::from_ymd(2026, 1, 10)),
+            price: Some(40.),
+            ..Default::default()
+        };
+        let fields = vec![custom::Field {
+            id: 1,
+            name: "Thrifted".into(),
+            kind: custom::Kind::YesNo,
+        }];
+
+        let context = context::Edit {
+            edit: true,
+            key: item.id,
+            care_options: care::form_options(&item),
+            condition_options: condition::form_options(&item),
+            tracking_options: Tracking::form_options(item.tracking),
+            warmth_options: Warmth::form_options(item.warmth),
+            season_options: Season::form_options(&item.seasons),
+            suggested_condition: Some("New"),
+            units: Units::Cm.label(),
+            measurement_fields: measurements::form_fields(&item, Units::Cm),
+            default_currency: "USD".into(),
+            custom_fields: custom::form_fields(&fields, &[(1, "yes".into())]),
+            tags: item.tags.join(", "),
+            name: item.name,
+            description: item.description,
+            color: item.color,
+            retailer: item.retailer,
+            wash_after: item.wash_after,
+            quantity: item.quantity,
+            purchased_on: item.purchased_on,
+            price: item.price,
+            ..Default::default()
+        };
+        check(&hb, "edit", "edit", &context);
+    }
+}
@@ -0,0 +1,202 @@
+//! Smaller copies of photos, so pages showing several items don't load every original. They're
+//! made when a photo is uploaded, and kept next to it in the same store under a derived name.
+//! Anything missing (like photos from before thumbnails existed) is made the first time it's
+//! asked for.
+
+use {
+    super::{db::Attachment, media::Media},
+    image::{io::Reader, DynamicImage, GenericImageView, ImageOutputFormat},
+    serde::Deserialize,
+    std::io::Cursor,
+};
+
+/// Most pixels a photo can have to be scaled. Decoding takes a few bytes for each pixel, and a
+/// small file can claim to be enormous.
+const MAX_PIXELS: u64 = 50_000_000;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Size {
+    Thumb,
+    Medium,
+}
+
+impl Size {
+    const ALL: [Size; 2] = [Size::Thumb, Size::Medium];
+
+    /// The longest side, in pixels.
+    fn pixels(self) -> u32 {
+        match self {
+            Size::Thumb => 128,
+            Size::Medium => 512,
+        }
+    }
+}
+
+/// Photos stay JPEG; anything else becomes PNG, which keeps transparency.
+pub(crate) fn content_type(original: &str) -> &'static str {
+    match original {
+        "image/jpeg" => "image/jpeg",
+        _ => "image/png",
+    }
+}
+
+fn name(stored_as: &str, size: Size, original_type: &str) -> String {
+    let stem = stored_as
+        .rsplit_once('.')
+        .map_or(stored_as, |(stem, _)| stem);
+    let extension = match content_type(original_type) {
+        "image/jpeg" => "jpg",
+        _ => "png",
+    };
+    format!("{}.{}px.{}", stem, size.pixels(), extension)
+}
+
+/// Decode a photo, once its header shows it isn't too big to.
+fn decode(data: &[u8]) -> anyhow::Result<DynamicImage> {
+    let reader = || Reader::new(Cursor::new(data)).with_guessed_format();
+    let (width, height) = reader()?.into_dimensions()?;
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        anyhow::bail!("{}x{} is more than {} pixels", width, height, MAX_PIXELS);
+    }
+    Ok(reader()?.decode()?)
+}
+
+/// Scale an image down so its longest side fits, keeping its proportions. Smaller images are
+/// left at their own size.
+fn render(image: &DynamicImage, size: Size, original_type: &str) -> image::ImageResult<Vec<u8>> {
+    let pixels = size.pixels();
+    let format = match content_type(original_type) {
+        "image/jpeg" => ImageOutputFormat::Jpeg(85),
+        _ => ImageOutputFormat::Png,
+    };
+    let mut out = Vec::new();
+    if image.width() > pixels || image.height() > pixels {
+        image.thumbnail(pixels, pixels).write_to(&mut out, format)?;
+    } else {
+        image.write_to(&mut out, format)?;
+    }
+    Ok(out)
+}
+
+/// Make and store thumbnails of a photo in the given sizes, decoding it only once.
+async fn make(
+    media: &Media,
+    stored_as: &str,
+    original_type: &str,
+    data: Vec<u8>,
+    sizes: Vec<Size>,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let content_type = original_type.to_string();
+    // decoding and scaling take a while for big photos, so keep them off the request threads
+    let thumbnails = tokio::task::spawn_blocking(move || {
+        let image = decode(&data)?;
+        sizes
+            .into_iter()
+            .map(|size| Ok((size, render(&image, size, &content_type)?)))
+            .collect::<anyhow::Result<Vec<_>>>()
+    })
+    .await??;
+    for (size, thumbnail) in &thumbnails {
+        media
+            .write(&name(stored_as, *size, original_type), thumbnail)
+            .await?;
+    }
+    Ok(thumbnails.into_iter().map(|(_, t)| t).collect())
+}
+
+/// Make every size of thumbnail for a newly stored photo.
+pub(crate) async fn create(
+    media: &Media,
+    stored_as: &str,
+    original_type: &str,
+    data: &[u8],
+) -> anyhow::Result<()> {
+    make(
+        media,
+        stored_as,
+        original_type,
+        data.to_vec(),
+        Size::ALL.to_vec(),
+    )
+    .await
+    .map(drop)
+}
+
+/// A photo's thumbnail, made now if it hasn't been already.
+pub(crate) async fn fetch(
+    media: &Media,
+    attachment: &Attachment,
+    size: Size,
+) -> anyhow::Result<Vec<u8>> {
+    let stored_as = name(&attachment.stored_as, size, &attachment.content_type);
+    if let Ok(thumbnail) = media.read(&stored_as).await {
+        return Ok(thumbnail);
+    }
+
+    let original = media.read(&attachment.stored_as).await?;
+    let mut made = make(
+        media,
+        &attachment.stored_as,
+        &attachment.content_type,
+        original,
+        vec![size],
+    )
+    .await?;
+    made.pop()
+        .ok_or_else(|| anyhow::anyhow!("No thumbnail was made"))
+}
+
+/// Delete a photo's thumbnails along with it. They may never have been made.
+pub(crate) async fn remove(media: &Media, attachment: &Attachment) {
+    for size in Size::ALL.iter() {
+        media
+            .remove_if_present(&name(
+                &attachment.stored_as,
+                *size,
+                &attachment.content_type,
+            ))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgba8(width, height)
+            .write_to(&mut data, ImageOutputFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn scaled_down_only() {
+        let original = decode(&png(1000, 500)).unwrap();
+
+        let thumb = render(&original, Size::Thumb, "image/png").unwrap();
+        assert_eq!(decode(&thumb).unwrap().dimensions(), (128, 64));
+
+        let small = render(&decode(&thumb).unwrap(), Size::Medium, "image/png").unwrap();
+        assert_eq!(decode(&small).unwrap().dimensions(), (128, 64));
+
+        assert_eq!(
+            name("3-99.png", Size::Medium, "image/png"),
+            "3-99.512px.png"
+        );
+    }
+
+    #[test]
+    fn too_many_pixels() {
+        // the header of a tiny image changed to claim a huge one, with the checksum to match
+        let mut data = png(1, 1);
+        data[16..24].copy_from_slice(&[0, 0, 0x80, 0, 0, 0, 0x80, 0]);
+        data[29..33].copy_from_slice(&[0xc4, 0x7c, 0xa3, 0x7f]);
+
+        let error = decode(&data).unwrap_err().to_string();
+        assert!(error.contains("32768x32768"), "{}", error);
+        assert!(decode(&b"not an image"[..]).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! How long requests take, kept per route so a slow page stands out from the rest: the latest
+//! durations of each are held in memory for the admin page, and any single request over the
+//! budget is logged as it happens.
+
+use {
+    serde::Serialize,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    warp::http::Method,
+};
+
+/// How many of the latest requests to each route are kept.
+const SAMPLES: usize = 500;
+
+/// Routes beyond this many are ignored, in case something keeps requesting made-up paths.
+const MAX_ROUTES: usize = 200;
+
+#[derive(Clone)]
+pub(crate) struct Timings {
+    budget: Duration,
+    routes: Arc<Mutex<HashMap<String, VecDeque<Duration>>>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RouteTiming {
+    pub(crate) route: String,
+    pub(crate) requests: usize,
+    pub(crate) p50_ms: u128,
+    pub(crate) p95_ms: u128,
+    /// Whether the 95th percentile is over the budget
+    pub(crate) slow: bool,
+}
+
+/// A request's route, with ids and tokens in the path replaced by placeholders so that requests
+/// for different items are counted together.
+pub(crate) fn route(method: &Method, path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let segment = if segments.last() == Some(&"t") {
+            ":token"
+        } else if segment.parse::<u64>().is_ok() {
+            ":id"
+        } else {
+            segment
+        };
+        segments.push(segment);
+    }
+    format!("{} /{}", method, segments.join("/"))
+}
+
+/// The duration at or below which `percent` of the sorted durations fall.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    match sorted.len() {
+        0 => Duration::default(),
+        n => sorted[((n * percent).div_ceil(100)).clamp(1, n) - 1],
+    }
+}
+
+impl Timings {
+    pub(crate) fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            routes: Default::default(),
+        }
+    }
+
+    pub(crate) fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Keep how long a request took, returning whether it went over the budget.
+    pub(crate) fn record(&self, route: String, elapsed: Duration) -> bool {
+        let mut routes = self.routes.lock().unwrap();
+        if routes.len() < MAX_ROUTES || routes.contains_key(&route) {
+            let samples = routes.entry(route).or_default();
+            if samples.len() == SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(elapsed);
+        }
+
+        elapsed > self.budget
+    }
+
+    /// Every route seen so far, slowest first.
+    pub(crate) fn summary(&self) -> Vec<RouteTiming> {
+        let routes = self.routes.lock().unwrap();
+        let mut summary = routes
+            .iter()
+            .map(|(route, samples)| {
+                let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+                sorted.sort();
+                let p95 = percentile(&sorted, 95);
+                RouteTiming {
+                    route: route.clone(),
+                    requests: sorted.len(),
+                    p50_ms: percentile(&sorted, 50).as_millis(),
+                    p95_ms: p95.as_millis(),
+                    slow: p95 > self.budget,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        summary.sort_by(|a, b| b.p95_ms.cmp(&a.p95_ms).then(a.route.cmp(&b.route)));
+        summary
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        assert_eq!(route(&Method::GET, "/"), "GET /");
+        assert_eq!(
+            route(&Method::POST, "/item/12/attachments/3/remove"),
+            "POST /item/:id/attachments/:id/remove"
+        );
+        assert_eq!(route(&Method::GET, "/t/4f0a9c21be77"), "GET /t/:token");
+    }
+
+    #[test]
+    fn percentiles() {
+        let timings = Timings::new(Duration::from_millis(50));
+        for ms in 1..=100 {
+            timings.record("GET /".into(), Duration::from_millis(ms));
+        }
+        assert!(!timings.record("GET /item/:id".into(), Duration::from_millis(10)));
+
+        let summary = timings.summary();
+        assert_eq!(
+            summary[0],
+            RouteTiming {
+                route: "GET /".into(),
+                requests: 100,
+                p50_ms: 50,
+                p95_ms: 95,
+                slow: true,
+            }
+        );
+        assert_eq!(summary[1].p95_ms, 10);
+        assert!(!summary[1].slow);
+    }
+}
@@ -0,0 +1,90 @@
+//! Trips and their packing lists. A trip can log a wear of everything packed for it on each of
+//! its days, so a week away doesn't have to be logged item by item when you're back.
+
+use {
+    chrono::{Duration, NaiveDate},
+    serde::Serialize,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Trip {
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    pub(crate) starts_on: NaiveDate,
+    pub(crate) ends_on: NaiveDate,
+    pub(crate) log_wears: bool,
+    /// The last day wears were logged for, if any
+    pub(crate) logged_through: Option<NaiveDate>,
+    pub(crate) items: Vec<TripItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TripItem {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) color: String,
+    pub(crate) packed: bool,
+}
+
+impl Trip {
+    pub(crate) fn packed(&self) -> usize {
+        self.items.iter().filter(|i| i.packed).count()
+    }
+}
+
+/// Days of a trip whose wears are still to be logged: from its start, or the day after the last
+/// one logged, to its end or today, whichever comes first.
+pub(crate) fn days_to_log(
+    starts_on: NaiveDate,
+    ends_on: NaiveDate,
+    logged_through: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Vec<NaiveDate> {
+    let first = match logged_through {
+        Some(day) if day >= starts_on => day + Duration::days(1),
+        _ => starts_on,
+    };
+    let last = ends_on.min(today);
+
+    let mut days = Vec::new();
+    let mut day = first;
+    while day <= last {
+        days.push(day);
+        day += Duration::days(1);
+    }
+    days
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn days_left_to_log() {
+        let day = |d| NaiveDate::from_ymd(2026, 10, d);
+
+        // not started yet
+        assert!(days_to_log(day(20), day(25), None, day(17)).is_empty());
+        // part way through
+        assert_eq!(
+            days_to_log(day(15), day(20), None, day(17)),
+            vec![day(15), day(16), day(17)]
+        );
+        assert_eq!(
+            days_to_log(day(15), day(20), Some(day(16)), day(17)),
+            vec![day(17)]
+        );
+        // over, with the last days still to log
+        assert_eq!(
+            days_to_log(day(10), day(12), Some(day(10)), day(17)),
+            vec![day(11), day(12)]
+        );
+        assert!(days_to_log(day(10), day(12), Some(day(12)), day(17)).is_empty());
+        // logged before the dates were moved later
+        assert_eq!(
+            days_to_log(day(16), day(17), Some(day(12)), day(17)),
+            vec![day(16), day(17)]
+        );
+    }
+}
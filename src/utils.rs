@@ -1,19 +1,85 @@
 use std::fmt;
 
 use {
-    serde::{de::Visitor, Deserializer, Serializer},
-    warp::{http::StatusCode, Reply},
+    chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc, Weekday},
+    serde::{
+        de::{DeserializeOwned, Visitor},
+        Deserialize, Deserializer, Serializer,
+    },
+    serde_json::Value,
+    warp::{http::StatusCode, Filter, Rejection, Reply},
 };
 
 pub fn go_home<T>(_: T) -> impl Reply {
     warp::reply::with_header(StatusCode::SEE_OTHER, "Location", "/")
 }
 
+pub fn go_to_item(id: usize) -> impl Reply {
+    warp::reply::with_header(StatusCode::SEE_OTHER, "Location", format!("/item/{}", id))
+}
+
+pub fn go_to(location: String) -> impl Reply {
+    warp::reply::with_header(StatusCode::SEE_OTHER, "Location", location)
+}
+
+/// Escape a value for use in a query string.
+pub fn encode_query(value: &str) -> String {
+    percent_encoding::utf8_percent_encode(value, percent_encoding::NON_ALPHANUMERIC).to_string()
+}
+
 pub fn default_color() -> String {
     "#000000".into()
 }
 
-pub fn join_comma<S: Serializer>(list: &[String], s: S) -> Result<S::Ok, S::Error> {
+pub fn one() -> u32 {
+    1
+}
+
+pub fn parse_time(s: &str) -> chrono::ParseResult<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+}
+
+/// When something logged as happening on `date` happened: now if that's today (or no day was
+/// given), otherwise midday of that day, local time.
+pub fn logged_at(date: Option<NaiveDate>, now: DateTime<Local>) -> DateTime<Utc> {
+    match date {
+        Some(day) if day < now.date().naive_local() => Local
+            .from_local_datetime(&day.and_hms(12, 0, 0))
+            .earliest()
+            .map_or_else(
+                || Utc.from_utc_datetime(&day.and_hms(12, 0, 0)),
+                |t| t.with_timezone(&Utc),
+            ),
+        _ => now.with_timezone(&Utc),
+    }
+}
+
+/// Check that a day something is being logged for isn't still to come.
+pub fn check_not_future(date: Option<NaiveDate>) -> anyhow::Result<()> {
+    match date {
+        Some(day) if day > Local::today().naive_local() => {
+            anyhow::bail!("Can't log something on {}, which hasn't happened yet", day)
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.parse()
+        .map_err(|_| format!("{} is not a day of the week", s))
+}
+
+/// Loosely check that a string is an absolute http(s) URL, without pulling in a full URL parser.
+pub fn is_web_url(s: &str) -> bool {
+    let rest = s
+        .strip_prefix("https://")
+        .or_else(|| s.strip_prefix("http://"));
+
+    matches!(rest, Some(r) if !r.is_empty() && !r.starts_with('/') && !r.contains(char::is_whitespace))
+}
+
+pub fn join_comma<T: AsRef<str>, S: Serializer>(list: &[T], s: S) -> Result<S::Ok, S::Error> {
+    let list = list.iter().map(AsRef::as_ref).collect::<Vec<_>>();
     s.serialize_str(&list.join(", "))
 }
 
@@ -35,6 +101,99 @@ impl<'de> Visitor<'de> for StringListVisitor {
     }
 }
 
+/// Deserialize an optional form (or JSON) field, treating an empty input as absent.
+pub fn optional<'a, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'a>,
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    let raw = match Option::<Value>::deserialize(d)? {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(s)) => s,
+        Some(other) => other.to_string(),
+    };
+
+    match raw.trim() {
+        "" => Ok(None),
+        s => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Like `optional`, but tells an absent field (`None`) apart from one set to null or left empty
+/// (`Some(None)`). Use with `#[serde(default)]`.
+pub fn nullable<'a, D, T>(d: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'a>,
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    optional(d).map(Some)
+}
+
+/// Like `optional`, for a number of something there's always at least one of: empty or zero
+/// means one.
+pub fn at_least_one<'a, D>(d: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    optional::<D, u32>(d).map(|n| n.unwrap_or(1).max(1))
+}
+
+/// Accept a JSON or form body if there is one, or fall back to defaults if the request is empty.
+pub fn body_or_default<T>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Default + Send + 'static,
+{
+    let empty = warp::header::optional::<u64>("content-length").and_then(
+        |length: Option<u64>| async move {
+            match length {
+                None | Some(0) => Ok(T::default()),
+                Some(_) => Err(warp::reject()),
+            }
+        },
+    );
+
+    warp::body::content_length_limit(1024 * 32)
+        .and(warp::body::json().or(warp::body::form()).unify())
+        .or(empty)
+        .unify()
+}
+
 pub fn split_comma<'a, D: Deserializer<'a>>(d: D) -> Result<Vec<String>, D::Error> {
     d.deserialize_str(StringListVisitor)
 }
+
+/// Deserialize a comma-separated list of values that could each be deserialized from a string,
+/// like unit enum variants. Empty entries are skipped.
+pub fn comma_separated<'a, D, T>(d: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'a>,
+    T: DeserializeOwned,
+{
+    split_comma(d)?
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .map(|s| serde_json::from_value(Value::String(s)).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backdated() {
+        let now = Local.ymd(2020, 6, 10).and_hms(18, 30, 0);
+
+        assert_eq!(logged_at(None, now), now.with_timezone(&Utc));
+        assert_eq!(
+            logged_at(Some(NaiveDate::from_ymd(2020, 6, 10)), now),
+            now.with_timezone(&Utc)
+        );
+        assert_eq!(
+            logged_at(Some(NaiveDate::from_ymd(2020, 6, 6)), now),
+            Local.ymd(2020, 6, 6).and_hms(12, 0, 0).with_timezone(&Utc)
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Dressing for the weather. Items can be rated for how warm they are and the seasons they're
+//! worn in, and given a day's forecast, the ones that suit it are suggested, least recently worn
+//! first. Forecasts come from OpenWeatherMap when there's an API key for it; without one, the
+//! temperature can be given by hand.
+
+use {
+    super::{http, Item},
+    chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc},
+    hyper::{Body, Request, Uri},
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::{
+        collections::BTreeMap,
+        fmt,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Items suggested from each category.
+const PER_CATEGORY: usize = 3;
+
+/// How long a forecast is used before asking for a fresh one.
+const FORECAST_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Warmth {
+    Light,
+    Medium,
+    Warm,
+}
+
+impl Warmth {
+    pub(crate) const ALL: [Self; 3] = [Self::Light, Self::Medium, Self::Warm];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Medium => "medium",
+            Self::Warm => "warm",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Light => "Light, for warm days",
+            Self::Medium => "Medium",
+            Self::Warm => "Warm, for cold days",
+        }
+    }
+
+    /// What suits a temperature, in °C.
+    fn for_temperature(celsius: f64) -> Self {
+        match celsius {
+            t if t >= 20. => Self::Light,
+            t if t >= 10. => Self::Medium,
+            _ => Self::Warm,
+        }
+    }
+
+    /// Choices for the item form, with `current` selected.
+    pub(crate) fn form_options(current: Option<Self>) -> serde_json::Value {
+        Self::ALL
+            .iter()
+            .map(|w| {
+                json!({ "value": w.as_str(), "label": w.label(), "selected": Some(*w) == current })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Warmth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Warmth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|w| w.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown warmth: {}", s))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub(crate) const ALL: [Self; 4] = [Self::Spring, Self::Summer, Self::Autumn, Self::Winter];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Spring => "spring",
+            Self::Summer => "summer",
+            Self::Autumn => "autumn",
+            Self::Winter => "winter",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Spring => "Spring",
+            Self::Summer => "Summer",
+            Self::Autumn => "Autumn",
+            Self::Winter => "Winter",
+        }
+    }
+
+    /// The season a day falls in, by whole months, turned around south of the equator.
+    pub(crate) fn on(date: NaiveDate, southern: bool) -> Self {
+        let month = match southern {
+            true => (date.month() + 5) % 12 + 1,
+            false => date.month(),
+        };
+        match month {
+            3..=5 => Self::Spring,
+            6..=8 => Self::Summer,
+            9..=11 => Self::Autumn,
+            _ => Self::Winter,
+        }
+    }
+
+    /// Checkboxes for the item form, with the item's own seasons checked.
+    pub(crate) fn form_options(seasons: &[Self]) -> serde_json::Value {
+        Self::ALL
+            .iter()
+            .map(|s| {
+                json!({ "value": s.as_str(), "label": s.label(), "selected": seasons.contains(s) })
+            })
+            .collect()
+    }
+}
+
+impl AsRef<str> for Season {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl FromStr for Season {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|season| season.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown season: {}", s))
+    }
+}
+
+/// Fold the form's season checkboxes, one field each, into the single comma-separated `seasons`
+/// field an item is read from.
+pub(crate) fn join_seasons(fields: &mut Vec<(String, String)>) {
+    let seasons = fields
+        .iter()
+        .filter(|(k, _)| k == "season")
+        .map(|(_, v)| v.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    fields.retain(|(k, _)| k != "season" && k != "seasons");
+    fields.push(("seasons".into(), seasons));
+}
+
+/// Where forecasts are for, as `LATITUDE,LONGITUDE` in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Location {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fail = || format!("{} is not a latitude and longitude, like 51.5,-0.12", s);
+        let (latitude, longitude) = s.split_once(',').ok_or_else(fail)?;
+        let (latitude, longitude) = (
+            latitude.trim().parse::<f64>().map_err(|_| fail())?,
+            longitude.trim().parse::<f64>().map_err(|_| fail())?,
+        );
+        if latitude.abs() > 90. || longitude.abs() > 180. {
+            return Err(fail());
+        }
+        Ok(Self {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+/// A day's weather, in °C.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Forecast {
+    pub(crate) low: f64,
+    pub(crate) high: f64,
+    pub(crate) summary: String,
+}
+
+impl Forecast {
+    /// A forecast of one temperature all day, as given by hand.
+    pub(crate) fn constant(celsius: f64) -> Self {
+        Self {
+            low: celsius,
+            high: celsius,
+            summary: String::new(),
+        }
+    }
+
+    /// Warmths that suit some part of the day, from the warmest part to the coldest.
+    pub(crate) fn warmths(&self) -> Vec<Warmth> {
+        let (lightest, warmest) = (
+            Warmth::for_temperature(self.high),
+            Warmth::for_temperature(self.low),
+        );
+        Warmth::ALL
+            .iter()
+            .copied()
+            .filter(|w| *w >= lightest && *w <= warmest)
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Pick {
+    pub(crate) key: usize,
+    pub(crate) name: String,
+    pub(crate) color: String,
+    pub(crate) warmth: Option<&'static str>,
+    pub(crate) last_wear: Option<DateTime<Utc>>,
+    /// The last wear as the settings show times, filled in by the page
+    pub(crate) last_wear_fmt: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Group {
+    /// The items' first tag; empty for items without one
+    pub(crate) category: String,
+    pub(crate) items: Vec<Pick>,
+}
+
+/// Items that suit the season and, with a forecast, the day's temperatures, a few from each
+/// category, least recently worn first. Items due a wash are left out, and items without a warmth
+/// or seasons are taken to suit any weather.
+pub(crate) fn suggest<'a>(
+    items: impl Iterator<Item = &'a Item>,
+    forecast: Option<&Forecast>,
+    season: Season,
+) -> Vec<Group> {
+    let warmths = forecast.map(Forecast::warmths);
+    let mut suitable = items
+        .filter(|i| !i.needs_wash())
+        .filter(|i| match (&warmths, i.warmth) {
+            (Some(warmths), Some(warmth)) => warmths.contains(&warmth),
+            _ => true,
+        })
+        .filter(|i| i.seasons.is_empty() || i.seasons.contains(&season))
+        .collect::<Vec<_>>();
+    // never worn comes first, then the longest ago
+    suitable.sort_by(|a, b| a.last_wear.cmp(&b.last_wear).then(a.name.cmp(&b.name)));
+
+    let mut groups = BTreeMap::<String, Vec<Pick>>::new();
+    for item in suitable {
+        let category = item
+            .tags
+            .first()
+            .map(|t| t.trim().to_lowercase())
+            .unwrap_or_default();
+        let picks = groups.entry(category).or_default();
+        if picks.len() < PER_CATEGORY {
+            picks.push(Pick {
+                key: item.id,
+                name: item.name.clone(),
+                color: item.color.clone(),
+                warmth: item.warmth.map(Warmth::label),
+                last_wear: item.last_wear,
+                last_wear_fmt: None,
+            });
+        }
+    }
+
+    // uncategorized items sort first by name, but belong at the end
+    let mut groups = groups
+        .into_iter()
+        .map(|(category, items)| Group { category, items })
+        .collect::<Vec<_>>();
+    groups.sort_by_key(|g| g.category.is_empty());
+    groups
+}
+
+#[derive(Deserialize)]
+struct ForecastResponse {
+    list: Vec<ForecastEntry>,
+    city: City,
+}
+
+#[derive(Deserialize)]
+struct City {
+    /// Offset from UTC, in seconds
+    timezone: i64,
+}
+
+#[derive(Deserialize)]
+struct ForecastEntry {
+    /// Start of the three hours it covers, as a Unix timestamp
+    dt: i64,
+    main: Temperatures,
+    #[serde(default)]
+    weather: Vec<Conditions>,
+}
+
+#[derive(Deserialize)]
+struct Temperatures {
+    temp_min: f64,
+    temp_max: f64,
+}
+
+#[derive(Deserialize)]
+struct Conditions {
+    description: String,
+}
+
+/// Today's weather from a five-day forecast in three-hour steps: the entries for today where
+/// the forecast is, or late at night when today has none left, the next day's worth.
+fn summarize(response: ForecastResponse, today: NaiveDate) -> Option<Forecast> {
+    let local = |entry: &ForecastEntry| {
+        chrono::NaiveDateTime::from_timestamp_opt(entry.dt + response.city.timezone, 0)
+    };
+    let mut entries = response
+        .list
+        .iter()
+        .filter(|e| local(e).is_some_and(|t| t.date() == today))
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        entries = response.list.iter().take(8).collect();
+    }
+
+    let low = entries.iter().map(|e| e.main.temp_min).reduce(f64::min)?;
+    let high = entries.iter().map(|e| e.main.temp_max).reduce(f64::max)?;
+    // described as it will be around the middle of the day
+    let summary = entries
+        .iter()
+        .min_by_key(|e| local(e).map_or(24, |t| (t.hour() as i64 - 13).abs()))
+        .and_then(|e| e.weather.first())
+        .map(|c| c.description.clone())
+        .unwrap_or_default();
+
+    Some(Forecast { low, high, summary })
+}
+
+/// Forecasts from OpenWeatherMap, if there's an API key, and where they're for, which also says
+/// which half of the year is summer. Forecasts are kept for an hour.
+#[derive(Clone, Default)]
+pub(crate) struct Weather {
+    api_key: Option<String>,
+    location: Option<Location>,
+    cached: Arc<Mutex<Option<(Instant, NaiveDate, Forecast)>>>,
+}
+
+impl Weather {
+    pub(crate) fn new(api_key: Option<String>, location: Option<Location>) -> anyhow::Result<Self> {
+        if api_key.is_some() && location.is_none() {
+            anyhow::bail!("A location is needed to get forecasts for");
+        }
+        Ok(Self {
+            api_key,
+            location,
+            cached: Arc::default(),
+        })
+    }
+
+    pub(crate) fn configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    pub(crate) fn season(&self, date: NaiveDate) -> Season {
+        Season::on(date, self.location.is_some_and(|l| l.latitude < 0.))
+    }
+
+    /// Today's forecast, or nothing without an API key.
+    pub(crate) async fn today(&self, today: NaiveDate) -> anyhow::Result<Option<Forecast>> {
+        let (api_key, location) = match (&self.api_key, self.location) {
+            (Some(key), Some(location)) => (key, location),
+            _ => return Ok(None),
+        };
+
+        if let Ok(cached) = self.cached.lock() {
+            if let Some((at, day, forecast)) = &*cached {
+                if *day == today && at.elapsed() < FORECAST_LIFETIME {
+                    return Ok(Some(forecast.clone()));
+                }
+            }
+        }
+
+        let uri = format!(
+            "https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&units=metric&appid={}",
+            location.latitude,
+            location.longitude,
+            crate::utils::encode_query(api_key)
+        )
+        .parse::<Uri>()?;
+        let request = Request::get(uri.clone())
+            .header("Host", uri.host().unwrap_or_default())
+            .body(Body::empty())?;
+        let (status, body) = http::send(&uri, request).await?;
+        if !status.is_success() {
+            anyhow::bail!(
+                "Weather provider responded {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
+        }
+
+        let forecast = summarize(serde_json::from_slice(&body)?, today)
+            .ok_or_else(|| anyhow::anyhow!("Weather provider sent an empty forecast"))?;
+        if let Ok(mut cached) = self.cached.lock() {
+            *cached = Some((Instant::now(), today, forecast.clone()));
+        }
+        Ok(Some(forecast))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, chrono::TimeZone};
+
+    fn item(id: usize, tag: &str, warmth: Option<Warmth>, seasons: &[Season]) -> Item {
+        Item {
+            id,
+            name: format!("item {}", id),
+            tags: vec![tag.into()],
+            warmth,
+            seasons: seasons.to_vec(),
+            quantity: 1,
+            ..Item::default()
+        }
+    }
+
+    #[test]
+    fn suggested_for_the_weather() {
+        let worn = |day| Some(Utc.ymd(2026, 10, day).and_hms(8, 0, 0));
+        let items = [
+            Item {
+                last_wear: worn(16),
+                ..item(1, "top", Some(Warmth::Light), &[])
+            },
+            Item {
+                last_wear: worn(2),
+                ..item(2, "top", Some(Warmth::Medium), &[])
+            },
+            item(3, "top", None, &[Season::Autumn, Season::Winter]),
+            item(4, "coat", Some(Warmth::Warm), &[]),
+            item(5, "", None, &[]),
+            item(6, "top", None, &[Season::Summer]),
+            Item {
+                count: 2,
+                wash_after: Some(2),
+                ..item(7, "top", None, &[])
+            },
+        ];
+        let keys = |groups: Vec<Group>| {
+            groups
+                .into_iter()
+                .map(|g| (g.category, g.items.iter().map(|p| p.key).collect()))
+                .collect::<Vec<(String, Vec<usize>)>>()
+        };
+
+        let mild = Forecast {
+            low: 12.,
+            high: 21.,
+            summary: "light rain".into(),
+        };
+        assert_eq!(mild.warmths(), vec![Warmth::Light, Warmth::Medium]);
+        assert_eq!(
+            keys(suggest(items.iter(), Some(&mild), Season::Autumn)),
+            vec![("top".into(), vec![3, 2, 1]), (String::new(), vec![5]),]
+        );
+        assert_eq!(
+            keys(suggest(
+                items.iter(),
+                Some(&Forecast::constant(-2.)),
+                Season::Winter
+            )),
+            vec![
+                ("coat".into(), vec![4]),
+                ("top".into(), vec![3]),
+                (String::new(), vec![5]),
+            ]
+        );
+        assert_eq!(
+            keys(suggest(items.iter(), None, Season::Summer)),
+            vec![
+                ("coat".into(), vec![4]),
+                ("top".into(), vec![6, 2, 1]),
+                (String::new(), vec![5]),
+            ]
+        );
+
+        let day = |m| NaiveDate::from_ymd(2026, m, 17);
+        assert_eq!(Season::on(day(10), false), Season::Autumn);
+        assert_eq!(Season::on(day(10), true), Season::Spring);
+        assert_eq!(Season::on(day(1), true), Season::Summer);
+        assert_eq!(Season::on(day(12), false), Season::Winter);
+    }
+
+    #[test]
+    fn todays_forecast() {
+        // 2026-10-17T00:00:00Z, in a timezone two hours ahead
+        let midnight = 1_792_195_200 - 2 * 60 * 60;
+        let entry = |hours: i64, low: f64, high: f64, description: &str| {
+            json!({
+                "dt": midnight + hours * 60 * 60,
+                "main": { "temp": (low + high) / 2., "temp_min": low, "temp_max": high },
+                "weather": [{ "description": description }],
+            })
+        };
+        let response = |list| {
+            serde_json::from_value::<ForecastResponse>(json!({
+                "list": list,
+                "city": { "name": "Somewhere", "timezone": 7200 },
+            }))
+            .unwrap()
+        };
+        let list = vec![
+            entry(6, 7., 9., "mist"),
+            entry(12, 14., 16., "few clouds"),
+            entry(18, 11., 12., "light rain"),
+            entry(24, 8., 9., "clear sky"),
+        ];
+
+        assert_eq!(
+            summarize(response(list.clone()), NaiveDate::from_ymd(2026, 10, 17)),
+            Some(Forecast {
+                low: 7.,
+                high: 16.,
+                summary: "few clouds".into(),
+            })
+        );
+        // nothing left for today, so the next day's worth
+        assert_eq!(
+            summarize(response(list), NaiveDate::from_ymd(2026, 10, 16)).map(|f| (f.low, f.high)),
+            Some((7., 16.))
+        );
+        assert_eq!(
+            summarize(response(Vec::new()), NaiveDate::from_ymd(2026, 10, 17)),
+            None
+        );
+
+        assert_eq!(
+            "-33.9, 151.2".parse::<Location>(),
+            Ok(Location {
+                latitude: -33.9,
+                longitude: 151.2,
+            })
+        );
+        assert!("91,0".parse::<Location>().is_err());
+        assert!("London".parse::<Location>().is_err());
+    }
+}